@@ -0,0 +1,91 @@
+//! Loading a large task list, with the tags of every task fetched in one
+//! batch against the old way of one tag query per task.
+//!
+//! Run with `cargo bench --bench task_loading`.
+
+use std::time::{Duration, Instant};
+use pluto_todo_lib::database::{Database, TaskRepository};
+use pluto_todo_lib::models::{CreateTaskRequest, Task};
+use rusqlite::{params, Connection};
+
+const TASKS: usize = 5_000;
+const RUNS: usize = 10;
+
+fn main() {
+    // On disk, like the app's own database
+    let path = std::env::temp_dir().join(format!("pluto-todo-bench-{}.db", std::process::id()));
+    let db = Database::new(&path).expect("Failed to open database");
+    seed(&db.connection);
+
+    let task_repo = TaskRepository::new(&db.connection);
+    let batched = median(|| {
+        let tasks = task_repo.get_all(None).expect("Failed to load tasks");
+        assert_eq!(tasks.len(), TASKS);
+    });
+    let per_task = median(|| {
+        let tasks = load_tags_per_task(&db.connection);
+        assert_eq!(tasks, TASKS);
+    });
+
+    println!("Loading {} tasks with 3 tags each, median of {} runs", TASKS, RUNS);
+    println!("  one tag query per task: {:>8.2} ms", per_task.as_secs_f64() * 1000.0);
+    println!("  tags in one batch:      {:>8.2} ms", batched.as_secs_f64() * 1000.0);
+    println!("  speedup:                {:>8.1}x", per_task.as_secs_f64() / batched.as_secs_f64());
+
+    drop(db);
+    let _ = std::fs::remove_file(&path);
+}
+
+fn seed(conn: &Connection) {
+    let task_repo = TaskRepository::new(conn);
+    let tx = conn.unchecked_transaction().expect("Failed to start transaction");
+    for i in 0..TASKS {
+        let task = Task::new(CreateTaskRequest {
+            title: format!("Task {}", i),
+            description: Some("Something to do".to_string()),
+            priority: None,
+            due_date: None,
+            category_id: None,
+            tags: Some(vec![format!("area{}", i % 10), format!("day{}", i % 7), "shared".to_string()]),
+            parent_id: None,
+            recurrence: None,
+        })
+        .expect("Failed to build task");
+        task_repo.create(&task).expect("Failed to create task");
+    }
+    tx.commit().expect("Failed to commit");
+}
+
+/// What `TaskRepository` used to do: the task rows, then a statement per
+/// task for its tags. Returns how many tasks were loaded.
+fn load_tags_per_task(conn: &Connection) -> usize {
+    let mut stmt = conn
+        .prepare("SELECT * FROM tasks WHERE deleted_at IS NULL ORDER BY position ASC, created_at DESC")
+        .expect("Failed to prepare");
+    let ids: Vec<String> = stmt
+        .query_map([], |row| row.get(0))
+        .and_then(|rows| rows.collect())
+        .expect("Failed to load tasks");
+
+    for id in &ids {
+        let mut tag_stmt = conn.prepare("SELECT tag FROM task_tags WHERE task_id = ?1").expect("Failed to prepare");
+        let tags: Vec<String> = tag_stmt
+            .query_map(params![id], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .expect("Failed to load tags");
+        assert_eq!(tags.len(), 3);
+    }
+    ids.len()
+}
+
+fn median(mut run: impl FnMut()) -> Duration {
+    let mut times: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[RUNS / 2]
+}
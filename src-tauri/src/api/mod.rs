@@ -0,0 +1,67 @@
+//! Opt-in REST/JSON API on localhost, for scripts, Stream Deck buttons and
+//! bookmarklets. Requests are handled by `routes`, on the same repositories
+//! and database connection as the Tauri commands; `server` runs it in the app.
+
+pub mod openapi;
+pub mod routes;
+pub mod server;
+
+use serde::Serialize;
+
+/// Requests with a larger body are refused
+pub const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// An HTTP request, stripped down to what the routes look at.
+#[derive(Debug, Clone, Default)]
+pub struct ApiRequest {
+    pub method: String,
+    /// Path and query string, e.g. `/api/tasks?completed=false`
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl ApiRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiResponse {
+    pub status: u16,
+    /// JSON, or empty for 204
+    pub body: String,
+    /// Whether the request changed tasks or categories, so the window
+    /// should reload them
+    pub changed: bool,
+}
+
+impl ApiResponse {
+    pub fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        match serde_json::to_string(value) {
+            Ok(body) => ApiResponse { status, body, changed: false },
+            Err(e) => ApiResponse::error(500, &format!("Failed to encode response: {}", e)),
+        }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        ApiResponse {
+            status,
+            body: serde_json::json!({ "error": message }).to_string(),
+            changed: false,
+        }
+    }
+
+    pub fn no_content() -> Self {
+        ApiResponse { status: 204, body: String::new(), changed: false }
+    }
+
+    fn changed(mut self) -> Self {
+        self.changed = true;
+        self
+    }
+}
@@ -0,0 +1,321 @@
+use serde_json::{json, Value};
+use crate::models::DEFAULT_API_PORT;
+
+/// The OpenAPI 3.0 description served at `/api/openapi.json`. Keep it in
+/// step with `routes`.
+pub fn spec() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Pluto ToDo local API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Tasks and categories of the running Pluto app. Only reachable from this computer. \
+                            Send the token shown in the app's settings as `Authorization: Bearer <token>`."
+        },
+        "servers": [{ "url": "http://127.0.0.1:{port}", "variables": { "port": { "default": DEFAULT_API_PORT.to_string() } } }],
+        "security": [{ "bearerAuth": [] }],
+        "paths": {
+            "/api/tasks": {
+                "get": {
+                    "summary": "List tasks",
+                    "description": "Tasks in the trash are never listed.",
+                    "parameters": [
+                        query_param("completed", "boolean", "Only completed (true) or open (false) tasks"),
+                        {
+                            "name": "priority", "in": "query",
+                            "schema": { "type": "string", "enum": ["low", "medium", "high"] }
+                        },
+                        query_param("category_id", "string", "Only tasks in this category"),
+                        query_param("no_category", "boolean", "Only tasks without a category"),
+                        query_param("parent_id", "string", "Only direct subtasks of this task"),
+                        query_param("search", "string", "Full-text search over title, description and tags"),
+                        query_param("due_before", "string", "Due on or before this date (YYYY-MM-DD or RFC 3339)"),
+                        query_param("due_after", "string", "Due on or after this date (YYYY-MM-DD or RFC 3339)"),
+                        query_param("q", "string", "A filter query as typed in the app, e.g. `priority:high #work -done`")
+                    ],
+                    "responses": {
+                        "200": list_response("Task"),
+                        "400": error_response("Invalid filter"),
+                        "401": error_response("Missing or wrong token")
+                    }
+                },
+                "post": {
+                    "summary": "Create a task",
+                    "requestBody": body("CreateTaskRequest"),
+                    "responses": {
+                        "201": object_response("Task"),
+                        "400": error_response("Invalid task")
+                    }
+                }
+            },
+            "/api/tasks/{id}": {
+                "parameters": [id_param()],
+                "get": {
+                    "summary": "Get a task",
+                    "responses": { "200": object_response("Task"), "404": error_response("No such task") }
+                },
+                "patch": {
+                    "summary": "Change a task",
+                    "description": "Only the fields given are changed. An empty `due_date` clears the due date and an empty `recurrence` stops the task repeating.",
+                    "requestBody": body("UpdateTaskRequest"),
+                    "responses": {
+                        "200": object_response("Task"),
+                        "400": error_response("Invalid change"),
+                        "404": error_response("No such task")
+                    }
+                },
+                "delete": {
+                    "summary": "Move a task to the trash",
+                    "parameters": [{
+                        "name": "subtasks", "in": "query",
+                        "description": "`delete` trashes the subtasks too; `promote` (the default) moves them up a level",
+                        "schema": { "type": "string", "enum": ["promote", "delete"] }
+                    }],
+                    "responses": { "204": { "description": "Deleted" }, "404": error_response("No such task") }
+                }
+            },
+            "/api/tasks/{id}/toggle": {
+                "parameters": [id_param()],
+                "post": {
+                    "summary": "Complete or reopen a task",
+                    "description": "Completing a recurring task creates its next occurrence. Completing a task that depends on open tasks is allowed, with a warning.",
+                    "responses": { "200": object_response("ToggleCompletionResult"), "404": error_response("No such task") }
+                }
+            },
+            "/api/tasks/{id}/subtasks": {
+                "parameters": [id_param()],
+                "get": {
+                    "summary": "Direct subtasks of a task",
+                    "responses": { "200": list_response("Task"), "404": error_response("No such task") }
+                }
+            },
+            "/api/tasks/{id}/hierarchy": {
+                "parameters": [id_param()],
+                "get": {
+                    "summary": "A task followed by all of its subtasks, at any depth",
+                    "responses": { "200": list_response("Task"), "404": error_response("No such task") }
+                }
+            },
+            "/api/tasks/{id}/progress": {
+                "parameters": [id_param()],
+                "get": {
+                    "summary": "How many of a task's subtasks are done",
+                    "responses": { "200": object_response("TaskProgress"), "404": error_response("No such task") }
+                }
+            },
+            "/api/search": {
+                "get": {
+                    "summary": "Full-text search, best matches first",
+                    "parameters": [
+                        { "name": "q", "in": "query", "required": true, "schema": { "type": "string" } },
+                        { "name": "limit", "in": "query", "schema": { "type": "integer", "maximum": 100, "default": 100 } }
+                    ],
+                    "responses": {
+                        "200": list_response("TaskSearchResult"),
+                        "400": error_response("A search made only of exclusions, e.g. `-draft`")
+                    }
+                }
+            },
+            "/api/categories": {
+                "get": {
+                    "summary": "List categories",
+                    "responses": { "200": list_response("Category") }
+                },
+                "post": {
+                    "summary": "Create a category",
+                    "requestBody": body("CreateCategoryRequest"),
+                    "responses": { "201": object_response("Category"), "400": error_response("Invalid category") }
+                }
+            },
+            "/api/categories/{id}": {
+                "parameters": [id_param()],
+                "get": {
+                    "summary": "Get a category",
+                    "responses": { "200": object_response("Category"), "404": error_response("No such category") }
+                },
+                "patch": {
+                    "summary": "Change a category",
+                    "requestBody": body("UpdateCategoryRequest"),
+                    "responses": { "200": object_response("Category"), "404": error_response("No such category") }
+                },
+                "delete": {
+                    "summary": "Delete a category",
+                    "description": "Only a category without tasks, including those in the trash, can be deleted.",
+                    "responses": {
+                        "204": { "description": "Deleted" },
+                        "404": error_response("No such category"),
+                        "409": error_response("The category still has tasks")
+                    }
+                }
+            },
+            "/api/openapi.json": {
+                "get": {
+                    "summary": "This description",
+                    "security": [],
+                    "responses": { "200": { "description": "OpenAPI document" } }
+                }
+            }
+        },
+        "components": {
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer" }
+            },
+            "schemas": {
+                "Priority": { "type": "string", "enum": ["Low", "Medium", "High"] },
+                "Task": {
+                    "type": "object",
+                    "required": ["id", "title", "completed", "priority", "tags", "created_at", "updated_at"],
+                    "properties": {
+                        "id": { "type": "string" },
+                        "title": { "type": "string" },
+                        "description": { "type": "string", "nullable": true },
+                        "completed": { "type": "boolean" },
+                        "priority": { "$ref": "#/components/schemas/Priority" },
+                        "due_date": {
+                            "type": "string", "nullable": true,
+                            "description": "UTC, without an offset. A due date without a time is midnight."
+                        },
+                        "category_id": { "type": "string", "nullable": true },
+                        "tags": { "type": "array", "items": { "type": "string" } },
+                        "parent_id": { "type": "string", "nullable": true },
+                        "recurrence": { "type": "string", "nullable": true, "description": "RRULE text, e.g. `FREQ=WEEKLY;BYDAY=MO`" },
+                        "created_at": { "type": "string" },
+                        "updated_at": { "type": "string" },
+                        "deleted_at": { "type": "string", "nullable": true },
+                        "position": { "type": "integer", "description": "Manual order among tasks with the same parent and category" }
+                    }
+                },
+                "CreateTaskRequest": {
+                    "type": "object",
+                    "required": ["title"],
+                    "properties": {
+                        "title": { "type": "string" },
+                        "description": { "type": "string" },
+                        "priority": { "$ref": "#/components/schemas/Priority" },
+                        "due_date": { "type": "string", "description": "YYYY-MM-DD or RFC 3339" },
+                        "category_id": { "type": "string" },
+                        "tags": { "type": "array", "items": { "type": "string" } },
+                        "parent_id": { "type": "string" },
+                        "recurrence": { "type": "string", "description": "RRULE text" }
+                    }
+                },
+                "UpdateTaskRequest": {
+                    "type": "object",
+                    "properties": {
+                        "title": { "type": "string" },
+                        "description": { "type": "string" },
+                        "completed": { "type": "boolean" },
+                        "priority": { "$ref": "#/components/schemas/Priority" },
+                        "due_date": { "type": "string", "description": "YYYY-MM-DD or RFC 3339; empty clears it" },
+                        "category_id": { "type": "string" },
+                        "tags": { "type": "array", "items": { "type": "string" } },
+                        "parent_id": { "type": "string" },
+                        "recurrence": { "type": "string", "description": "RRULE text; empty stops the task repeating" }
+                    }
+                },
+                "ToggleCompletionResult": {
+                    "type": "object",
+                    "required": ["task"],
+                    "properties": {
+                        "task": { "$ref": "#/components/schemas/Task" },
+                        "warning": {
+                            "type": "string", "nullable": true,
+                            "description": "Set when the task was completed while tasks it depends on are still open"
+                        }
+                    }
+                },
+                "TaskProgress": {
+                    "type": "object",
+                    "properties": {
+                        "total_subtasks": { "type": "integer" },
+                        "completed_subtasks": { "type": "integer" },
+                        "progress_percentage": { "type": "number" },
+                        "has_subtasks": { "type": "boolean" }
+                    }
+                },
+                "TaskSearchResult": {
+                    "type": "object",
+                    "properties": {
+                        "task": { "$ref": "#/components/schemas/Task" },
+                        "score": { "type": "number", "description": "Higher is more relevant" },
+                        "title_highlight": { "type": "string", "description": "Matches are wrapped in \\u0002 ... \\u0003" },
+                        "description_snippet": { "type": "string", "nullable": true }
+                    }
+                },
+                "Category": {
+                    "type": "object",
+                    "required": ["id", "name", "color", "created_at"],
+                    "properties": {
+                        "id": { "type": "string" },
+                        "name": { "type": "string" },
+                        "color": { "type": "string" },
+                        "icon": { "type": "string", "nullable": true },
+                        "created_at": { "type": "string" }
+                    }
+                },
+                "CreateCategoryRequest": {
+                    "type": "object",
+                    "required": ["name", "color"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "color": { "type": "string", "example": "#3b82f6" },
+                        "icon": { "type": "string" }
+                    }
+                },
+                "UpdateCategoryRequest": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "color": { "type": "string" },
+                        "icon": { "type": "string" }
+                    }
+                },
+                "Error": {
+                    "type": "object",
+                    "required": ["error"],
+                    "properties": { "error": { "type": "string" } }
+                }
+            }
+        }
+    })
+}
+
+fn query_param(name: &str, kind: &str, description: &str) -> Value {
+    json!({ "name": name, "in": "query", "description": description, "schema": { "type": kind } })
+}
+
+fn id_param() -> Value {
+    json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } })
+}
+
+fn body(schema: &str) -> Value {
+    json!({
+        "required": true,
+        "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", schema) } } }
+    })
+}
+
+fn object_response(schema: &str) -> Value {
+    json!({
+        "description": "OK",
+        "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", schema) } } }
+    })
+}
+
+fn list_response(schema: &str) -> Value {
+    json!({
+        "description": "OK",
+        "content": {
+            "application/json": {
+                "schema": { "type": "array", "items": { "$ref": format!("#/components/schemas/{}", schema) } }
+            }
+        }
+    })
+}
+
+fn error_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Error" } } }
+    })
+}
@@ -0,0 +1,434 @@
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
+use std::sync::Mutex;
+use crate::api::{openapi, ApiRequest, ApiResponse};
+use crate::database::filter_query::FilterQuery;
+use crate::database::search::to_fts_query;
+use crate::database::{CategoryRepository, Database, Journal, TaskRepository};
+use crate::models::{Category, CreateCategoryRequest, CreateTaskRequest, Priority, Task, TaskFilter, ToggleCompletionResult, UpdateCategoryRequest, UpdateTaskRequest};
+use crate::utils::AppError;
+
+/// Host names the API answers to. Anything else is refused, so a web page
+/// cannot reach the API by pointing its own domain at 127.0.0.1.
+const LOCAL_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+/// Most results `/api/search` returns, and the default when no limit is given
+const MAX_SEARCH_RESULTS: usize = 100;
+
+/// A failed request: its status and the message returned as `{"error": ...}`
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        ApiError { status, message: message.into() }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::new(400, message)
+    }
+
+    fn not_found(what: &str) -> Self {
+        ApiError::new(404, format!("{} not found", what))
+    }
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(error: rusqlite::Error) -> Self {
+        ApiError::new(500, format!("Database error: {}", error))
+    }
+}
+
+impl From<AppError> for ApiError {
+    fn from(error: AppError) -> Self {
+        ApiError::new(500, error.to_string())
+    }
+}
+
+type RouteResult = Result<ApiResponse, ApiError>;
+
+/// Answer one request. `token` is the one clients must send as
+/// `Authorization: Bearer <token>`. The database is only locked once the
+/// request has been authenticated, and for as long as it is handled, so the
+/// app and the API never write at the same time.
+pub fn handle(db: &Mutex<Database>, token: &str, request: &ApiRequest) -> ApiResponse {
+    if !is_local_host(request.header("Host")) {
+        return ApiResponse::error(403, "Only requests to localhost are accepted");
+    }
+    // CORS preflight, for bookmarklets; browsers send it without credentials
+    if request.method == "OPTIONS" {
+        return ApiResponse::no_content();
+    }
+
+    let (path, query) = split_url(&request.url);
+    let segments: Vec<String> = path.split('/').filter(|s| !s.is_empty()).map(percent_decode).collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    if request.method == "GET" && segments == ["api", "openapi.json"] {
+        return ApiResponse::json(200, &openapi::spec());
+    }
+    if !is_authorized(request.header("Authorization"), token) {
+        return ApiResponse::error(401, "Missing or wrong API token");
+    }
+
+    let db_lock = match db.lock() {
+        Ok(lock) => lock,
+        Err(e) => return ApiResponse::error(500, &format!("Database lock error: {}", e)),
+    };
+    route(&db_lock.connection, request, &segments, &query).unwrap_or_else(|e| ApiResponse::error(e.status, &e.message))
+}
+
+fn route(conn: &Connection, request: &ApiRequest, segments: &[&str], query: &[(String, String)]) -> RouteResult {
+    let method = request.method.as_str();
+
+    match segments {
+        ["api", "tasks"] => match method {
+            "GET" => list_tasks(conn, query),
+            "POST" => create_task(conn, parse_body(request)?),
+            _ => Err(method_not_allowed()),
+        },
+        ["api", "tasks", id] => match method {
+            "GET" => get_task(conn, id).map(|task| ApiResponse::json(200, &task)),
+            "PATCH" => update_task(conn, id, parse_body(request)?),
+            "DELETE" => delete_task(conn, id, query),
+            _ => Err(method_not_allowed()),
+        },
+        ["api", "tasks", id, "toggle"] if method == "POST" => toggle_task(conn, id),
+        ["api", "tasks", id, "subtasks"] if method == "GET" => {
+            get_task(conn, id)?;
+            Ok(ApiResponse::json(200, &TaskRepository::new(conn).get_direct_subtasks(id)?))
+        }
+        ["api", "tasks", id, "hierarchy"] if method == "GET" => {
+            let mut tasks = vec![get_task(conn, id)?];
+            tasks.extend(TaskRepository::new(conn).get_task_hierarchy(Some(id.to_string()))?);
+            Ok(ApiResponse::json(200, &tasks))
+        }
+        ["api", "tasks", id, "progress"] if method == "GET" => {
+            get_task(conn, id)?;
+            Ok(ApiResponse::json(200, &TaskRepository::new(conn).calculate_task_progress(id)?))
+        }
+        ["api", "search"] if method == "GET" => search(conn, query),
+        ["api", "categories"] => match method {
+            "GET" => Ok(ApiResponse::json(200, &CategoryRepository::new(conn).get_all()?)),
+            "POST" => create_category(conn, parse_body(request)?),
+            _ => Err(method_not_allowed()),
+        },
+        ["api", "categories", id] => match method {
+            "GET" => get_category(conn, id).map(|category| ApiResponse::json(200, &category)),
+            "PATCH" => update_category(conn, id, parse_body(request)?),
+            "DELETE" => delete_category(conn, id),
+            _ => Err(method_not_allowed()),
+        },
+        ["api", "tasks", _, "toggle" | "subtasks" | "hierarchy" | "progress"] | ["api", "search"] => Err(method_not_allowed()),
+        _ => Err(ApiError::not_found("Endpoint")),
+    }
+}
+
+/// The `Host` header without its port
+fn is_local_host(host: Option<&str>) -> bool {
+    let Some(host) = host else { return false };
+    let name = match host.rfind(':') {
+        Some(i) if !host[i..].contains(']') => &host[..i],
+        _ => host,
+    };
+    LOCAL_HOSTS.iter().any(|local| name.eq_ignore_ascii_case(local))
+}
+
+fn is_authorized(header: Option<&str>, token: &str) -> bool {
+    let Some(given) = header.and_then(|h| h.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    // Compare in constant time so the token cannot be guessed byte by byte
+    let (given, token) = (given.trim().as_bytes(), token.as_bytes());
+    !token.is_empty() && given.len() == token.len() && given.iter().zip(token).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn method_not_allowed() -> ApiError {
+    ApiError::new(405, "Method not allowed")
+}
+
+fn parse_body<T: DeserializeOwned>(request: &ApiRequest) -> Result<T, ApiError> {
+    serde_json::from_str(&request.body).map_err(|e| ApiError::bad_request(format!("Invalid request body: {}", e)))
+}
+
+fn get_task(conn: &Connection, id: &str) -> Result<Task, ApiError> {
+    TaskRepository::new(conn).get_by_id(id)?.ok_or_else(|| ApiError::not_found("Task"))
+}
+
+fn get_category(conn: &Connection, id: &str) -> Result<Category, ApiError> {
+    CategoryRepository::new(conn).get_by_id(id)?.ok_or_else(|| ApiError::not_found("Category"))
+}
+
+/// Tasks matching the query string: `completed`, `priority`, `category_id`,
+/// `no_category`, `parent_id`, `search`, `due_before`, `due_after` and `q`,
+/// a filter query as typed in the app.
+fn list_tasks(conn: &Connection, query: &[(String, String)]) -> RouteResult {
+    let param = |name: &str| query.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone());
+    let flag = |name: &str| -> Result<Option<bool>, ApiError> {
+        match param(name).as_deref() {
+            None => Ok(None),
+            Some("true") => Ok(Some(true)),
+            Some("false") => Ok(Some(false)),
+            Some(other) => Err(ApiError::bad_request(format!("{} must be true or false, not '{}'", name, other))),
+        }
+    };
+
+    let priority = match param("priority") {
+        Some(p) => Some(parse_priority(&p)?),
+        None => None,
+    };
+    if let Some(search) = param("search") {
+        to_fts_query(&search).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    }
+    let filter = TaskFilter {
+        completed: flag("completed")?,
+        priority,
+        category_id: param("category_id"),
+        parent_id: param("parent_id"),
+        search_query: param("search"),
+        due_before: param("due_before"),
+        due_after: param("due_after"),
+        no_category: flag("no_category")?,
+    };
+    let clause = match param("q") {
+        Some(q) => FilterQuery::parse(&q)
+            .map_err(|e| ApiError::bad_request(format!("Invalid filter query: {}", e)))?
+            .map(|q| q.to_sql()),
+        None => None,
+    };
+
+    let tasks = TaskRepository::new(conn).get_all_matching(Some(filter), clause.as_ref())?;
+    Ok(ApiResponse::json(200, &tasks))
+}
+
+fn parse_priority(value: &str) -> Result<Priority, ApiError> {
+    match value.to_ascii_lowercase().as_str() {
+        "low" => Ok(Priority::Low),
+        "medium" => Ok(Priority::Medium),
+        "high" => Ok(Priority::High),
+        _ => Err(ApiError::bad_request(format!("Unknown priority '{}'", value))),
+    }
+}
+
+fn search(conn: &Connection, query: &[(String, String)]) -> RouteResult {
+    let text = query
+        .iter()
+        .find(|(key, _)| key == "q")
+        .map(|(_, value)| value.as_str())
+        .ok_or_else(|| ApiError::bad_request("Missing q"))?;
+    let limit = match query.iter().find(|(key, _)| key == "limit") {
+        Some((_, limit)) => limit.parse::<usize>().map_err(|_| ApiError::bad_request("limit must be a number"))?,
+        None => MAX_SEARCH_RESULTS,
+    };
+    to_fts_query(text).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let results = TaskRepository::new(conn).search(text, limit.min(MAX_SEARCH_RESULTS))?;
+    Ok(ApiResponse::json(200, &results))
+}
+
+fn create_task(conn: &Connection, request: CreateTaskRequest) -> RouteResult {
+    if request.title.trim().is_empty() {
+        return Err(ApiError::bad_request("Task title cannot be empty"));
+    }
+    check_references(conn, request.category_id.as_deref(), request.parent_id.as_deref())?;
+
+    let task_repo = TaskRepository::new(conn);
+    let journal = Journal::new(conn);
+
+    let task = Task::new(request).map_err(ApiError::bad_request)?;
+    let tx = conn.unchecked_transaction()?;
+    task_repo.create(&task)?;
+
+    let after = journal.capture_tasks(std::slice::from_ref(&task.id))?;
+    journal.record("Create task", vec![], after)?;
+    tx.commit()?;
+
+    Ok(ApiResponse::json(201, &task).changed())
+}
+
+fn update_task(conn: &Connection, id: &str, request: UpdateTaskRequest) -> RouteResult {
+    let task_repo = TaskRepository::new(conn);
+    let journal = Journal::new(conn);
+
+    let mut task = get_task(conn, id)?;
+    check_references(conn, request.category_id.as_deref(), request.parent_id.as_deref())?;
+    if let Some(parent_id) = &request.parent_id {
+        if task_repo.subtree_ids(id)?.contains(parent_id) {
+            return Err(ApiError::bad_request("A task cannot be moved under itself or one of its subtasks"));
+        }
+    }
+    let before = journal.capture_tasks(std::slice::from_ref(&task.id))?;
+
+    task.update(request).map_err(ApiError::bad_request)?;
+    let tx = conn.unchecked_transaction()?;
+    task_repo.update(&task)?;
+
+    let after = journal.capture_tasks(std::slice::from_ref(&task.id))?;
+    journal.record("Edit task", before, after)?;
+    tx.commit()?;
+
+    Ok(ApiResponse::json(200, &task).changed())
+}
+
+/// A missing category or parent is a client mistake, not a database error
+fn check_references(conn: &Connection, category_id: Option<&str>, parent_id: Option<&str>) -> Result<(), ApiError> {
+    if let Some(category_id) = category_id {
+        if CategoryRepository::new(conn).get_by_id(category_id)?.is_none() {
+            return Err(ApiError::bad_request(format!("Category {} does not exist", category_id)));
+        }
+    }
+    if let Some(parent_id) = parent_id {
+        if TaskRepository::new(conn).get_by_id(parent_id)?.is_none() {
+            return Err(ApiError::bad_request(format!("Parent task {} does not exist", parent_id)));
+        }
+    }
+    Ok(())
+}
+
+/// Move a task to the trash. `?subtasks=delete` takes its subtasks along;
+/// by default they move up to the task's parent.
+fn delete_task(conn: &Connection, id: &str, query: &[(String, String)]) -> RouteResult {
+    let task_repo = TaskRepository::new(conn);
+    let journal = Journal::new(conn);
+    get_task(conn, id)?;
+
+    let tx = conn.unchecked_transaction()?;
+    match query.iter().find(|(key, _)| key == "subtasks").map(|(_, value)| value.as_str()) {
+        Some("delete") => {
+            let ids = task_repo.subtree_ids(id)?;
+            let before = journal.capture_tasks(&ids)?;
+            task_repo.delete_task_and_subtasks(id)?;
+            journal.record("Delete task with subtasks", before, vec![])?;
+        }
+        None | Some("promote") => {
+            let mut ids = vec![id.to_string()];
+            ids.extend(task_repo.get_direct_subtasks(id)?.into_iter().map(|t| t.id));
+            let before = journal.capture_tasks(&ids)?;
+            task_repo.delete_task_and_promote_subtasks(id)?;
+            let after = journal.capture_tasks(&ids)?;
+            journal.record("Delete task", before, after)?;
+        }
+        Some(other) => return Err(ApiError::bad_request(format!("subtasks must be delete or promote, not '{}'", other))),
+    }
+    tx.commit()?;
+
+    Ok(ApiResponse::no_content().changed())
+}
+
+fn toggle_task(conn: &Connection, id: &str) -> RouteResult {
+    let task_repo = TaskRepository::new(conn);
+    let journal = Journal::new(conn);
+
+    let mut task = get_task(conn, id)?;
+    let before = journal.capture_tasks(std::slice::from_ref(&task.id))?;
+    let mut changed_ids = vec![task.id.clone()];
+
+    let tx = conn.unchecked_transaction()?;
+    let completed = !task.completed;
+    changed_ids.extend(task_repo.set_completed(&mut task, completed)?);
+
+    let label = if task.completed { "Complete task" } else { "Reopen task" };
+    let after = journal.capture_tasks(&changed_ids)?;
+    journal.record(label, before, after)?;
+    tx.commit()?;
+
+    // Completing a task that is still waiting on others is allowed, with a warning
+    let open_blockers: Vec<Task> = if task.completed {
+        task_repo.get_blockers(&task.id)?.into_iter().filter(|t| !t.completed).collect()
+    } else {
+        vec![]
+    };
+
+    Ok(ApiResponse::json(200, &ToggleCompletionResult::new(task, &open_blockers)).changed())
+}
+
+fn create_category(conn: &Connection, request: CreateCategoryRequest) -> RouteResult {
+    if request.name.trim().is_empty() {
+        return Err(ApiError::bad_request("Category name cannot be empty"));
+    }
+    let category_repo = CategoryRepository::new(conn);
+    let journal = Journal::new(conn);
+
+    let category = Category::new(request);
+    let tx = conn.unchecked_transaction()?;
+    category_repo.create(&category)?;
+
+    let after = journal.capture_categories(std::slice::from_ref(&category.id))?;
+    journal.record("Create category", vec![], after)?;
+    tx.commit()?;
+
+    Ok(ApiResponse::json(201, &category).changed())
+}
+
+fn update_category(conn: &Connection, id: &str, request: UpdateCategoryRequest) -> RouteResult {
+    let category_repo = CategoryRepository::new(conn);
+    let journal = Journal::new(conn);
+
+    let mut category = get_category(conn, id)?;
+    let before = journal.capture_categories(std::slice::from_ref(&category.id))?;
+
+    category.update(request);
+    let tx = conn.unchecked_transaction()?;
+    category_repo.update(&category)?;
+
+    let after = journal.capture_categories(std::slice::from_ref(&category.id))?;
+    journal.record("Edit category", before, after)?;
+    tx.commit()?;
+
+    Ok(ApiResponse::json(200, &category).changed())
+}
+
+fn delete_category(conn: &Connection, id: &str) -> RouteResult {
+    let category_repo = CategoryRepository::new(conn);
+    let journal = Journal::new(conn);
+    get_category(conn, id)?;
+
+    let before = journal.capture_categories(&[id.to_string()])?;
+    let tx = conn.unchecked_transaction()?;
+    match category_repo.delete(id) {
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == rusqlite::ErrorCode::ConstraintViolation => {
+            return Err(ApiError::new(409, "Category still has tasks; move them to another category first"));
+        }
+        result => result?,
+    }
+    journal.record("Delete category", before, vec![])?;
+    tx.commit()?;
+
+    Ok(ApiResponse::no_content().changed())
+}
+
+/// Split `/path?a=1&b=2` into the path and decoded query parameters
+fn split_url(url: &str) -> (&str, Vec<(String, String)>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(&key.replace('+', " ")), percent_decode(&value.replace('+', " ")))
+        })
+        .collect();
+    (path, params)
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
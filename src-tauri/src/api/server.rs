@@ -0,0 +1,142 @@
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use tiny_http::{Header, Response, Server};
+use crate::api::{routes, ApiRequest, ApiResponse, MAX_BODY_BYTES};
+use crate::database::{Database, SettingsRepository};
+use crate::models::ApiSettings;
+use crate::utils::AppError;
+
+/// Emitted whenever an API request changed tasks or categories, so the
+/// window can reload them
+pub const API_CHANGED_EVENT: &str = "api-changed";
+
+/// The API server, while it is turned on. Kept in the app state so settings
+/// changes can restart or stop it.
+#[derive(Default)]
+pub struct ApiServer {
+    running: Mutex<Option<Running>>,
+}
+
+struct Running {
+    server: Arc<Server>,
+    thread: JoinHandle<()>,
+}
+
+impl ApiServer {
+    /// Start, restart or stop the server to match `settings`. Must not be
+    /// called while holding the database lock, as the request being served
+    /// may be waiting for it.
+    pub fn apply(&self, app: &AppHandle, settings: &ApiSettings) -> Result<(), AppError> {
+        let mut running = self
+            .running
+            .lock()
+            .map_err(|e| AppError::Server(format!("API server lock error: {}", e)))?;
+        if let Some(old) = running.take() {
+            old.server.unblock();
+            let _ = old.thread.join();
+        }
+        if !settings.enabled {
+            return Ok(());
+        }
+
+        // Loopback only: the API is for this computer, never the network
+        let server = Server::http(("127.0.0.1", settings.port))
+            .map_err(|e| AppError::Server(format!("Could not listen on port {}: {}", settings.port, e)))?;
+        let server = Arc::new(server);
+
+        let thread = {
+            let server = Arc::clone(&server);
+            let app = app.clone();
+            let token = settings.token.clone();
+            std::thread::spawn(move || serve(&server, &app, &token))
+        };
+        *running = Some(Running { server, thread });
+        Ok(())
+    }
+}
+
+/// Start the API server if it is turned on in settings.
+pub fn start_api_server(app: &tauri::App) {
+    let settings = {
+        let db = app.state::<Mutex<Database>>();
+        let db_lock = match db.lock() {
+            Ok(lock) => lock,
+            Err(e) => {
+                eprintln!("Database lock error: {}", e);
+                return;
+            }
+        };
+        SettingsRepository::new(&db_lock.connection).api_settings()
+    };
+
+    let result = settings
+        .map_err(AppError::from)
+        .and_then(|settings| app.state::<ApiServer>().apply(app.handle(), &settings));
+    if let Err(e) = result {
+        eprintln!("Failed to start API server: {}", e);
+    }
+}
+
+/// Answer requests one at a time until the server is unblocked. Each takes
+/// the same database lock as the Tauri commands.
+fn serve(server: &Server, app: &AppHandle, token: &str) {
+    for mut request in server.incoming_requests() {
+        let response = match read_request(&mut request) {
+            Ok(api_request) => routes::handle(&app.state::<Mutex<Database>>(), token, &api_request),
+            Err(response) => response,
+        };
+
+        if response.changed {
+            if let Err(e) = app.emit(API_CHANGED_EVENT, ()) {
+                eprintln!("Failed to notify window of API change: {}", e);
+            }
+        }
+        if let Err(e) = request.respond(to_http(response)) {
+            eprintln!("Failed to answer API request: {}", e);
+        }
+    }
+}
+
+fn read_request(request: &mut tiny_http::Request) -> Result<ApiRequest, ApiResponse> {
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| ApiResponse::error(400, &format!("Failed to read request: {}", e)))?;
+    if body.len() > MAX_BODY_BYTES {
+        return Err(ApiResponse::error(413, "Request body is too large"));
+    }
+
+    Ok(ApiRequest {
+        method: request.method().as_str().to_ascii_uppercase(),
+        url: request.url().to_string(),
+        headers: request
+            .headers()
+            .iter()
+            .map(|h| (h.field.as_str().as_str().to_string(), h.value.as_str().to_string()))
+            .collect(),
+        body: String::from_utf8(body).map_err(|_| ApiResponse::error(400, "Request body is not UTF-8"))?,
+    })
+}
+
+fn to_http(response: ApiResponse) -> Response<std::io::Cursor<Vec<u8>>> {
+    let headers = [
+        ("Content-Type", "application/json; charset=utf-8"),
+        // Bookmarklets call the API from other origins; the token is what
+        // keeps it private
+        ("Access-Control-Allow-Origin", "*"),
+        ("Access-Control-Allow-Headers", "Authorization, Content-Type"),
+        ("Access-Control-Allow-Methods", "GET, POST, PATCH, DELETE, OPTIONS"),
+    ];
+
+    let mut http = Response::from_data(response.body.into_bytes()).with_status_code(response.status);
+    for (field, value) in headers {
+        if let Ok(header) = Header::from_bytes(field, value) {
+            http.add_header(header);
+        }
+    }
+    http
+}
@@ -0,0 +1,594 @@
+//! Command-line interface to the Pluto task database, for scripts and cron.
+//!
+//! Works on the same `pluto_todo.db` as the app, and records its changes in
+//! the same history, so they can be undone from the app.
+
+mod output;
+
+use chrono::{Local, NaiveDateTime};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rusqlite::Connection;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use pluto_todo_lib::database::filter_query::FilterQuery;
+use pluto_todo_lib::database::{taskwarrior, CategoryRepository, Database, Journal, TaskRepository, DATABASE_FILE};
+use pluto_todo_lib::interop::backup::Backup;
+use pluto_todo_lib::interop::csv::export_csv;
+use pluto_todo_lib::interop::services::{parse_natural_due, parse_service_date};
+use pluto_todo_lib::interop::{ical, org, todotxt, ExportSummary};
+use pluto_todo_lib::models::{Category, CreateTaskRequest, Priority, QuickAddPreview, Task, TaskFilter, ToggleCompletionResult, UpdateTaskRequest};
+use pluto_todo_lib::utils::AppError;
+use output::{emit, Output};
+
+/// Must match `identifier` in tauri.conf.json, which names the app data directory
+const APP_IDENTIFIER: &str = "com.plutotool.pluto-todo";
+
+#[derive(Parser)]
+#[command(name = "pluto-todo-cli", version, about = "Manage Pluto tasks from the terminal")]
+struct Cli {
+    /// Database to use instead of the app's
+    #[arg(long, global = true, env = "PLUTO_TODO_DB", value_name = "PATH")]
+    db: Option<PathBuf>,
+
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add a task
+    ///
+    /// The text is read like quick add in the app, e.g.
+    /// "Pay rent every month on the 1st !high #finance @Personal"
+    Add(AddArgs),
+    /// List tasks, open ones by default
+    List(ListArgs),
+    /// Mark tasks as done
+    Done {
+        /// Task IDs, or enough of their start to tell them apart
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Reopen the tasks instead
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Change a task
+    Edit(EditArgs),
+    /// Move a task to the trash
+    Delete {
+        id: String,
+        /// Also delete its subtasks; otherwise they move up a level
+        #[arg(long)]
+        with_subtasks: bool,
+    },
+    /// Full-text search over titles, descriptions and tags
+    Search {
+        query: String,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// List categories with their number of open tasks
+    Categories,
+    /// Write tasks to a file, or to standard output
+    Export(ExportArgs),
+}
+
+#[derive(Args)]
+struct AddArgs {
+    #[arg(required = true, num_args = 1..)]
+    text: Vec<String>,
+    /// Use the text as the title as it is, without reading fields from it
+    #[arg(long)]
+    literal: bool,
+    #[arg(short, long)]
+    description: Option<String>,
+    #[arg(short, long, value_enum)]
+    priority: Option<PriorityArg>,
+    /// A date such as 2026-11-05, "tomorrow 9am" or "next friday"
+    #[arg(long)]
+    due: Option<String>,
+    /// Category name or ID
+    #[arg(short, long)]
+    category: Option<String>,
+    #[arg(short, long = "tag")]
+    tags: Vec<String>,
+    /// Make it a subtask of this task
+    #[arg(long)]
+    parent: Option<String>,
+    /// RRULE text such as FREQ=WEEKLY;BYDAY=MO
+    #[arg(long)]
+    repeat: Option<String>,
+}
+
+#[derive(Args)]
+struct FilterArgs {
+    #[arg(short, long, value_enum)]
+    priority: Option<PriorityArg>,
+    /// Category name or ID
+    #[arg(short, long, conflicts_with = "no_category")]
+    category: Option<String>,
+    /// Only tasks without a category
+    #[arg(long)]
+    no_category: bool,
+    /// Only tasks due on or before this date
+    #[arg(long, value_name = "DATE")]
+    due_before: Option<String>,
+    /// Only tasks due on or after this date
+    #[arg(long, value_name = "DATE")]
+    due_after: Option<String>,
+    /// A filter query as in the app, e.g. "#client-a due:<2026-11-01"
+    #[arg(short, long)]
+    query: Option<String>,
+}
+
+#[derive(Args)]
+struct ListArgs {
+    #[command(flatten)]
+    filter: FilterArgs,
+    /// Include completed tasks
+    #[arg(short, long, conflicts_with = "done")]
+    all: bool,
+    /// Only completed tasks
+    #[arg(long)]
+    done: bool,
+}
+
+#[derive(Args)]
+struct EditArgs {
+    id: String,
+    #[arg(long)]
+    title: Option<String>,
+    /// An empty description clears it
+    #[arg(short, long)]
+    description: Option<String>,
+    #[arg(short, long, value_enum)]
+    priority: Option<PriorityArg>,
+    /// A date as for `add`; an empty one clears the due date
+    #[arg(long)]
+    due: Option<String>,
+    /// Category name or ID
+    #[arg(short, long, conflicts_with = "no_category")]
+    category: Option<String>,
+    #[arg(long)]
+    no_category: bool,
+    /// Replace the tags; repeat for several
+    #[arg(short, long = "tag", conflicts_with = "no_tags")]
+    tags: Vec<String>,
+    #[arg(long)]
+    no_tags: bool,
+    #[arg(long, conflicts_with = "no_parent")]
+    parent: Option<String>,
+    /// Make it a top-level task
+    #[arg(long)]
+    no_parent: bool,
+    /// RRULE text; an empty one stops the task repeating
+    #[arg(long)]
+    repeat: Option<String>,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    #[arg(short, long, value_enum)]
+    format: ExportFormat,
+    /// File to write; standard output if left out
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    #[command(flatten)]
+    filter: FilterArgs,
+    /// Include completed tasks
+    #[arg(short, long)]
+    all: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PriorityArg {
+    Low,
+    Medium,
+    High,
+}
+
+impl From<PriorityArg> for Priority {
+    fn from(priority: PriorityArg) -> Self {
+        match priority {
+            PriorityArg::Low => Priority::Low,
+            PriorityArg::Medium => Priority::Medium,
+            PriorityArg::High => Priority::High,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// A full backup, as written by the app; filters do not apply
+    Backup,
+    Csv,
+    Ics,
+    Todotxt,
+    Org,
+    Taskwarrior,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let out = Output::new(cli.json);
+
+    match run(cli.command, cli.db, &out) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(AppError::Io(e)) if e.kind() == std::io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Where the app keeps its database: `pluto_todo.db` in the app data
+/// directory, the same path Tauri resolves for the app.
+fn default_db_path() -> Result<PathBuf, AppError> {
+    let data_dir = dirs::data_dir()
+        .ok_or_else(|| AppError::InvalidInput("Could not find the app data directory; pass --db".to_string()))?;
+    Ok(data_dir.join(APP_IDENTIFIER).join(DATABASE_FILE))
+}
+
+fn run(command: Command, db: Option<PathBuf>, out: &Output) -> Result<(), AppError> {
+    let path = match db {
+        Some(path) => path,
+        None => {
+            let path = default_db_path()?;
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            path
+        }
+    };
+    let database = Database::new(&path)?;
+    let conn = &database.connection;
+
+    match command {
+        Command::Add(args) => add(conn, args, out),
+        Command::List(args) => list(conn, args, out),
+        Command::Done { ids, undo } => done(conn, &ids, !undo, out),
+        Command::Edit(args) => edit(conn, args, out),
+        Command::Delete { id, with_subtasks } => delete(conn, &id, with_subtasks, out),
+        Command::Search { query, limit } => {
+            let results = TaskRepository::new(conn).search(&query, limit)?;
+            out.search_results(&results, &CategoryRepository::new(conn).get_all()?)
+        }
+        Command::Categories => categories(conn, out),
+        Command::Export(args) => export(conn, args, out),
+    }
+}
+
+fn add(conn: &Connection, args: AddArgs, out: &Output) -> Result<(), AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let journal = Journal::new(conn);
+    let categories = CategoryRepository::new(conn).get_all()?;
+
+    let text = args.text.join(" ");
+    let mut request = if args.literal {
+        CreateTaskRequest {
+            title: text,
+            description: None,
+            priority: None,
+            due_date: None,
+            category_id: None,
+            tags: None,
+            parent_id: None,
+            recurrence: None,
+        }
+    } else {
+        let preview = QuickAddPreview::parse(&text, Local::now().naive_local(), &categories);
+        for warning in &preview.warnings {
+            eprintln!("warning: {}", warning);
+        }
+        preview.to_create_request()
+    };
+    if request.title.trim().is_empty() {
+        return Err(AppError::InvalidInput("Task title cannot be empty".to_string()));
+    }
+
+    request.description = args.description.or(request.description);
+    if let Some(priority) = args.priority {
+        request.priority = Some(Priority::from(priority).to_string());
+    }
+    if let Some(category) = args.category {
+        request.category_id = Some(find_category(&categories, &category)?.id.clone());
+    }
+    if !args.tags.is_empty() {
+        let mut tags = request.tags.unwrap_or_default();
+        tags.extend(args.tags);
+        request.tags = Some(tags);
+    }
+    if let Some(parent) = args.parent {
+        request.parent_id = Some(find_task(conn, &parent)?.id);
+    }
+
+    let mut task = Task::new(request).map_err(AppError::InvalidInput)?;
+    if let Some(due) = args.due {
+        let (due_date, recurrence) = parse_due(&due)?;
+        task.due_date = due_date;
+        task.recurrence = recurrence.or(task.recurrence);
+    }
+    if let Some(repeat) = args.repeat {
+        task.recurrence = Some(repeat.parse().map_err(|e| AppError::InvalidInput(format!("Invalid --repeat: {}", e)))?);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    task_repo.create(&task)?;
+    let after = journal.capture_tasks(std::slice::from_ref(&task.id))?;
+    journal.record("Create task", vec![], after)?;
+    tx.commit()?;
+
+    out.task(&task, &categories)
+}
+
+fn list(conn: &Connection, args: ListArgs, out: &Output) -> Result<(), AppError> {
+    let categories = CategoryRepository::new(conn).get_all()?;
+    let completed = match (args.all, args.done) {
+        (true, _) => None,
+        (false, true) => Some(true),
+        (false, false) => Some(false),
+    };
+    let tasks = filtered_tasks(conn, &args.filter, completed, &categories)?;
+    out.task_list(&tasks, &categories)
+}
+
+fn done(conn: &Connection, ids: &[String], completed: bool, out: &Output) -> Result<(), AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let journal = Journal::new(conn);
+
+    let tasks = ids.iter().map(|id| find_task(conn, id)).collect::<Result<Vec<_>, _>>()?;
+    let mut changed_ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+    let before = journal.capture_tasks(&changed_ids)?;
+
+    // All or nothing, so a failure part way leaves every task as it was
+    let tx = conn.unchecked_transaction()?;
+    let mut updated = Vec::new();
+    for mut task in tasks {
+        if task.completed == completed {
+            updated.push(task);
+            continue;
+        }
+        changed_ids.extend(task_repo.set_completed(&mut task, completed)?);
+        updated.push(task);
+    }
+
+    let label = match (completed, updated.len()) {
+        (true, 1) => "Complete task",
+        (true, _) => "Complete tasks",
+        (false, 1) => "Reopen task",
+        (false, _) => "Reopen tasks",
+    };
+    let after = journal.capture_tasks(&changed_ids)?;
+    journal.record(label, before, after)?;
+    tx.commit()?;
+
+    // Checked once all are done, so finishing a task with its blockers is fine
+    if completed {
+        for task in &updated {
+            let open_blockers: Vec<Task> = task_repo.get_blockers(&task.id)?.into_iter().filter(|t| !t.completed).collect();
+            if let Some(warning) = ToggleCompletionResult::new(task.clone(), &open_blockers).warning {
+                eprintln!("warning: {}", warning);
+            }
+        }
+    }
+
+    out.task_list(&updated, &CategoryRepository::new(conn).get_all()?)
+}
+
+fn edit(conn: &Connection, args: EditArgs, out: &Output) -> Result<(), AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let journal = Journal::new(conn);
+    let categories = CategoryRepository::new(conn).get_all()?;
+
+    let mut task = find_task(conn, &args.id)?;
+    let before = journal.capture_tasks(std::slice::from_ref(&task.id))?;
+
+    let parent_id = match args.parent {
+        Some(parent) => {
+            let parent = find_task(conn, &parent)?;
+            if task_repo.subtree_ids(&task.id)?.contains(&parent.id) {
+                return Err(AppError::InvalidInput("A task cannot be moved under itself or one of its subtasks".to_string()));
+            }
+            Some(parent.id)
+        }
+        None => None,
+    };
+    task.update(UpdateTaskRequest {
+        title: args.title,
+        description: args.description.clone(),
+        completed: None,
+        priority: args.priority.map(|p| Priority::from(p).to_string()),
+        due_date: None,
+        category_id: args.category.map(|c| find_category(&categories, &c).map(|c| c.id.clone())).transpose()?,
+        tags: (!args.tags.is_empty()).then_some(args.tags),
+        parent_id,
+        recurrence: args.repeat.clone(),
+    }).map_err(AppError::InvalidInput)?;
+
+    if args.description.as_deref() == Some("") {
+        task.description = None;
+    }
+    if let Some(due) = args.due {
+        let (due_date, recurrence) = if due.trim().is_empty() { (None, None) } else { parse_due(&due)? };
+        task.due_date = due_date;
+        if recurrence.is_some() {
+            task.recurrence = recurrence;
+        }
+    }
+    if let Some(repeat) = args.repeat.filter(|r| !r.is_empty()) {
+        task.recurrence = Some(repeat.parse().map_err(|e| AppError::InvalidInput(format!("Invalid --repeat: {}", e)))?);
+    }
+    if args.no_category {
+        task.category_id = None;
+    }
+    if args.no_tags {
+        task.tags.clear();
+    }
+    if args.no_parent {
+        task.parent_id = None;
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    task_repo.update(&task)?;
+    let after = journal.capture_tasks(std::slice::from_ref(&task.id))?;
+    journal.record("Edit task", before, after)?;
+    tx.commit()?;
+
+    out.task(&task, &categories)
+}
+
+fn delete(conn: &Connection, id: &str, with_subtasks: bool, out: &Output) -> Result<(), AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let journal = Journal::new(conn);
+
+    let task = find_task(conn, id)?;
+    let subtree = task_repo.subtree_ids(&task.id)?;
+
+    if with_subtasks {
+        let before = journal.capture_tasks(&subtree)?;
+        let tx = conn.unchecked_transaction()?;
+        task_repo.delete_task_and_subtasks(&task.id)?;
+        journal.record("Delete task with subtasks", before, vec![])?;
+        tx.commit()?;
+        return out.deleted(&subtree);
+    }
+
+    let mut ids = vec![task.id.clone()];
+    ids.extend(task_repo.get_direct_subtasks(&task.id)?.into_iter().map(|t| t.id));
+    let before = journal.capture_tasks(&ids)?;
+    let tx = conn.unchecked_transaction()?;
+    task_repo.delete_task_and_promote_subtasks(&task.id)?;
+    let after = journal.capture_tasks(&ids)?;
+    journal.record("Delete task", before, after)?;
+    tx.commit()?;
+
+    out.deleted(std::slice::from_ref(&task.id))
+}
+
+fn categories(conn: &Connection, out: &Output) -> Result<(), AppError> {
+    let categories = CategoryRepository::new(conn).get_all()?;
+    let open = TaskRepository::new(conn).get_all(Some(TaskFilter {
+        completed: Some(false),
+        priority: None,
+        category_id: None,
+        parent_id: None,
+        search_query: None,
+        due_before: None,
+        due_after: None,
+        no_category: None,
+    }))?;
+
+    let counts: Vec<usize> = categories
+        .iter()
+        .map(|c| open.iter().filter(|t| t.category_id.as_deref() == Some(c.id.as_str())).count())
+        .collect();
+    out.categories(&categories, &counts)
+}
+
+fn export(conn: &Connection, args: ExportArgs, out: &Output) -> Result<(), AppError> {
+    let categories = CategoryRepository::new(conn).get_all()?;
+    let completed = if args.all { None } else { Some(false) };
+
+    let (text, summary) = match args.format {
+        ExportFormat::Backup => {
+            let backup = Backup::export(conn)?;
+            let summary = ExportSummary { tasks: backup.tasks.len(), categories: backup.categories.len() };
+            (backup.to_json()?, summary)
+        }
+        ExportFormat::Csv | ExportFormat::Taskwarrior => {
+            // Both exporters take a structured filter only
+            if args.filter.query.is_some() {
+                return Err(AppError::InvalidInput("--query is not supported for this format".to_string()));
+            }
+            let filter = task_filter(&args.filter, completed, &categories)?;
+            let (text, count) = match args.format {
+                ExportFormat::Csv => export_csv(conn, Some(filter))?,
+                _ => taskwarrior::export_taskwarrior(conn, Some(filter))?,
+            };
+            (text, ExportSummary { tasks: count, categories: 0 })
+        }
+        format => {
+            let tasks = filtered_tasks(conn, &args.filter, completed, &categories)?;
+            let text = match format {
+                ExportFormat::Ics => ical::export_ics(&tasks, &categories),
+                ExportFormat::Todotxt => todotxt::export_todotxt(&tasks, &categories),
+                _ => org::export_org(&tasks, &categories),
+            };
+            (text, ExportSummary { tasks: tasks.len(), categories: 0 })
+        }
+    };
+
+    match args.output {
+        Some(path) => {
+            std::fs::write(&path, text)?;
+            out.exported(&summary, &path)
+        }
+        None => emit(text.trim_end_matches('\n')),
+    }
+}
+
+fn task_filter(args: &FilterArgs, completed: Option<bool>, categories: &[Category]) -> Result<TaskFilter, AppError> {
+    Ok(TaskFilter {
+        completed,
+        priority: args.priority.map(Priority::from),
+        category_id: args.category.as_deref().map(|c| find_category(categories, c).map(|c| c.id.clone())).transpose()?,
+        parent_id: None,
+        search_query: None,
+        due_before: args.due_before.clone(),
+        due_after: args.due_after.clone(),
+        no_category: args.no_category.then_some(true),
+    })
+}
+
+fn filtered_tasks(conn: &Connection, args: &FilterArgs, completed: Option<bool>, categories: &[Category]) -> Result<Vec<Task>, AppError> {
+    let filter = task_filter(args, completed, categories)?;
+    let clause = match &args.query {
+        Some(query) => FilterQuery::parse(query)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid filter query: {}", e)))?
+            .map(|q| q.to_sql()),
+        None => None,
+    };
+    Ok(TaskRepository::new(conn).get_all_matching(Some(filter), clause.as_ref())?)
+}
+
+/// A live task by its ID or an unambiguous start of it, as printed by `list`.
+fn find_task(conn: &Connection, id: &str) -> Result<Task, AppError> {
+    let task_repo = TaskRepository::new(conn);
+    if let Some(task) = task_repo.get_by_id(id)? {
+        return Ok(task);
+    }
+
+    let mut matches = task_repo.get_all(None)?.into_iter().filter(|t| t.id.starts_with(id));
+    match (matches.next(), matches.next()) {
+        (Some(task), None) if !id.is_empty() => Ok(task),
+        (Some(_), Some(_)) => Err(AppError::InvalidInput(format!("'{}' matches more than one task; give more of the ID", id))),
+        _ => Err(AppError::TaskNotFound),
+    }
+}
+
+/// A category by ID or by name, ignoring case.
+fn find_category<'a>(categories: &'a [Category], name: &str) -> Result<&'a Category, AppError> {
+    categories
+        .iter()
+        .find(|c| c.id == name)
+        .or_else(|| categories.iter().find(|c| c.name.eq_ignore_ascii_case(name)))
+        .ok_or(AppError::CategoryNotFound)
+}
+
+/// An ISO date or date-time, or a date in words the way quick add reads
+/// them, which may also set a recurrence ("every monday").
+fn parse_due(text: &str) -> Result<(Option<NaiveDateTime>, Option<pluto_todo_lib::models::RecurrenceRule>), AppError> {
+    if let Some(due) = parse_service_date(text, false) {
+        return Ok((Some(due), None));
+    }
+    let (due, recurrence, rest) = parse_natural_due(text, Local::now().naive_local());
+    if due.is_none() || !rest.trim().is_empty() {
+        return Err(AppError::InvalidInput(format!("Could not read the date '{}'", text)));
+    }
+    Ok((due, recurrence))
+}
@@ -0,0 +1,193 @@
+use chrono::{Local, NaiveDateTime, NaiveTime, TimeZone};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use pluto_todo_lib::database::search::{HIGHLIGHT_END, HIGHLIGHT_START};
+use pluto_todo_lib::interop::ExportSummary;
+use pluto_todo_lib::models::{Category, Priority, Task, TaskSearchResult};
+use pluto_todo_lib::utils::AppError;
+
+/// How much of a task ID `list` shows; any unambiguous start is accepted back
+const SHORT_ID_LEN: usize = 8;
+
+/// Prints results either for people or, with `--json`, for scripts.
+pub struct Output {
+    json: bool,
+    /// Bold search matches when writing to a terminal
+    color: bool,
+}
+
+#[derive(Serialize)]
+struct CategoryRow<'a> {
+    #[serde(flatten)]
+    category: &'a Category,
+    open_tasks: usize,
+}
+
+#[derive(Serialize)]
+struct Deleted<'a> {
+    deleted: &'a [String],
+}
+
+#[derive(Serialize)]
+struct Exported<'a> {
+    #[serde(flatten)]
+    summary: &'a ExportSummary,
+    path: &'a Path,
+}
+
+impl Output {
+    pub fn new(json: bool) -> Self {
+        Output {
+            json,
+            color: std::io::stdout().is_terminal(),
+        }
+    }
+
+    pub fn task(&self, task: &Task, categories: &[Category]) -> Result<(), AppError> {
+        if self.json {
+            return print_json(task);
+        }
+        emit(&task_line(task, categories))
+    }
+
+    /// Tasks with their subtasks indented below them, when both are listed.
+    pub fn task_list(&self, tasks: &[Task], categories: &[Category]) -> Result<(), AppError> {
+        if self.json {
+            return print_json(&tasks);
+        }
+        if tasks.is_empty() {
+            return emit("No tasks");
+        }
+
+        let listed: HashSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+        let mut children: HashMap<&str, Vec<&Task>> = HashMap::new();
+        let mut roots = Vec::new();
+        for task in tasks {
+            match task.parent_id.as_deref().filter(|p| listed.contains(p)) {
+                Some(parent) => children.entry(parent).or_default().push(task),
+                None => roots.push(task),
+            }
+        }
+
+        let mut stack: Vec<(&Task, usize)> = roots.into_iter().rev().map(|t| (t, 0)).collect();
+        while let Some((task, depth)) = stack.pop() {
+            emit(&format!("{}{}", "  ".repeat(depth), task_line(task, categories)))?;
+            if let Some(subtasks) = children.get(task.id.as_str()) {
+                stack.extend(subtasks.iter().rev().map(|t| (*t, depth + 1)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn search_results(&self, results: &[TaskSearchResult], categories: &[Category]) -> Result<(), AppError> {
+        if self.json {
+            return print_json(&results);
+        }
+        if results.is_empty() {
+            return emit("No matching tasks");
+        }
+
+        for result in results {
+            let task = Task {
+                title: self.highlight(&result.title_highlight),
+                ..result.task.clone()
+            };
+            emit(&task_line(&task, categories))?;
+            if let Some(snippet) = &result.description_snippet {
+                emit(&format!("    {}", self.highlight(snippet).replace('\n', " ")))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn deleted(&self, ids: &[String]) -> Result<(), AppError> {
+        if self.json {
+            return print_json(&Deleted { deleted: ids });
+        }
+        match ids.len() {
+            1 => emit("Moved 1 task to the trash"),
+            n => emit(&format!("Moved {} tasks to the trash", n)),
+        }
+    }
+
+    pub fn categories(&self, categories: &[Category], open_tasks: &[usize]) -> Result<(), AppError> {
+        if self.json {
+            let rows: Vec<CategoryRow> = categories
+                .iter()
+                .zip(open_tasks)
+                .map(|(category, &open_tasks)| CategoryRow { category, open_tasks })
+                .collect();
+            return print_json(&rows);
+        }
+
+        let width = categories.iter().map(|c| c.name.chars().count()).max().unwrap_or(0);
+        for (category, count) in categories.iter().zip(open_tasks) {
+            emit(&format!("{:<width$}  {:>4} open  {}", category.name, count, short_id(&category.id), width = width))?;
+        }
+        Ok(())
+    }
+
+    pub fn exported(&self, summary: &ExportSummary, path: &Path) -> Result<(), AppError> {
+        if self.json {
+            return print_json(&Exported { summary, path });
+        }
+        emit(&format!("Exported {} tasks to {}", summary.tasks, path.display()))
+    }
+
+    fn highlight(&self, text: &str) -> String {
+        let (start, end) = if self.color { ("\x1b[1m", "\x1b[0m") } else { ("", "") };
+        text.replace(HIGHLIGHT_START, start).replace(HIGHLIGHT_END, end)
+    }
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    emit(&json)
+}
+
+/// Write a line to standard output. A closed pipe (`| head`) is reported as
+/// an I/O error for `main` to ignore, instead of panicking like `println!`.
+pub fn emit(text: &str) -> Result<(), AppError> {
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", text)?;
+    Ok(())
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..SHORT_ID_LEN).unwrap_or(id)
+}
+
+/// One line per task, in the same notation `add` reads:
+/// `3f2a9c1e [ ] Pay rent !high @Personal #finance due 2026-11-01 (FREQ=MONTHLY)`
+fn task_line(task: &Task, categories: &[Category]) -> String {
+    let mut line = format!("{} [{}] {}", short_id(&task.id), if task.completed { "x" } else { " " }, task.title);
+
+    match task.priority {
+        Priority::High => line.push_str(" !high"),
+        Priority::Low => line.push_str(" !low"),
+        Priority::Medium => {}
+    }
+    if let Some(category) = task.category_id.as_deref().and_then(|id| categories.iter().find(|c| c.id == id)) {
+        line.push_str(&format!(" @{}", category.name));
+    }
+    for tag in &task.tags {
+        line.push_str(&format!(" #{}", tag));
+    }
+    if let Some(due) = task.due_date {
+        line.push_str(&format!(" due {}", format_due(due)));
+    }
+    if let Some(rule) = &task.recurrence {
+        line.push_str(&format!(" ({})", rule));
+    }
+    line
+}
+
+/// Date-only due dates are stored as midnight UTC; others are shown in local time
+fn format_due(due: NaiveDateTime) -> String {
+    if due.time() == NaiveTime::MIN {
+        return due.format("%Y-%m-%d").to_string();
+    }
+    Local.from_utc_datetime(&due).format("%Y-%m-%d %H:%M").to_string()
+}
@@ -0,0 +1,63 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+use crate::api::server::ApiServer;
+use crate::database::{Database, SettingsRepository};
+use crate::models::ApiSettings;
+
+#[tauri::command]
+pub async fn get_api_settings(
+    db: State<'_, Mutex<Database>>,
+) -> Result<ApiSettings, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let settings_repo = SettingsRepository::new(&db_lock.connection);
+    
+    settings_repo.api_settings().map_err(|e| format!("Failed to get API settings: {}", e))
+}
+
+/// Turn the local API on or off, or move it to another port. An empty token
+/// keeps the current one.
+#[tauri::command]
+pub async fn set_api_settings(
+    app: AppHandle,
+    db: State<'_, Mutex<Database>>,
+    api_server: State<'_, ApiServer>,
+    settings: ApiSettings,
+) -> Result<ApiSettings, String> {
+    let settings = {
+        let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        let settings_repo = SettingsRepository::new(&db_lock.connection);
+        
+        let mut settings = settings;
+        if settings.port == 0 {
+            return Err("Choose a port between 1 and 65535".to_string());
+        }
+        if settings.token.trim().is_empty() {
+            settings.token = settings_repo.api_settings().map_err(|e| format!("Failed to get API settings: {}", e))?.token;
+        }
+        settings_repo.set_api_settings(&settings).map_err(|e| format!("Failed to set API settings: {}", e))?;
+        settings
+    };
+    
+    // The database lock must be released first; a request may be waiting on it
+    api_server.apply(&app, &settings).map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Replace the API token, so anything holding the old one is locked out.
+#[tauri::command]
+pub async fn regenerate_api_token(
+    app: AppHandle,
+    db: State<'_, Mutex<Database>>,
+    api_server: State<'_, ApiServer>,
+) -> Result<ApiSettings, String> {
+    let settings = {
+        let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        let settings_repo = SettingsRepository::new(&db_lock.connection);
+        
+        settings_repo.regenerate_api_token().map_err(|e| format!("Failed to replace API token: {}", e))?;
+        settings_repo.api_settings().map_err(|e| format!("Failed to get API settings: {}", e))?
+    };
+    
+    api_server.apply(&app, &settings).map_err(|e| e.to_string())?;
+    Ok(settings)
+}
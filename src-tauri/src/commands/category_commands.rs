@@ -1,6 +1,6 @@
 use std::sync::Mutex;
 use tauri::State;
-use crate::database::{Database, CategoryRepository};
+use crate::database::{Database, CategoryRepository, Journal};
 use crate::models::{Category, CreateCategoryRequest, UpdateCategoryRequest};
 
 #[tauri::command]
@@ -14,9 +14,16 @@ pub async fn create_category(
     let category_repo = CategoryRepository::new(&db_lock.connection);
     
     let request = CreateCategoryRequest { name, color, icon };
+    let journal = Journal::new(&db_lock.connection);
+    
     let category = Category::new(request);
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to create category: {}", e))?;
     category_repo.create(&category).map_err(|e| format!("Failed to create category: {}", e))?;
     
+    let after = journal.capture_categories(std::slice::from_ref(&category.id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Create category", vec![], after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to create category: {}", e))?;
+    
     Ok(category)
 }
 
@@ -57,10 +64,18 @@ pub async fn update_category(
         .map_err(|e| format!("Failed to get category: {}", e))?
         .ok_or_else(|| "Category not found".to_string())?;
     
+    let journal = Journal::new(&db_lock.connection);
+    let before = journal.capture_categories(std::slice::from_ref(&id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    
     let request = UpdateCategoryRequest { name, color, icon };
     category.update(request);
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to update category: {}", e))?;
     category_repo.update(&category).map_err(|e| format!("Failed to update category: {}", e))?;
     
+    let after = journal.capture_categories(&[id]).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Edit category", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to update category: {}", e))?;
+    
     Ok(category)
 }
 
@@ -72,5 +87,12 @@ pub async fn delete_category(
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let category_repo = CategoryRepository::new(&db_lock.connection);
     
-    category_repo.delete(&id).map_err(|e| format!("Failed to delete category: {}", e))
+    let journal = Journal::new(&db_lock.connection);
+    let before = journal.capture_categories(std::slice::from_ref(&id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to delete category: {}", e))?;
+    category_repo.delete(&id).map_err(|e| format!("Failed to delete category: {}", e))?;
+    
+    journal.record("Delete category", before, vec![]).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to delete category: {}", e))
 }
@@ -0,0 +1,110 @@
+use std::sync::Mutex;
+use tauri::State;
+use crate::database::filter_query::{FilterQuery, FilterTerm};
+use crate::database::{Database, DependencyRepository, Journal, TaskRepository};
+use crate::models::{Task, TaskDependencies};
+
+/// Record that `task_id` cannot start until `depends_on_id` is done. Links
+/// that would make a task wait on itself, directly or through other tasks,
+/// are refused.
+#[tauri::command]
+pub async fn add_task_dependency(
+    db: State<'_, Mutex<Database>>,
+    task_id: String,
+    depends_on_id: String,
+) -> Result<(), String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    let dependency_repo = DependencyRepository::new(&db_lock.connection);
+    
+    let task = task_repo
+        .get_by_id(&task_id)
+        .map_err(|e| format!("Failed to get task: {}", e))?
+        .filter(|t| t.deleted_at.is_none())
+        .ok_or_else(|| "Task not found".to_string())?;
+    let blocker = task_repo
+        .get_by_id(&depends_on_id)
+        .map_err(|e| format!("Failed to get task: {}", e))?
+        .filter(|t| t.deleted_at.is_none())
+        .ok_or_else(|| "Blocking task not found".to_string())?;
+    
+    if dependency_repo.would_create_cycle(&task_id, &depends_on_id).map_err(|e| format!("Failed to check dependencies: {}", e))? {
+        return Err(format!(
+            "\"{}\" cannot wait on \"{}\", which already waits on it",
+            task.title, blocker.title
+        ));
+    }
+    
+    let journal = Journal::new(&db_lock.connection);
+    let before = journal.capture_tasks(std::slice::from_ref(&task_id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to add dependency: {}", e))?;
+    dependency_repo.add(&task_id, &depends_on_id).map_err(|e| format!("Failed to add dependency: {}", e))?;
+    
+    let after = journal.capture_tasks(&[task_id]).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Add dependency", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to add dependency: {}", e))
+}
+
+#[tauri::command]
+pub async fn remove_task_dependency(
+    db: State<'_, Mutex<Database>>,
+    task_id: String,
+    depends_on_id: String,
+) -> Result<(), String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let dependency_repo = DependencyRepository::new(&db_lock.connection);
+    
+    let journal = Journal::new(&db_lock.connection);
+    let before = journal.capture_tasks(std::slice::from_ref(&task_id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to remove dependency: {}", e))?;
+    let removed = dependency_repo
+        .remove(&task_id, &depends_on_id)
+        .map_err(|e| format!("Failed to remove dependency: {}", e))?;
+    if !removed {
+        return Err("Dependency not found".to_string());
+    }
+    
+    let after = journal.capture_tasks(&[task_id]).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Remove dependency", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to remove dependency: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_task_dependencies(
+    db: State<'_, Mutex<Database>>,
+    task_id: String,
+) -> Result<TaskDependencies, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    Ok(TaskDependencies {
+        blocked_by: task_repo.get_blockers(&task_id).map_err(|e| format!("Failed to get dependencies: {}", e))?,
+        blocking: task_repo.get_dependents(&task_id).map_err(|e| format!("Failed to get dependencies: {}", e))?,
+    })
+}
+
+/// Open tasks that are waiting on at least one open task.
+#[tauri::command]
+pub async fn get_blocked_tasks(
+    db: State<'_, Mutex<Database>>,
+) -> Result<Vec<Task>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    let clause = FilterQuery::Term(FilterTerm::Blocked).to_sql();
+    task_repo.get_all_matching(None, Some(&clause)).map_err(|e| format!("Failed to get tasks: {}", e))
+}
+
+/// Open tasks that can be started now: everything they wait on is done.
+#[tauri::command]
+pub async fn get_unblocked_tasks(
+    db: State<'_, Mutex<Database>>,
+) -> Result<Vec<Task>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    let clause = FilterQuery::Term(FilterTerm::Unblocked).to_sql();
+    task_repo.get_all_matching(None, Some(&clause)).map_err(|e| format!("Failed to get tasks: {}", e))
+}
@@ -0,0 +1,38 @@
+use std::sync::Mutex;
+use tauri::State;
+use crate::database::{Database, Journal};
+use crate::models::UndoState;
+
+#[tauri::command]
+pub async fn undo(
+    db: State<'_, Mutex<Database>>,
+) -> Result<Option<String>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let journal = Journal::new(&db_lock.connection);
+    
+    let entry = journal.undo().map_err(|e| format!("Failed to undo: {}", e))?;
+    
+    Ok(entry.map(|e| e.label))
+}
+
+#[tauri::command]
+pub async fn redo(
+    db: State<'_, Mutex<Database>>,
+) -> Result<Option<String>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let journal = Journal::new(&db_lock.connection);
+    
+    let entry = journal.redo().map_err(|e| format!("Failed to redo: {}", e))?;
+    
+    Ok(entry.map(|e| e.label))
+}
+
+#[tauri::command]
+pub async fn get_undo_state(
+    db: State<'_, Mutex<Database>>,
+) -> Result<UndoState, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let journal = Journal::new(&db_lock.connection);
+    
+    journal.state().map_err(|e| format!("Failed to get undo state: {}", e))
+}
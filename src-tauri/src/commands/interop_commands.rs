@@ -0,0 +1,466 @@
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use crate::database::{CategoryRepository, Database, Journal, SettingsRepository, TaskRepository};
+use crate::database::taskwarrior;
+use crate::interop::backup::{Backup, ImportMode};
+use crate::interop::caldav::{self, CalDavSyncReport};
+use crate::interop::csv::{self as csv_format, CsvColumnMapping, CsvImportReport, CsvPreview};
+use crate::interop::ical;
+use crate::interop::markdown::{self, MarkdownSyncReport};
+use crate::interop::org;
+use crate::interop::services::{self, ServiceImportReport, ServiceTask};
+use crate::interop::{google_tasks, microsoft_todo, todoist};
+use crate::interop::todotxt::{self, TodoTxtSyncReport};
+use crate::interop::{ExportSummary, ImportSummary};
+use crate::models::{CalDavAccount, EntityState, TaskFilter};
+
+/// Record an import as a single undoable operation. `before` holds the prior
+/// state of any existing rows the import may have updated.
+fn record_import(journal: &Journal, label: &str, before: Vec<EntityState>, summary: &ImportSummary) -> Result<(), String> {
+    let mut after = journal.capture_categories(&summary.category_ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    after.extend(journal.capture_tasks(&summary.task_ids).map_err(|e| format!("Failed to record history: {}", e))?);
+    journal.record(label, before, after).map_err(|e| format!("Failed to record history: {}", e))
+}
+
+/// Write every category, task and reminder to a versioned JSON backup.
+#[tauri::command]
+pub async fn export_all(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+) -> Result<ExportSummary, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let backup = Backup::export(&db_lock.connection).map_err(|e| format!("Failed to export data: {}", e))?;
+    let json = backup.to_json().map_err(|e| format!("Failed to export data: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    
+    Ok(backup.summary())
+}
+
+/// Read a JSON backup written by `export_all`. Nothing is written unless the
+/// whole document is valid.
+#[tauri::command]
+pub async fn import_all(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+    mode: ImportMode,
+) -> Result<ImportSummary, String> {
+    let json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let backup = Backup::from_json(&json).map_err(|e| format!("Failed to import backup: {}", e))?;
+    
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let journal = Journal::new(&db_lock.connection);
+    
+    let summary = backup.import(&db_lock.connection, mode).map_err(|e| format!("Failed to import backup: {}", e))?;
+    
+    // A replace clears the history, so only a merge can be undone
+    if mode == ImportMode::Merge {
+        record_import(&journal, "Import backup", vec![], &summary)?;
+    }
+    
+    Ok(summary)
+}
+
+/// Write tasks matching `filter` to an iCalendar file as VTODO components.
+#[tauri::command]
+pub async fn export_ics(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+    filter: Option<TaskFilter>,
+) -> Result<ExportSummary, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    let category_repo = CategoryRepository::new(&db_lock.connection);
+    
+    let tasks = task_repo.get_all(filter).map_err(|e| format!("Failed to get tasks: {}", e))?;
+    let categories = category_repo.get_all().map_err(|e| format!("Failed to get categories: {}", e))?;
+    
+    std::fs::write(&path, ical::export_ics(&tasks, &categories)).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    
+    Ok(ExportSummary { tasks: tasks.len(), categories: 0 })
+}
+
+/// Import the VTODO and VEVENT items of an iCalendar file. Items whose UID
+/// matches an existing task update it.
+#[tauri::command]
+pub async fn import_ics(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+) -> Result<ImportSummary, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let items = ical::parse_ics(&text).map_err(|e| format!("Failed to import calendar: {}", e))?;
+    
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let journal = Journal::new(&db_lock.connection);
+    
+    let uids: Vec<String> = items.iter().filter_map(|item| item.uid()).collect();
+    let before = journal.capture_tasks(&uids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let summary = ical::import_items(&db_lock.connection, &items).map_err(|e| format!("Failed to import calendar: {}", e))?;
+    record_import(&journal, "Import calendar", before, &summary)?;
+    
+    Ok(summary)
+}
+
+/// Write tasks matching `filter` to a CSV file with a header row.
+#[tauri::command]
+pub async fn export_csv(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+    filter: Option<TaskFilter>,
+) -> Result<ExportSummary, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let (text, count) = csv_format::export_csv(&db_lock.connection, filter).map_err(|e| format!("Failed to export CSV: {}", e))?;
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    
+    Ok(ExportSummary { tasks: count, categories: 0 })
+}
+
+/// Read the header and first rows of a CSV file and suggest a column mapping.
+#[tauri::command]
+pub async fn preview_csv(path: String) -> Result<CsvPreview, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    csv_format::preview_csv(&text).map_err(|e| format!("Failed to read CSV: {}", e))
+}
+
+/// Import a CSV file using `mapping`. With `dry_run` every row is validated
+/// and the problems reported, but nothing is written.
+#[tauri::command]
+pub async fn import_csv(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+    mapping: CsvColumnMapping,
+    dry_run: bool,
+) -> Result<CsvImportReport, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let journal = Journal::new(&db_lock.connection);
+    
+    let report = csv_format::import_csv(&db_lock.connection, &text, &mapping, dry_run).map_err(|e| format!("Failed to import CSV: {}", e))?;
+    if !dry_run {
+        record_import(&journal, "Import CSV", vec![], &report.summary)?;
+    }
+    
+    Ok(report)
+}
+
+/// Write tasks matching `filter` to a todo.txt file, one line per task.
+#[tauri::command]
+pub async fn export_todotxt(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+    filter: Option<TaskFilter>,
+) -> Result<ExportSummary, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    let category_repo = CategoryRepository::new(&db_lock.connection);
+    
+    let tasks = task_repo.get_all(filter).map_err(|e| format!("Failed to get tasks: {}", e))?;
+    let categories = category_repo.get_all().map_err(|e| format!("Failed to get categories: {}", e))?;
+    
+    std::fs::write(&path, todotxt::export_todotxt(&tasks, &categories)).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    
+    Ok(ExportSummary { tasks: tasks.len(), categories: 0 })
+}
+
+/// Import the lines of a todo.txt file. Lines whose `id:` matches an
+/// existing task update it.
+#[tauri::command]
+pub async fn import_todotxt(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+) -> Result<ImportSummary, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let ids: Vec<String> = text
+        .lines()
+        .filter_map(todotxt::TodoTxtItem::parse)
+        .filter_map(|item| item.id)
+        .collect();
+    
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let journal = Journal::new(&db_lock.connection);
+    let before = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let summary = todotxt::import_todotxt(&db_lock.connection, &text).map_err(|e| format!("Failed to import todo.txt: {}", e))?;
+    record_import(&journal, "Import todo.txt", before, &summary)?;
+    
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn get_todotxt_sync_path(
+    db: State<'_, Mutex<Database>>,
+) -> Result<Option<String>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let settings_repo = SettingsRepository::new(&db_lock.connection);
+    
+    settings_repo.todotxt_sync_path().map_err(|e| format!("Failed to get todo.txt sync settings: {}", e))
+}
+
+/// Choose the todo.txt file to keep in sync, or turn sync off with `None`.
+/// A newly chosen file is synced straight away.
+#[tauri::command]
+pub async fn set_todotxt_sync_path(
+    db: State<'_, Mutex<Database>>,
+    path: Option<String>,
+) -> Result<Option<TodoTxtSyncReport>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let settings_repo = SettingsRepository::new(&db_lock.connection);
+    
+    settings_repo.set_todotxt_sync_path(path.as_deref()).map_err(|e| format!("Failed to set todo.txt sync settings: {}", e))?;
+    
+    todotxt::sync_configured(&db_lock.connection).map_err(|e| format!("Failed to sync todo.txt: {}", e))
+}
+
+/// Sync with the chosen todo.txt file now.
+#[tauri::command]
+pub async fn sync_todotxt(
+    db: State<'_, Mutex<Database>>,
+) -> Result<TodoTxtSyncReport, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    todotxt::sync_configured(&db_lock.connection)
+        .map_err(|e| format!("Failed to sync todo.txt: {}", e))?
+        .ok_or_else(|| "No todo.txt file is set up for syncing".to_string())
+}
+
+#[tauri::command]
+pub async fn get_markdown_vault_path(
+    db: State<'_, Mutex<Database>>,
+) -> Result<Option<String>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let settings_repo = SettingsRepository::new(&db_lock.connection);
+    
+    settings_repo.markdown_vault_path().map_err(|e| format!("Failed to get vault sync settings: {}", e))
+}
+
+/// Choose the Markdown vault whose checkboxes become tasks, or turn sync off
+/// with `None`. A newly chosen vault is synced straight away.
+#[tauri::command]
+pub async fn set_markdown_vault_path(
+    db: State<'_, Mutex<Database>>,
+    path: Option<String>,
+) -> Result<Option<MarkdownSyncReport>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let settings_repo = SettingsRepository::new(&db_lock.connection);
+    
+    settings_repo.set_markdown_vault_path(path.as_deref()).map_err(|e| format!("Failed to set vault sync settings: {}", e))?;
+    
+    markdown::sync_configured(&db_lock.connection).map_err(|e| format!("Failed to sync Markdown vault: {}", e))
+}
+
+/// Sync with the chosen Markdown vault now.
+#[tauri::command]
+pub async fn sync_markdown_vault(
+    db: State<'_, Mutex<Database>>,
+) -> Result<MarkdownSyncReport, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    markdown::sync_configured(&db_lock.connection)
+        .map_err(|e| format!("Failed to sync Markdown vault: {}", e))?
+        .ok_or_else(|| "No Markdown vault is set up for syncing".to_string())
+}
+
+/// Write tasks matching `filter` as JSON that `task import` accepts.
+#[tauri::command]
+pub async fn export_taskwarrior(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+    filter: Option<TaskFilter>,
+) -> Result<ExportSummary, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let (json, count) = taskwarrior::export_taskwarrior(&db_lock.connection, filter).map_err(|e| format!("Failed to export Taskwarrior tasks: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    
+    Ok(ExportSummary { tasks: count, categories: 0 })
+}
+
+/// Import the output of `task export`. Tasks whose UUID matches an existing
+/// task update it.
+#[tauri::command]
+pub async fn import_taskwarrior(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+) -> Result<ImportSummary, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let items = taskwarrior::parse_taskwarrior(&text).map_err(|e| format!("Failed to import Taskwarrior tasks: {}", e))?;
+    
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let journal = Journal::new(&db_lock.connection);
+    
+    let uuids: Vec<String> = items.iter().map(|item| item.uuid.clone()).collect();
+    let before = journal.capture_tasks(&uuids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let summary = taskwarrior::import_taskwarrior(&db_lock.connection, &items).map_err(|e| format!("Failed to import Taskwarrior tasks: {}", e))?;
+    record_import(&journal, "Import Taskwarrior tasks", before, &summary)?;
+    
+    Ok(summary)
+}
+
+/// Write tasks matching `filter` to an Org file, subtasks nested under their
+/// parent.
+#[tauri::command]
+pub async fn export_org(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+    filter: Option<TaskFilter>,
+) -> Result<ExportSummary, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    let category_repo = CategoryRepository::new(&db_lock.connection);
+    
+    let tasks = task_repo.get_all(filter).map_err(|e| format!("Failed to get tasks: {}", e))?;
+    let categories = category_repo.get_all().map_err(|e| format!("Failed to get categories: {}", e))?;
+    
+    std::fs::write(&path, org::export_org(&tasks, &categories)).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    
+    Ok(ExportSummary { tasks: tasks.len(), categories: 0 })
+}
+
+/// Import the TODO/DONE headlines of an Org file. Headlines whose PLUTO_ID
+/// property matches an existing task update it.
+#[tauri::command]
+pub async fn import_org(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+) -> Result<ImportSummary, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let headlines = org::parse_org(&text);
+    
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let journal = Journal::new(&db_lock.connection);
+    
+    let ids: Vec<String> = headlines.iter().filter_map(|h| h.id()).collect();
+    let before = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let summary = org::import_org(&db_lock.connection, &headlines).map_err(|e| format!("Failed to import Org file: {}", e))?;
+    record_import(&journal, "Import Org file", before, &summary)?;
+    
+    Ok(summary)
+}
+
+/// Write parsed tasks from another app and record them as one undoable
+/// operation. Imported tasks are always new, so there is no prior state.
+fn import_service(
+    db: &State<'_, Mutex<Database>>,
+    tasks: Vec<ServiceTask>,
+    mut report: ServiceImportReport,
+) -> Result<ServiceImportReport, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let journal = Journal::new(&db_lock.connection);
+    
+    services::import_service_tasks(&db_lock.connection, tasks, &mut report)
+        .map_err(|e| format!("Failed to import from {}: {}", report.source, e))?;
+    record_import(&journal, &format!("Import from {}", report.source), Vec::new(), &report.summary)?;
+    
+    Ok(report)
+}
+
+/// Import a Todoist JSON backup, a project CSV file, or a folder of CSV
+/// files from an unpacked backup archive.
+#[tauri::command]
+pub async fn import_todoist(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+) -> Result<ServiceImportReport, String> {
+    let mut report = ServiceImportReport::new(todoist::TODOIST_SOURCE);
+    let now = chrono::Local::now().naive_local();
+    let tasks = todoist::read_todoist_path(Path::new(&path), now, &mut report)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    
+    import_service(&db, tasks, report)
+}
+
+/// Import lists exported from Microsoft To Do as Graph API JSON.
+#[tauri::command]
+pub async fn import_microsoft_todo(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+) -> Result<ServiceImportReport, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mut report = ServiceImportReport::new(microsoft_todo::MICROSOFT_TODO_SOURCE);
+    let tasks = microsoft_todo::parse_microsoft_todo(&text, &mut report)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    
+    import_service(&db, tasks, report)
+}
+
+/// Import `Tasks.json` from a Google Takeout archive.
+#[tauri::command]
+pub async fn import_google_tasks(
+    db: State<'_, Mutex<Database>>,
+    path: String,
+) -> Result<ServiceImportReport, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mut report = ServiceImportReport::new(google_tasks::GOOGLE_TASKS_SOURCE);
+    let tasks = google_tasks::parse_google_tasks(&text, &mut report)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    
+    import_service(&db, tasks, report)
+}
+
+/// The CalDAV account tasks are synced with, without its password.
+#[tauri::command]
+pub async fn get_caldav_account(
+    db: State<'_, Mutex<Database>>,
+) -> Result<Option<CalDavAccount>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let settings_repo = SettingsRepository::new(&db_lock.connection);
+    
+    settings_repo.caldav_account().map_err(|e| format!("Failed to get CalDAV settings: {}", e))
+}
+
+/// Set up the CalDAV account to sync with, or turn sync off with `None`. An
+/// empty password keeps the stored one, since it is never sent to the
+/// frontend. The account is synced straight away.
+///
+/// The password is stored as plain text in the app database, like the API
+/// token: anyone who can read that file can read it. Backups and exports
+/// never include settings, so it does not leave the computer with them.
+#[tauri::command]
+pub async fn set_caldav_account(
+    app: AppHandle,
+    db: State<'_, Mutex<Database>>,
+    account: Option<CalDavAccount>,
+) -> Result<Option<CalDavSyncReport>, String> {
+    {
+        let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        let settings_repo = SettingsRepository::new(&db_lock.connection);
+        
+        let mut account = account;
+        if let Some(ref mut account) = account {
+            if account.password.is_empty() {
+                let current = settings_repo.caldav_account().map_err(|e| format!("Failed to get CalDAV settings: {}", e))?;
+                if let Some(current) = current.filter(|c| c.url == account.url && c.username == account.username) {
+                    account.password = current.password;
+                }
+            }
+        }
+        settings_repo.set_caldav_account(account.as_ref()).map_err(|e| format!("Failed to set CalDAV settings: {}", e))?;
+    }
+    
+    run_caldav_sync(app).await
+}
+
+/// Sync with the CalDAV server now.
+#[tauri::command]
+pub async fn sync_caldav(
+    app: AppHandle,
+) -> Result<CalDavSyncReport, String> {
+    run_caldav_sync(app)
+        .await?
+        .ok_or_else(|| "No CalDAV account is set up for syncing".to_string())
+}
+
+/// The sync waits on the server, so it runs on a blocking thread; it only
+/// locks the database between requests.
+async fn run_caldav_sync(app: AppHandle) -> Result<Option<CalDavSyncReport>, String> {
+    tauri::async_runtime::spawn_blocking(move || caldav::sync_configured(&app.state::<Mutex<Database>>()))
+        .await
+        .map_err(|e| format!("Failed to sync with CalDAV server: {}", e))?
+        .map_err(|e| format!("Failed to sync with CalDAV server: {}", e))
+}
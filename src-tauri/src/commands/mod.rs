@@ -1,5 +1,17 @@
 pub mod task_commands;
 pub mod category_commands;
+pub mod reminder_commands;
+pub mod dependency_commands;
+pub mod history_commands;
+pub mod trash_commands;
+pub mod interop_commands;
+pub mod api_commands;
 
 pub use task_commands::*;
 pub use category_commands::*;
+pub use reminder_commands::*;
+pub use dependency_commands::*;
+pub use history_commands::*;
+pub use trash_commands::*;
+pub use interop_commands::*;
+pub use api_commands::*;
@@ -0,0 +1,77 @@
+use std::sync::Mutex;
+use tauri::State;
+use crate::database::{Database, Journal, ReminderRepository, TaskRepository};
+use crate::models::{Reminder, ReminderOffset};
+
+#[tauri::command]
+pub async fn create_reminder(
+    db: State<'_, Mutex<Database>>,
+    task_id: String,
+    offset: ReminderOffset,
+) -> Result<Reminder, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    let reminder_repo = ReminderRepository::new(&db_lock.connection);
+    
+    task_repo
+        .get_by_id(&task_id)
+        .map_err(|e| format!("Failed to get task: {}", e))?
+        .ok_or_else(|| "Task not found".to_string())?;
+    
+    match offset {
+        ReminderOffset::BeforeDue { minutes } if minutes < 0 => {
+            return Err("Reminder offset must not be negative".to_string());
+        }
+        ReminderOffset::OnDueDay { hour, minute } if hour > 23 || minute > 59 => {
+            return Err("Reminder time must be a valid time of day".to_string());
+        }
+        _ => {}
+    }
+    
+    let journal = Journal::new(&db_lock.connection);
+    let before = journal.capture_tasks(std::slice::from_ref(&task_id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let reminder = Reminder::new(task_id.clone(), offset);
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to create reminder: {}", e))?;
+    reminder_repo.create(&reminder).map_err(|e| format!("Failed to create reminder: {}", e))?;
+    
+    let after = journal.capture_tasks(&[task_id]).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Add reminder", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to create reminder: {}", e))?;
+    
+    Ok(reminder)
+}
+
+#[tauri::command]
+pub async fn get_task_reminders(
+    db: State<'_, Mutex<Database>>,
+    task_id: String,
+) -> Result<Vec<Reminder>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let reminder_repo = ReminderRepository::new(&db_lock.connection);
+    
+    reminder_repo.get_for_task(&task_id).map_err(|e| format!("Failed to get reminders: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_reminder(
+    db: State<'_, Mutex<Database>>,
+    id: String,
+) -> Result<(), String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let reminder_repo = ReminderRepository::new(&db_lock.connection);
+    let journal = Journal::new(&db_lock.connection);
+    
+    let reminder = reminder_repo
+        .get_by_id(&id)
+        .map_err(|e| format!("Failed to get reminder: {}", e))?
+        .ok_or_else(|| "Reminder not found".to_string())?;
+    let before = journal.capture_tasks(std::slice::from_ref(&reminder.task_id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to delete reminder: {}", e))?;
+    reminder_repo.delete(&id).map_err(|e| format!("Failed to delete reminder: {}", e))?;
+    
+    let after = journal.capture_tasks(&[reminder.task_id]).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Remove reminder", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to delete reminder: {}", e))
+}
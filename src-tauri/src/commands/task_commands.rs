@@ -1,7 +1,10 @@
+use std::collections::HashMap;
 use std::sync::Mutex;
 use tauri::State;
-use crate::database::{Database, TaskRepository};
-use crate::models::{Task, CreateTaskRequest, UpdateTaskRequest, TaskFilter};
+use crate::database::{atomically, CategoryRepository, Database, Journal, TaskRepository};
+use crate::database::filter_query::{FilterQuery, FilterQueryError};
+use crate::models::{Task, TaskNode, CreateTaskRequest, UpdateTaskRequest, TaskFilter, TaskSearchResult, QuickAddPreview, QuickAddResult, ToggleCompletionResult, TaskSort, TaskCursor, TaskPage, TaskCounts, MAX_PAGE_SIZE, BulkResult};
+use crate::utils::AppError;
 
 // Task command handlers for the Tauri application
 
@@ -13,21 +16,95 @@ pub async fn create_task(
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let task_repo = TaskRepository::new(&db_lock.connection);
     
-    let task = Task::new(request);
+    let journal = Journal::new(&db_lock.connection);
+    
+    let task = Task::new(request)?;
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to create task: {}", e))?;
     task_repo.create(&task).map_err(|e| format!("Failed to create task: {}", e))?;
     
+    let after = journal.capture_tasks(std::slice::from_ref(&task.id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Create task", vec![], after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to create task: {}", e))?;
+    
     Ok(task)
 }
 
+/// Show how a quick-add line would be parsed, without creating anything.
+#[tauri::command]
+pub async fn preview_quick_add(
+    db: State<'_, Mutex<Database>>,
+    input: String,
+) -> Result<QuickAddPreview, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let category_repo = CategoryRepository::new(&db_lock.connection);
+    
+    let categories = category_repo.get_all().map_err(|e| format!("Failed to get categories: {}", e))?;
+    Ok(QuickAddPreview::parse(&input, chrono::Local::now().naive_local(), &categories))
+}
+
+/// Create a task from a single line such as
+/// "Pay rent every month on the 1st !high #finance @Personal tomorrow 9am".
+#[tauri::command]
+pub async fn quick_add_task(
+    db: State<'_, Mutex<Database>>,
+    input: String,
+) -> Result<QuickAddResult, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    let category_repo = CategoryRepository::new(&db_lock.connection);
+    let journal = Journal::new(&db_lock.connection);
+    
+    let categories = category_repo.get_all().map_err(|e| format!("Failed to get categories: {}", e))?;
+    let preview = QuickAddPreview::parse(&input, chrono::Local::now().naive_local(), &categories);
+    if preview.title.trim().is_empty() {
+        return Err("Task title cannot be empty".to_string());
+    }
+    
+    let task = Task::new(preview.to_create_request())?;
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to create task: {}", e))?;
+    task_repo.create(&task).map_err(|e| format!("Failed to create task: {}", e))?;
+    
+    let after = journal.capture_tasks(std::slice::from_ref(&task.id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Create task", vec![], after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to create task: {}", e))?;
+    
+    Ok(QuickAddResult { task, preview })
+}
+
+/// One page of tasks in `sort` order, manual order by default. Without a
+/// `limit` every matching task is returned.
 #[tauri::command]
 pub async fn get_tasks(
     db: State<'_, Mutex<Database>>,
     filter: Option<TaskFilter>,
-) -> Result<Vec<Task>, String> {
+    sort: Option<TaskSort>,
+    limit: Option<usize>,
+    cursor: Option<String>,
+) -> Result<TaskPage, String> {
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let task_repo = TaskRepository::new(&db_lock.connection);
     
-    task_repo.get_all(filter).map_err(|e| format!("Failed to get tasks: {}", e))
+    let sort = sort.unwrap_or_default();
+    let cursor = cursor
+        .map(|text| TaskCursor::decode(&text, &sort))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let limit = limit.map(|limit| limit.clamp(1, MAX_PAGE_SIZE));
+    
+    task_repo
+        .get_page(filter, None, sort, limit, cursor.as_ref())
+        .map_err(|e| format!("Failed to get tasks: {}", e))
+}
+
+/// How many tasks each sidebar view holds, without loading the lists.
+#[tauri::command]
+pub async fn get_task_counts(
+    db: State<'_, Mutex<Database>>,
+) -> Result<TaskCounts, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    task_repo.get_counts().map_err(|e| format!("Failed to count tasks: {}", e))
 }
 
 #[tauri::command]
@@ -55,13 +132,89 @@ pub async fn update_task(
         .map_err(|e| format!("Failed to get task: {}", e))?
         .ok_or_else(|| "Task not found".to_string())?;
     
-    task.update(request);
+    let journal = Journal::new(&db_lock.connection);
+    let before = journal.capture_tasks(std::slice::from_ref(&id)).map_err(|e| format!("Failed to record history: {}", e))?;
     
+    task.update(request)?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to update task: {}", e))?;
     task_repo.update(&task).map_err(|e| format!("Failed to update task: {}", e))?;
     
+    let after = journal.capture_tasks(&[id]).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Edit task", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to update task: {}", e))?;
+    
     Ok(task)
 }
 
+/// Move a task under `parent_id` (`None` for the top level) and place it
+/// directly before `before_id` or after `after_id`; with neither it goes last.
+/// The anchor has to be in the list the task is moved into: same parent and
+/// same category.
+#[tauri::command]
+pub async fn move_task(
+    db: State<'_, Mutex<Database>>,
+    id: String,
+    parent_id: Option<String>,
+    before_id: Option<String>,
+    after_id: Option<String>,
+) -> Result<Task, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    if before_id.is_some() && after_id.is_some() {
+        return Err("Give either a task to move before or one to move after, not both".to_string());
+    }
+    let task = task_repo
+        .get_by_id(&id)
+        .map_err(|e| format!("Failed to get task: {}", e))?
+        .ok_or_else(|| "Task not found".to_string())?;
+    
+    if let Some(parent_id) = &parent_id {
+        task_repo
+            .get_by_id(parent_id)
+            .map_err(|e| format!("Failed to get task: {}", e))?
+            .ok_or_else(|| "Parent task not found".to_string())?;
+        let subtree = task_repo.subtree_ids(&id).map_err(|e| format!("Failed to get subtasks: {}", e))?;
+        if subtree.contains(parent_id) {
+            return Err("A task cannot be moved under itself or one of its subtasks".to_string());
+        }
+    }
+    if let Some(anchor_id) = before_id.as_ref().or(after_id.as_ref()) {
+        let anchor = task_repo
+            .get_by_id(anchor_id)
+            .map_err(|e| format!("Failed to get task: {}", e))?
+            .ok_or_else(|| "Anchor task not found".to_string())?;
+        if anchor.id == id || anchor.parent_id != parent_id || anchor.category_id != task.category_id {
+            return Err("The anchor task must be another task in the list being moved into".to_string());
+        }
+    }
+    
+    // The moved task and the list it joins, which may get renumbered
+    let mut ids = vec![id.clone()];
+    ids.extend(
+        task_repo
+            .sibling_ids(parent_id.as_deref(), task.category_id.as_deref())
+            .map_err(|e| format!("Failed to get tasks: {}", e))?,
+    );
+    
+    let journal = Journal::new(&db_lock.connection);
+    let before = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to move task: {}", e))?;
+    task_repo
+        .move_task(&id, parent_id.as_deref(), before_id.as_deref(), after_id.as_deref())
+        .map_err(|e| format!("Failed to move task: {}", e))?;
+    let after = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Move task", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to move task: {}", e))?;
+    
+    task_repo
+        .get_by_id(&id)
+        .map_err(|e| format!("Failed to get task: {}", e))?
+        .ok_or_else(|| "Task not found".to_string())
+}
+
 #[tauri::command]
 pub async fn delete_task(
     db: State<'_, Mutex<Database>>,
@@ -70,7 +223,24 @@ pub async fn delete_task(
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let task_repo = TaskRepository::new(&db_lock.connection);
     
-    task_repo.delete(&id).map_err(|e| format!("Failed to delete task: {}", e))
+    let journal = Journal::new(&db_lock.connection);
+    // Subtasks move up to the task's parent, so they change too
+    let mut ids = vec![id.clone()];
+    ids.extend(
+        task_repo
+            .get_direct_subtasks(&id)
+            .map_err(|e| format!("Failed to get subtasks: {}", e))?
+            .into_iter()
+            .map(|t| t.id),
+    );
+    let before = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to delete task: {}", e))?;
+    task_repo.delete(&id).map_err(|e| format!("Failed to delete task: {}", e))?;
+    
+    let after = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Delete task", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to delete task: {}", e))
 }
 
 #[tauri::command]
@@ -81,7 +251,15 @@ pub async fn delete_task_with_subtasks(
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let task_repo = TaskRepository::new(&db_lock.connection);
     
-    task_repo.delete_task_and_subtasks(&id).map_err(|e| format!("Failed to delete task with subtasks: {}", e))
+    let journal = Journal::new(&db_lock.connection);
+    let ids = task_repo.subtree_ids(&id).map_err(|e| format!("Failed to get subtasks: {}", e))?;
+    let before = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to delete task with subtasks: {}", e))?;
+    task_repo.delete_task_and_subtasks(&id).map_err(|e| format!("Failed to delete task with subtasks: {}", e))?;
+    
+    journal.record("Delete task with subtasks", before, vec![]).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to delete task with subtasks: {}", e))
 }
 
 #[tauri::command]
@@ -92,7 +270,23 @@ pub async fn delete_task_and_promote_subtasks(
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let task_repo = TaskRepository::new(&db_lock.connection);
     
-    task_repo.delete_task_and_promote_subtasks(&id).map_err(|e| format!("Failed to delete task and promote subtasks: {}", e))
+    let journal = Journal::new(&db_lock.connection);
+    let mut ids = vec![id.clone()];
+    ids.extend(
+        task_repo
+            .get_direct_subtasks(&id)
+            .map_err(|e| format!("Failed to get subtasks: {}", e))?
+            .into_iter()
+            .map(|t| t.id),
+    );
+    let before = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to delete task and promote subtasks: {}", e))?;
+    task_repo.delete_task_and_promote_subtasks(&id).map_err(|e| format!("Failed to delete task and promote subtasks: {}", e))?;
+    
+    let after = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Delete task", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to delete task and promote subtasks: {}", e))
 }
 
 #[tauri::command]
@@ -110,6 +304,43 @@ pub async fn check_task_has_subtasks(
 pub async fn toggle_task_completion(
     db: State<'_, Mutex<Database>>,
     id: String,
+) -> Result<ToggleCompletionResult, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    let mut task = task_repo
+        .get_by_id(&id)
+        .map_err(|e| format!("Failed to get task: {}", e))?
+        .ok_or_else(|| "Task not found".to_string())?;
+    
+    let journal = Journal::new(&db_lock.connection);
+    let before = journal.capture_tasks(std::slice::from_ref(&id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    let mut changed_ids = vec![id];
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to toggle task: {}", e))?;
+    let completed = !task.completed;
+    changed_ids.extend(task_repo.set_completed(&mut task, completed).map_err(|e| format!("Failed to toggle task: {}", e))?);
+    
+    let label = if task.completed { "Complete task" } else { "Reopen task" };
+    let after = journal.capture_tasks(&changed_ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record(label, before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to toggle task: {}", e))?;
+    
+    // Completing a task that is still waiting on others is allowed, with a warning
+    let open_blockers = if task.completed {
+        let blockers = task_repo.get_blockers(&task.id).map_err(|e| format!("Failed to get dependencies: {}", e))?;
+        blockers.into_iter().filter(|t| !t.completed).collect()
+    } else {
+        vec![]
+    };
+    
+    Ok(ToggleCompletionResult::new(task, &open_blockers))
+}
+
+#[tauri::command]
+pub async fn skip_task_occurrence(
+    db: State<'_, Mutex<Database>>,
+    id: String,
 ) -> Result<Task, String> {
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let task_repo = TaskRepository::new(&db_lock.connection);
@@ -119,10 +350,25 @@ pub async fn toggle_task_completion(
         .map_err(|e| format!("Failed to get task: {}", e))?
         .ok_or_else(|| "Task not found".to_string())?;
     
-    task.completed = !task.completed;
+    let rule = task.recurrence.clone().ok_or_else(|| "Task does not recur".to_string())?;
+    let current = task.due_date.unwrap_or_else(|| chrono::Utc::now().naive_utc());
+    let next_due = rule
+        .next_after(current)
+        .ok_or_else(|| "No further occurrences in this series".to_string())?;
+    
+    let journal = Journal::new(&db_lock.connection);
+    let before = journal.capture_tasks(std::slice::from_ref(&id)).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    task.due_date = Some(next_due);
+    task.recurrence = Some(rule.advanced(current));
     task.updated_at = chrono::Utc::now().naive_utc();
     
-    task_repo.update(&task).map_err(|e| format!("Failed to toggle task: {}", e))?;
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to skip occurrence: {}", e))?;
+    task_repo.update(&task).map_err(|e| format!("Failed to skip occurrence: {}", e))?;
+    
+    let after = journal.capture_tasks(&[id]).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Skip occurrence", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to skip occurrence: {}", e))?;
     
     Ok(task)
 }
@@ -131,22 +377,36 @@ pub async fn toggle_task_completion(
 pub async fn search_tasks(
     db: State<'_, Mutex<Database>>,
     query: String,
-) -> Result<Vec<Task>, String> {
+    limit: Option<usize>,
+) -> Result<Vec<TaskSearchResult>, String> {
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let task_repo = TaskRepository::new(&db_lock.connection);
     
-    let filter = TaskFilter {
-        completed: None,
-        priority: None,
-        category_id: None,
-        parent_id: None,
-        search_query: Some(query),
-        due_before: None,
-        due_after: None,
-        no_category: None,
-    };
+    task_repo.search(&query, limit.unwrap_or(100)).map_err(|e| format!("Failed to search tasks: {}", e))
+}
+
+/// Run a filter query such as `priority:high #client-a due:<2026-11-01 -done`,
+/// optionally combined with a structured filter.
+#[tauri::command]
+pub async fn query_tasks(
+    db: State<'_, Mutex<Database>>,
+    query: String,
+    filter: Option<TaskFilter>,
+) -> Result<Vec<Task>, String> {
+    let parsed = FilterQuery::parse(&query).map_err(|e| format!("Invalid filter query: {}", e))?;
+    let clause = parsed.map(|q| q.to_sql());
+    
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
     
-    task_repo.get_all(Some(filter)).map_err(|e| format!("Failed to search tasks: {}", e))
+    task_repo.get_all_matching(filter, clause.as_ref()).map_err(|e| format!("Failed to query tasks: {}", e))
+}
+
+/// Check a filter query without running it. Returns the parse error, with
+/// its position, or `None` if the query is valid.
+#[tauri::command]
+pub async fn validate_filter_query(query: String) -> Result<Option<FilterQueryError>, String> {
+    Ok(FilterQuery::parse(&query).err())
 }
 
 #[tauri::command]
@@ -194,15 +454,17 @@ pub async fn get_subtasks(
     task_repo.get_all(Some(filter)).map_err(|e| format!("Failed to get subtasks: {}", e))
 }
 
+/// Every task below `root_id` (all tasks without one), parents before their
+/// children, each with its depth and path.
 #[tauri::command]
 pub async fn get_task_hierarchy(
     db: State<'_, Mutex<Database>>,
     root_id: Option<String>,
-) -> Result<Vec<Task>, String> {
+) -> Result<Vec<TaskNode>, String> {
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let task_repo = TaskRepository::new(&db_lock.connection);
     
-    task_repo.get_task_hierarchy(root_id).map_err(|e| format!("Failed to get task hierarchy: {}", e))
+    task_repo.get_subtree(root_id.as_deref()).map_err(|e| format!("Failed to get task hierarchy: {}", e))
 }
 
 #[tauri::command]
@@ -240,6 +502,19 @@ pub async fn calculate_task_progress(
     task_repo.calculate_task_progress(&id).map_err(|e| format!("Failed to calculate progress: {}", e))
 }
 
+/// Progress for many tasks at once, keyed by task ID, so a list can fetch it
+/// for every parent it shows in one call.
+#[tauri::command]
+pub async fn get_tasks_progress(
+    db: State<'_, Mutex<Database>>,
+    ids: Vec<String>,
+) -> Result<HashMap<String, TaskProgress>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    task_repo.get_tasks_progress(&ids).map_err(|e| format!("Failed to calculate progress: {}", e))
+}
+
 #[tauri::command]
 #[allow(non_snake_case)]
 pub async fn get_incomplete_subtasks(
@@ -275,25 +550,46 @@ pub async fn bulk_mark_subtasks_completed(
     let all_subtasks = task_repo.get_task_hierarchy(Some(parentId))
         .map_err(|e| format!("Failed to get task hierarchy: {}", e))?;
     
+    let subtask_ids: Vec<String> = all_subtasks.iter().map(|t| t.id.clone()).collect();
+    let before_states = Journal::new(&db_lock.connection)
+        .capture_tasks(&subtask_ids)
+        .map_err(|e| format!("Failed to record history: {}", e))?;
+    
     let mut updated_tasks = Vec::new();
+    let mut created_ids = Vec::new();
+    
+    // All or nothing: a failure part way leaves every subtask as it was
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to update subtasks: {}", e))?;
     
-    // Mark all incomplete subtasks as completed
+    // Mark all incomplete subtasks as completed; recurring ones hand their
+    // series on to the next instance
     for mut task in all_subtasks {
         if !task.completed {
-            task.completed = true;
-            task.updated_at = chrono::Utc::now().naive_utc();
-            
-            task_repo.update(&task)
-                .map_err(|e| format!("Failed to update task {}: {}", task.id, e))?;
+            created_ids.extend(
+                task_repo
+                    .set_completed(&mut task, true)
+                    .map_err(|e| format!("Failed to update task {}: {}", task.id, e))?,
+            );
             
             updated_tasks.push(task);
         }
     }
     
+    let journal = Journal::new(&db_lock.connection);
+    let before = before_states
+        .into_iter()
+        .filter(|state| updated_tasks.iter().any(|t| state.key().1 == t.id))
+        .collect();
+    let mut ids: Vec<String> = updated_tasks.iter().map(|t| t.id.clone()).collect();
+    ids.extend(created_ids);
+    let after = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Complete subtasks", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to update subtasks: {}", e))?;
+    
     Ok(updated_tasks)
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
 pub struct TaskProgress {
     pub total_subtasks: i32,
     pub completed_subtasks: i32,
@@ -328,11 +624,20 @@ pub async fn bulk_delete_tasks_with_subtasks(
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let task_repo = TaskRepository::new(&db_lock.connection);
     
+    let journal = Journal::new(&db_lock.connection);
+    let mut affected_ids = Vec::new();
+    for id in &ids {
+        affected_ids.extend(task_repo.subtree_ids(id).map_err(|e| format!("Failed to get subtasks: {}", e))?);
+    }
+    let before = journal.capture_tasks(&affected_ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to delete tasks: {}", e))?;
     for id in ids {
         task_repo.delete_task_and_subtasks(&id).map_err(|e| format!("Failed to delete task with subtasks: {}", e))?;
     }
     
-    Ok(())
+    journal.record("Delete tasks", before, vec![]).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to delete tasks: {}", e))
 }
 
 #[tauri::command]
@@ -343,9 +648,94 @@ pub async fn bulk_delete_tasks_and_promote_subtasks(
     let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     let task_repo = TaskRepository::new(&db_lock.connection);
     
+    let journal = Journal::new(&db_lock.connection);
+    let mut affected_ids = ids.clone();
+    for id in &ids {
+        let children = task_repo.get_direct_subtasks(id).map_err(|e| format!("Failed to get subtasks: {}", e))?;
+        affected_ids.extend(children.into_iter().map(|t| t.id));
+    }
+    let before = journal.capture_tasks(&affected_ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to delete tasks: {}", e))?;
     for id in ids {
         task_repo.delete_task_and_promote_subtasks(&id).map_err(|e| format!("Failed to delete task and promote subtasks: {}", e))?;
     }
     
-    Ok(())
+    let after = journal.capture_tasks(&affected_ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Delete tasks", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to delete tasks: {}", e))
+}
+
+/// Move each of `ids` to the trash, on its own: tasks that cannot be deleted
+/// are reported and the rest still go. Undone as one step.
+#[tauri::command]
+pub async fn bulk_delete_tasks(
+    db: State<'_, Mutex<Database>>,
+    ids: Vec<String>,
+) -> Result<BulkResult, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    let journal = Journal::new(&db_lock.connection);
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to delete tasks: {}", e))?;
+    
+    // Deleting a task moves its subtasks up a level, so they change too
+    let mut affected_ids = ids.clone();
+    for id in &ids {
+        let children = task_repo.get_direct_subtasks(id).map_err(|e| format!("Failed to get subtasks: {}", e))?;
+        affected_ids.extend(children.into_iter().map(|t| t.id));
+    }
+    let before = journal.capture_tasks(&affected_ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let mut result = BulkResult::default();
+    for id in &ids {
+        let outcome = atomically(&db_lock.connection, || -> Result<(), AppError> {
+            task_repo.get_by_id(id)?.ok_or(AppError::TaskNotFound)?;
+            task_repo.delete(id)?;
+            Ok(())
+        });
+        result.add(id.clone(), outcome);
+    }
+    
+    let after = journal.capture_tasks(&affected_ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Delete tasks", before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to delete tasks: {}", e))?;
+    
+    Ok(result)
+}
+
+/// Complete or reopen each of `ids`, on its own: tasks that cannot be
+/// changed are reported and the rest are still updated. Undone as one step.
+#[tauri::command]
+pub async fn bulk_set_tasks_completed(
+    db: State<'_, Mutex<Database>>,
+    ids: Vec<String>,
+    completed: bool,
+) -> Result<BulkResult, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    let journal = Journal::new(&db_lock.connection);
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to update tasks: {}", e))?;
+    let before = journal.capture_tasks(&ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    
+    let mut changed_ids = ids.clone();
+    let mut result = BulkResult::default();
+    for id in &ids {
+        let outcome = atomically(&db_lock.connection, || -> Result<(), AppError> {
+            let mut task = task_repo.get_by_id(id)?.ok_or(AppError::TaskNotFound)?;
+            if task.completed != completed {
+                changed_ids.extend(task_repo.set_completed(&mut task, completed)?);
+            }
+            Ok(())
+        });
+        result.add(id.clone(), outcome);
+    }
+    
+    let label = if completed { "Complete tasks" } else { "Reopen tasks" };
+    let after = journal.capture_tasks(&changed_ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record(label, before, after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to update tasks: {}", e))?;
+    
+    Ok(result)
 }
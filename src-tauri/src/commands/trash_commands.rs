@@ -0,0 +1,80 @@
+use std::sync::Mutex;
+use tauri::State;
+use crate::database::{Database, Journal, SettingsRepository, TaskRepository};
+use crate::models::Task;
+
+#[tauri::command]
+pub async fn list_trash(
+    db: State<'_, Mutex<Database>>,
+) -> Result<Vec<Task>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    task_repo.list_trash().map_err(|e| format!("Failed to list trash: {}", e))
+}
+
+#[tauri::command]
+pub async fn restore_task(
+    db: State<'_, Mutex<Database>>,
+    id: String,
+) -> Result<Vec<Task>, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    let journal = Journal::new(&db_lock.connection);
+    
+    let tx = db_lock.connection.unchecked_transaction().map_err(|e| format!("Failed to restore task: {}", e))?;
+    let restored_ids = task_repo.restore(&id).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => "Task not found in trash".to_string(),
+        e => format!("Failed to restore task: {}", e),
+    })?;
+    
+    // Before the restore none of these rows were visible
+    let after = journal.capture_tasks(&restored_ids).map_err(|e| format!("Failed to record history: {}", e))?;
+    journal.record("Restore task", vec![], after).map_err(|e| format!("Failed to record history: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to restore task: {}", e))?;
+    
+    let mut restored = Vec::new();
+    for id in restored_ids {
+        if let Some(task) = task_repo.get_by_id(&id).map_err(|e| format!("Failed to get task: {}", e))? {
+            restored.push(task);
+        }
+    }
+    
+    Ok(restored)
+}
+
+/// Permanently deletes everything in the trash. This cannot be undone.
+#[tauri::command]
+pub async fn empty_trash(
+    db: State<'_, Mutex<Database>>,
+) -> Result<usize, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    task_repo.empty_trash().map_err(|e| format!("Failed to empty trash: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_trash_retention_days(
+    db: State<'_, Mutex<Database>>,
+) -> Result<u32, String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let settings_repo = SettingsRepository::new(&db_lock.connection);
+    
+    settings_repo.trash_retention_days().map_err(|e| format!("Failed to get retention period: {}", e))
+}
+
+#[tauri::command]
+pub async fn set_trash_retention_days(
+    db: State<'_, Mutex<Database>>,
+    days: u32,
+) -> Result<(), String> {
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let settings_repo = SettingsRepository::new(&db_lock.connection);
+    let task_repo = TaskRepository::new(&db_lock.connection);
+    
+    settings_repo.set_trash_retention_days(days).map_err(|e| format!("Failed to set retention period: {}", e))?;
+    task_repo.purge_expired_trash().map_err(|e| format!("Failed to purge trash: {}", e))?;
+    
+    Ok(())
+}
@@ -1,14 +1,24 @@
-use rusqlite::{Connection, Result};
+use rusqlite::Connection;
 use std::path::Path;
+use std::time::Duration;
 use crate::database::migrations::run_migrations;
+use crate::utils::AppError;
+
+/// File name of the database inside the app data directory
+pub const DATABASE_FILE: &str = "pluto_todo.db";
+
+/// How long to wait for another process (the app or the CLI) to finish
+/// writing before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct Database {
     pub connection: Connection,
 }
 
 impl Database {
-    pub fn new(db_path: &Path) -> Result<Self> {
+    pub fn new(db_path: &Path) -> Result<Self, AppError> {
         let connection = Connection::open(db_path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
         
         // Enable foreign keys
         connection.execute("PRAGMA foreign_keys = ON", [])?;
@@ -21,7 +31,7 @@ impl Database {
         Ok(db)
     }
 
-    pub fn in_memory() -> Result<Self> {
+    pub fn in_memory() -> Result<Self, AppError> {
         let connection = Connection::open_in_memory()?;
         connection.execute("PRAGMA foreign_keys = ON", [])?;
         
@@ -31,3 +41,24 @@ impl Database {
         Ok(db)
     }
 }
+
+/// Run `f` so that either every change it makes is kept or, if it fails,
+/// none is. Uses a savepoint, so it also works inside a transaction or
+/// another `atomically`: a failure then only undoes this part.
+pub fn atomically<T, E>(conn: &Connection, f: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+where
+    E: From<rusqlite::Error>,
+{
+    conn.execute_batch("SAVEPOINT atomically")?;
+    let result = f().and_then(|value| {
+        // Releasing the outermost savepoint commits, which can still fail
+        conn.execute_batch("RELEASE atomically")?;
+        Ok(value)
+    });
+    if result.is_err() {
+        // Nothing more can be done if this fails; the original error is the
+        // one worth reporting
+        let _ = conn.execute_batch("ROLLBACK TO atomically; RELEASE atomically");
+    }
+    result
+}
@@ -0,0 +1,633 @@
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::database::search::to_fts_query;
+use crate::models::Priority;
+
+/// A parse error in a filter query. `position` and `length` are measured in
+/// characters from the start of the query, so the UI can underline the
+/// offending part.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterQueryError {
+    pub message: String,
+    pub position: usize,
+    pub length: usize,
+}
+
+impl fmt::Display for FilterQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for FilterQueryError {}
+
+/// Tasks that depend on at least one task that is neither done nor in the
+/// trash. A task in the trash no longer holds anything up.
+const BLOCKED_TASK_IDS: &str = "SELECT d.task_id FROM task_dependencies d \
+     JOIN tasks blocker ON blocker.id = d.depends_on_id \
+     WHERE blocker.completed = 0 AND blocker.deleted_at IS NULL";
+
+/// A compiled filter: a SQL boolean expression over the `tasks` table and the
+/// values for its `?` placeholders, in order.
+#[derive(Debug, Clone)]
+pub struct SqlClause {
+    pub sql: String,
+    pub params: Vec<Value>,
+}
+
+/// Parsed form of the textual filter language, e.g.
+///
+/// ```text
+/// priority:high #client-a due:<2026-11-01 -done (cat:Work OR cat:Personal)
+/// ```
+///
+/// - terms are ANDed by default; `OR`, `AND`, `NOT`, `-term` and parentheses
+///   combine them (NOT binds tightest, then AND, then OR)
+/// - `#tag` or `tag:name` matches a tag, `cat:Name` / `category:Name` a
+///   category by name (`cat:none` for uncategorised tasks)
+/// - `priority:high|medium|low` (or `p:`) matches a priority
+/// - `due:`, `created:` and `updated:` take a date (`YYYY-MM-DD`, `today`,
+///   `tomorrow`, `yesterday`) with an optional `<`, `<=`, `>`, `>=` prefix;
+///   `due:none` and `due:overdue` are also accepted. Dates are calendar days
+///   in local time, as in quick add; a date-only due date is on its own day
+/// - `done`, `is:done`, `is:open`, `is:recurring`, `is:subtask`, `is:root`,
+///   `is:blocked` (open, with a task it depends on still open) and
+///   `is:unblocked` / `is:ready`
+/// - `has:due`, `has:description`, `has:tags`, `has:subtasks`, `has:reminders`
+/// - anything else, bare or `"quoted"`, is a full-text search term
+///
+/// Values containing spaces can be quoted: `cat:"Client Work"`.
+#[derive(Debug, Clone)]
+pub enum FilterQuery {
+    And(Vec<FilterQuery>),
+    Or(Vec<FilterQuery>),
+    Not(Box<FilterQuery>),
+    Term(FilterTerm),
+}
+
+#[derive(Debug, Clone)]
+pub enum FilterTerm {
+    Text(String),
+    Tag(String),
+    Category(Option<String>),
+    Priority(Priority),
+    Date { field: DateField, bound: DateBound },
+    DueNone,
+    Overdue,
+    Completed,
+    Open,
+    Recurring,
+    Subtask,
+    Root,
+    Blocked,
+    Unblocked,
+    HasDue,
+    HasDescription,
+    HasTags,
+    HasSubtasks,
+    HasReminders,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    Due,
+    Created,
+    Updated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateBound {
+    Before(NaiveDate),
+    OnOrBefore(NaiveDate),
+    After(NaiveDate),
+    OnOrAfter(NaiveDate),
+    On(NaiveDate),
+}
+
+impl FilterQuery {
+    /// Parse a query. An empty (or all-whitespace) query parses to `None`,
+    /// meaning "no additional filtering".
+    pub fn parse(input: &str) -> Result<Option<FilterQuery>, FilterQueryError> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+
+        let mut parser = Parser { tokens, pos: 0, input_len: input.chars().count() };
+        let query = parser.parse_or()?;
+
+        if let Some(token) = parser.peek() {
+            let message = match token.kind {
+                TokenKind::RParen => "Unexpected ')' without a matching '('".to_string(),
+                _ => "Unexpected input".to_string(),
+            };
+            return Err(token.error(message));
+        }
+
+        Ok(Some(query))
+    }
+
+    pub fn to_sql(&self) -> SqlClause {
+        let mut params = Vec::new();
+        let sql = self.compile(&mut params);
+        SqlClause { sql, params }
+    }
+
+    fn compile(&self, params: &mut Vec<Value>) -> String {
+        match self {
+            FilterQuery::And(parts) => {
+                let parts: Vec<String> = parts.iter().map(|p| p.compile(params)).collect();
+                format!("({})", parts.join(" AND "))
+            }
+            FilterQuery::Or(parts) => {
+                let parts: Vec<String> = parts.iter().map(|p| p.compile(params)).collect();
+                format!("({})", parts.join(" OR "))
+            }
+            // A term over a NULL column is NULL, not false, so without the
+            // COALESCE `-cat:Work` would also drop uncategorised tasks
+            FilterQuery::Not(inner) => format!("NOT COALESCE({}, 0)", inner.compile(params)),
+            FilterQuery::Term(term) => term.compile(params),
+        }
+    }
+}
+
+impl FilterTerm {
+    fn compile(&self, params: &mut Vec<Value>) -> String {
+        match self {
+            // A single word or phrase: negation is the filter language's job,
+            // so there is no exclusion here that could fail
+            FilterTerm::Text(text) => match to_fts_query(text) {
+                Ok(Some(fts_query)) => {
+                    params.push(Value::Text(fts_query));
+                    "(id IN (SELECT task_id FROM tasks_fts WHERE tasks_fts MATCH ?))".to_string()
+                }
+                Ok(None) | Err(_) => "(0)".to_string(),
+            },
+            FilterTerm::Tag(tag) => {
+                params.push(Value::Text(tag.clone()));
+                "(id IN (SELECT task_id FROM task_tags WHERE tag = ? COLLATE NOCASE))".to_string()
+            }
+            FilterTerm::Category(Some(name)) => {
+                params.push(Value::Text(name.clone()));
+                "(category_id IN (SELECT id FROM categories WHERE name = ? COLLATE NOCASE))".to_string()
+            }
+            FilterTerm::Category(None) => "(category_id IS NULL)".to_string(),
+            FilterTerm::Priority(priority) => {
+                params.push(Value::Text(priority.to_string()));
+                "(priority = ?)".to_string()
+            }
+            FilterTerm::Date { field, bound } => {
+                let column = match field {
+                    DateField::Due => "due_date",
+                    DateField::Created => "created_at",
+                    DateField::Updated => "updated_at",
+                };
+                let next = |d: &NaiveDate| *d + Duration::days(1);
+                // `column op` the start of a local calendar day
+                let mut compare = |op: &str, day: NaiveDate| {
+                    let mut bind = |dt: NaiveDateTime| params.push(Value::Text(dt.format("%Y-%m-%d %H:%M:%S").to_string()));
+                    let midnight = day.and_time(NaiveTime::MIN);
+                    match field {
+                        // A date-only due date is stored as midnight of the
+                        // day itself, not as an instant
+                        DateField::Due => {
+                            bind(midnight);
+                            bind(local_midnight_utc(day));
+                            format!(
+                                "((time(due_date) = '00:00:00' AND due_date {0} ?) OR (time(due_date) != '00:00:00' AND due_date {0} ?))",
+                                op
+                            )
+                        }
+                        _ => {
+                            bind(local_midnight_utc(day));
+                            format!("{} {} ?", column, op)
+                        }
+                    }
+                };
+
+                match bound {
+                    DateBound::Before(d) => format!("({})", compare("<", *d)),
+                    DateBound::OnOrBefore(d) => format!("({})", compare("<", next(d))),
+                    DateBound::After(d) => format!("({})", compare(">=", next(d))),
+                    DateBound::OnOrAfter(d) => format!("({})", compare(">=", *d)),
+                    DateBound::On(d) => format!("({} AND {})", compare(">=", *d), compare("<", next(d))),
+                }
+            }
+            FilterTerm::DueNone => "(due_date IS NULL)".to_string(),
+            FilterTerm::Overdue => {
+                params.push(Value::Text(Utc::now().naive_utc().format("%Y-%m-%d %H:%M:%S").to_string()));
+                "(due_date < ? AND completed = 0)".to_string()
+            }
+            FilterTerm::Completed => "(completed = 1)".to_string(),
+            FilterTerm::Open => "(completed = 0)".to_string(),
+            FilterTerm::Recurring => "(recurrence IS NOT NULL)".to_string(),
+            FilterTerm::Subtask => "(parent_id IS NOT NULL)".to_string(),
+            FilterTerm::Root => "(parent_id IS NULL)".to_string(),
+            FilterTerm::Blocked => format!("(completed = 0 AND id IN ({}))", BLOCKED_TASK_IDS),
+            FilterTerm::Unblocked => format!("(completed = 0 AND id NOT IN ({}))", BLOCKED_TASK_IDS),
+            FilterTerm::HasDue => "(due_date IS NOT NULL)".to_string(),
+            FilterTerm::HasDescription => "(COALESCE(description, '') != '')".to_string(),
+            FilterTerm::HasTags => "(id IN (SELECT task_id FROM task_tags))".to_string(),
+            FilterTerm::HasSubtasks => {
+                "(id IN (SELECT parent_id FROM tasks WHERE parent_id IS NOT NULL AND deleted_at IS NULL))".to_string()
+            }
+            FilterTerm::HasReminders => "(id IN (SELECT task_id FROM task_reminders))".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    /// A term, with `-` negation already stripped
+    Word { text: String, negated: bool },
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    len: usize,
+}
+
+impl Token {
+    fn error(&self, message: impl Into<String>) -> FilterQueryError {
+        FilterQueryError { message: message.into(), position: self.start, length: self.len.max(1) }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, FilterQueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        match c {
+            '(' => {
+                tokens.push(Token { kind: TokenKind::LParen, start, len: 1 });
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token { kind: TokenKind::RParen, start, len: 1 });
+                i += 1;
+            }
+            '-' if chars.get(i + 1) == Some(&'(') => {
+                tokens.push(Token { kind: TokenKind::Not, start, len: 1 });
+                i += 1;
+            }
+            _ => {
+                let negated = c == '-' && chars.get(i + 1).map(|n| !n.is_whitespace()).unwrap_or(false);
+                if negated {
+                    i += 1;
+                }
+
+                let mut text = String::new();
+                let mut quoted = false;
+                while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '(' && chars[i] != ')' {
+                    if chars[i] == '"' {
+                        let quote_start = i;
+                        i += 1;
+                        while i < chars.len() && chars[i] != '"' {
+                            text.push(chars[i]);
+                            i += 1;
+                        }
+                        if i == chars.len() {
+                            return Err(FilterQueryError {
+                                message: "Unterminated quote".to_string(),
+                                position: quote_start,
+                                length: chars.len() - quote_start,
+                            });
+                        }
+                        quoted = true;
+                    } else {
+                        text.push(chars[i]);
+                    }
+                    i += 1;
+                }
+
+                let kind = match (text.as_str(), quoted, negated) {
+                    ("AND", false, false) => TokenKind::And,
+                    ("OR", false, false) => TokenKind::Or,
+                    ("NOT", false, false) => TokenKind::Not,
+                    // A lone quoted phrase is always a text term
+                    _ if quoted && chars[start + negated as usize] == '"' => TokenKind::Word {
+                        text: format!("\"{}\"", text),
+                        negated,
+                    },
+                    _ => TokenKind::Word { text, negated },
+                };
+                tokens.push(Token { kind, start, len: i - start });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    input_len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn end_error(&self, message: &str) -> FilterQueryError {
+        FilterQueryError { message: message.to_string(), position: self.input_len, length: 1 }
+    }
+
+    fn parse_or(&mut self) -> Result<FilterQuery, FilterQueryError> {
+        let mut parts = vec![self.parse_and()?];
+        while matches!(self.peek().map(|t| &t.kind), Some(TokenKind::Or)) {
+            self.next();
+            parts.push(self.parse_and()?);
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { FilterQuery::Or(parts) })
+    }
+
+    fn parse_and(&mut self) -> Result<FilterQuery, FilterQueryError> {
+        let mut parts = vec![self.parse_unary()?];
+        loop {
+            match self.peek().map(|t| &t.kind) {
+                Some(TokenKind::And) => {
+                    self.next();
+                    parts.push(self.parse_unary()?);
+                }
+                Some(TokenKind::Word { .. }) | Some(TokenKind::LParen) | Some(TokenKind::Not) => {
+                    parts.push(self.parse_unary()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { FilterQuery::And(parts) })
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterQuery, FilterQueryError> {
+        let token = self.next().ok_or_else(|| self.end_error("Expected a search term"))?;
+        match token.kind {
+            TokenKind::Not => Ok(FilterQuery::Not(Box::new(self.parse_unary()?))),
+            TokenKind::LParen => {
+                if matches!(self.peek().map(|t| &t.kind), Some(TokenKind::RParen)) {
+                    return Err(token.error("Empty parentheses"));
+                }
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token { kind: TokenKind::RParen, .. }) => Ok(inner),
+                    _ => Err(token.error("Missing closing ')' for this '('")),
+                }
+            }
+            TokenKind::Word { ref text, negated } => {
+                let term = parse_term(text, &token, negated as usize)?;
+                let term = FilterQuery::Term(term);
+                Ok(if negated { FilterQuery::Not(Box::new(term)) } else { term })
+            }
+            TokenKind::And | TokenKind::Or => {
+                Err(token.error(format!("Expected a search term before '{}'", if token.kind == TokenKind::And { "AND" } else { "OR" })))
+            }
+            TokenKind::RParen => Err(token.error("Unexpected ')'")),
+        }
+    }
+}
+
+/// `offset` is the number of characters before the term text proper (the
+/// leading `-` of a negated term), used to place error positions.
+fn parse_term(text: &str, token: &Token, offset: usize) -> Result<FilterTerm, FilterQueryError> {
+    let value_error = |key: &str, message: String| FilterQueryError {
+        message,
+        position: token.start + offset + key.chars().count() + 1,
+        length: token.len.saturating_sub(offset + key.chars().count() + 1).max(1),
+    };
+
+    if text.starts_with('"') {
+        return Ok(FilterTerm::Text(text.to_string()));
+    }
+    if text == "done" {
+        return Ok(FilterTerm::Completed);
+    }
+    if let Some(tag) = text.strip_prefix('#') {
+        if tag.is_empty() {
+            return Err(token.error("Expected a tag name after '#'"));
+        }
+        return Ok(FilterTerm::Tag(tag.to_string()));
+    }
+
+    let (key, value) = match text.split_once(':') {
+        Some(kv) => kv,
+        None => return Ok(FilterTerm::Text(text.to_string())),
+    };
+    let lower_value = value.to_lowercase();
+
+    if value.is_empty() {
+        return Err(value_error(key, format!("Expected a value after '{}:'", key)));
+    }
+
+    match key.to_lowercase().as_str() {
+        "tag" => Ok(FilterTerm::Tag(value.to_string())),
+        "cat" | "category" => Ok(FilterTerm::Category(if lower_value == "none" { None } else { Some(value.to_string()) })),
+        "priority" | "p" => match lower_value.as_str() {
+            "high" | "h" => Ok(FilterTerm::Priority(Priority::High)),
+            "medium" | "m" => Ok(FilterTerm::Priority(Priority::Medium)),
+            "low" | "l" => Ok(FilterTerm::Priority(Priority::Low)),
+            _ => Err(value_error(key, format!("Unknown priority '{}' (use high, medium or low)", value))),
+        },
+        "due" if lower_value == "none" => Ok(FilterTerm::DueNone),
+        "due" if lower_value == "overdue" => Ok(FilterTerm::Overdue),
+        "due" | "created" | "updated" => {
+            let field = match key.to_lowercase().as_str() {
+                "due" => DateField::Due,
+                "created" => DateField::Created,
+                _ => DateField::Updated,
+            };
+            let bound = parse_date_bound(&lower_value)
+                .ok_or_else(|| value_error(key, format!("Invalid date '{}' (use YYYY-MM-DD, today, tomorrow or yesterday)", value)))?;
+            Ok(FilterTerm::Date { field, bound })
+        }
+        "is" => match lower_value.as_str() {
+            "done" | "completed" => Ok(FilterTerm::Completed),
+            "open" | "pending" => Ok(FilterTerm::Open),
+            "recurring" => Ok(FilterTerm::Recurring),
+            "subtask" => Ok(FilterTerm::Subtask),
+            "root" => Ok(FilterTerm::Root),
+            "blocked" => Ok(FilterTerm::Blocked),
+            "unblocked" | "ready" => Ok(FilterTerm::Unblocked),
+            _ => Err(value_error(key, format!("Unknown state '{}'", value))),
+        },
+        "has" => match lower_value.as_str() {
+            "due" => Ok(FilterTerm::HasDue),
+            "description" | "desc" => Ok(FilterTerm::HasDescription),
+            "tags" | "tag" => Ok(FilterTerm::HasTags),
+            "subtasks" => Ok(FilterTerm::HasSubtasks),
+            "reminders" | "reminder" => Ok(FilterTerm::HasReminders),
+            _ => Err(value_error(key, format!("Unknown property '{}'", value))),
+        },
+        _ => Err(FilterQueryError {
+            message: format!("Unknown field '{}'", key),
+            position: token.start + offset,
+            length: key.chars().count(),
+        }),
+    }
+}
+
+/// The UTC instant a local calendar day starts at. Where a clock change
+/// skips midnight the day starts an hour later.
+fn local_midnight_utc(day: NaiveDate) -> NaiveDateTime {
+    let midnight = day.and_time(NaiveTime::MIN);
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&(midnight + Duration::hours(1))).earliest())
+        .map(|dt| dt.naive_utc())
+        .unwrap_or(midnight)
+}
+
+fn parse_date_bound(value: &str) -> Option<DateBound> {
+    let (op, date) = if let Some(d) = value.strip_prefix("<=") {
+        ("<=", d)
+    } else if let Some(d) = value.strip_prefix(">=") {
+        (">=", d)
+    } else if let Some(d) = value.strip_prefix('<') {
+        ("<", d)
+    } else if let Some(d) = value.strip_prefix('>') {
+        (">", d)
+    } else {
+        ("=", value.strip_prefix('=').unwrap_or(value))
+    };
+
+    let today = Local::now().date_naive();
+    let date = match date {
+        "today" => today,
+        "tomorrow" => today + Duration::days(1),
+        "yesterday" => today - Duration::days(1),
+        _ => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?,
+    };
+
+    Some(match op {
+        "<" => DateBound::Before(date),
+        "<=" => DateBound::OnOrBefore(date),
+        ">" => DateBound::After(date),
+        ">=" => DateBound::OnOrAfter(date),
+        _ => DateBound::On(date),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{CategoryRepository, Database, TaskRepository};
+    use crate::models::{Category, CreateCategoryRequest, CreateTaskRequest, Task};
+
+    fn add_task(repo: &TaskRepository, title: &str, category_id: Option<&str>, due_date: Option<&str>) {
+        let task = Task::new(CreateTaskRequest {
+            title: title.to_string(),
+            description: None,
+            priority: None,
+            due_date: due_date.map(String::from),
+            category_id: category_id.map(String::from),
+            tags: None,
+            parent_id: None,
+            recurrence: None,
+        })
+        .unwrap();
+        repo.create(&task).unwrap();
+    }
+
+    fn matching(db: &Database, query: &str) -> Vec<String> {
+        let clause = FilterQuery::parse(query).unwrap().unwrap().to_sql();
+        let mut titles: Vec<String> = TaskRepository::new(&db.connection)
+            .get_all_matching(None, Some(&clause))
+            .unwrap()
+            .into_iter()
+            .map(|t| t.title)
+            .collect();
+        titles.sort();
+        titles
+    }
+
+    /// Position and length of the error `query` fails to parse with
+    fn error_at(query: &str) -> (usize, usize) {
+        let error = FilterQuery::parse(query).unwrap_err();
+        (error.position, error.length)
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_offending_text() {
+        assert_eq!(error_at("(cat:Work"), (0, 1));
+        assert_eq!(error_at("cat:Work)"), (8, 1));
+        assert_eq!(error_at("priority:urgent"), (9, 6));
+        assert_eq!(error_at("due:<someday"), (4, 8));
+        assert_eq!(error_at("cat:"), (4, 1));
+        assert_eq!(error_at("is:weird"), (3, 5));
+        assert_eq!(error_at("\"open"), (0, 5));
+        // Past the end of the query when something is missing
+        assert_eq!(error_at("report OR"), (9, 1));
+        assert_eq!(error_at("NOT"), (3, 1));
+        assert_eq!(error_at("p:high OR OR x"), (10, 2));
+        // Counted in characters, not bytes
+        assert_eq!(error_at("café cat:Work)"), (13, 1));
+    }
+
+    #[test]
+    fn empty_query_means_no_filter() {
+        assert!(FilterQuery::parse("").unwrap().is_none());
+        assert!(FilterQuery::parse("   ").unwrap().is_none());
+    }
+
+    #[test]
+    fn negation_forms_agree() {
+        let db = Database::in_memory().unwrap();
+        let repo = TaskRepository::new(&db.connection);
+        add_task(&repo, "draft report", None, None);
+        add_task(&repo, "send invoice", None, None);
+        add_task(&repo, "report invoice", None, None);
+
+        assert_eq!(matching(&db, "-report"), ["send invoice"]);
+        assert_eq!(matching(&db, "NOT report"), ["send invoice"]);
+        assert_eq!(matching(&db, "NOT NOT report"), ["draft report", "report invoice"]);
+        // NOT binds tighter than AND, which binds tighter than OR
+        assert_eq!(matching(&db, "-report invoice"), ["send invoice"]);
+        assert_eq!(matching(&db, "NOT report OR draft"), ["draft report", "send invoice"]);
+        assert_eq!(matching(&db, "-(report OR invoice)"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn negation_keeps_rows_with_null_columns() {
+        let db = Database::in_memory().unwrap();
+        let repo = TaskRepository::new(&db.connection);
+        let reports = Category::new(CreateCategoryRequest { name: "Reports".to_string(), color: "#000000".to_string(), icon: None });
+        CategoryRepository::new(&db.connection).create(&reports).unwrap();
+
+        add_task(&repo, "filed", Some(&reports.id), Some("2026-10-20"));
+        add_task(&repo, "loose", None, None);
+        add_task(&repo, "later", None, Some("2026-12-01"));
+
+        assert_eq!(matching(&db, "cat:Reports"), ["filed"]);
+        assert_eq!(matching(&db, "-cat:Reports"), ["later", "loose"]);
+        assert_eq!(matching(&db, "NOT cat:Reports"), ["later", "loose"]);
+
+        assert_eq!(matching(&db, "due:<2026-11-01"), ["filed"]);
+        assert_eq!(matching(&db, "-due:<2026-11-01"), ["later", "loose"]);
+        assert_eq!(matching(&db, "-(cat:Reports OR due:>2026-11-01)"), ["loose"]);
+    }
+}
@@ -0,0 +1,236 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::collections::HashSet;
+use crate::database::{atomically, CategoryRepository, DependencyRepository, ReminderRepository, TaskRepository};
+use crate::models::{EntityState, JournalEntry, TaskSnapshot, UndoState};
+
+/// Number of operations kept in the undo history
+const MAX_JOURNAL_ENTRIES: i64 = 100;
+
+/// Persistent undo/redo history. Mutating commands capture the state of the
+/// rows they touch before and after the change and record both; undo and
+/// redo then simply restore one side or the other inside a transaction.
+pub struct Journal<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> Journal<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    pub fn capture_tasks(&self, ids: &[String]) -> Result<Vec<EntityState>> {
+        let task_repo = TaskRepository::new(self.conn);
+        let reminder_repo = ReminderRepository::new(self.conn);
+        let dependency_repo = DependencyRepository::new(self.conn);
+
+        let mut states = Vec::new();
+        for id in ids {
+            let state = match task_repo.get_by_id(id)? {
+                Some(task) => Some(Box::new(TaskSnapshot {
+                    reminders: reminder_repo.get_for_task(id)?,
+                    dependencies: dependency_repo.get_for_task(id)?,
+                    task,
+                })),
+                None => None,
+            };
+            states.push(EntityState::Task { id: id.clone(), state });
+        }
+        Ok(states)
+    }
+
+    pub fn capture_categories(&self, ids: &[String]) -> Result<Vec<EntityState>> {
+        let category_repo = CategoryRepository::new(self.conn);
+
+        let mut states = Vec::new();
+        for id in ids {
+            states.push(EntityState::Category {
+                id: id.clone(),
+                state: category_repo.get_by_id(id)?,
+            });
+        }
+        Ok(states)
+    }
+
+    /// Record an operation. Entities present on only one side are treated as
+    /// absent on the other, and entities that did not change are dropped.
+    pub fn record(&self, label: &str, before: Vec<EntityState>, after: Vec<EntityState>) -> Result<()> {
+        atomically(self.conn, || {
+            let mut seen = HashSet::new();
+            let mut before: Vec<_> = before.into_iter().filter(|b| seen.insert(b.key_owned())).collect();
+            let mut seen = HashSet::new();
+            let mut after: Vec<_> = after.into_iter().filter(|a| seen.insert(a.key_owned())).collect();
+
+            for state in after.clone() {
+                if !before.iter().any(|b| b.key() == state.key()) {
+                    before.push(state.absent());
+                }
+            }
+            for state in before.clone() {
+                if !after.iter().any(|a| a.key() == state.key()) {
+                    after.push(state.absent());
+                }
+            }
+
+            let (before, after): (Vec<_>, Vec<_>) = before
+                .into_iter()
+                .filter_map(|b| {
+                    let a = after.iter().find(|a| a.key() == b.key())?.clone();
+                    let unchanged = serde_json::to_value(&b).ok() == serde_json::to_value(&a).ok();
+                    (!unchanged).then_some((b, a))
+                })
+                .unzip();
+
+            if before.is_empty() {
+                return Ok(());
+            }
+
+            let to_json = |states: &Vec<EntityState>| {
+                serde_json::to_string(states).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+            };
+
+            // A new operation invalidates anything that could have been redone
+            self.conn.execute("DELETE FROM operation_journal WHERE undone = 1", [])?;
+            self.conn.execute(
+                "INSERT INTO operation_journal (label, before_state, after_state, undone, created_at)
+                 VALUES (?1, ?2, ?3, 0, ?4)",
+                params![label, to_json(&before)?, to_json(&after)?, chrono::Utc::now().naive_utc()],
+            )?;
+            self.conn.execute(
+                "DELETE FROM operation_journal WHERE id NOT IN (
+                    SELECT id FROM operation_journal ORDER BY id DESC LIMIT ?1
+                )",
+                params![MAX_JOURNAL_ENTRIES],
+            )?;
+
+            Ok(())
+        })
+    }
+
+    /// Revert the most recent operation. Returns `None` when there is nothing to undo.
+    pub fn undo(&self) -> Result<Option<JournalEntry>> {
+        let entry = match self.get_entry("WHERE undone = 0 ORDER BY id DESC")? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.apply(&entry.before)?;
+        tx.execute("UPDATE operation_journal SET undone = 1 WHERE id = ?1", params![entry.id])?;
+        tx.commit()?;
+
+        Ok(Some(entry))
+    }
+
+    /// Re-apply the most recently undone operation. Returns `None` when there is nothing to redo.
+    pub fn redo(&self) -> Result<Option<JournalEntry>> {
+        let entry = match self.get_entry("WHERE undone = 1 ORDER BY id ASC")? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.apply(&entry.after)?;
+        tx.execute("UPDATE operation_journal SET undone = 0 WHERE id = ?1", params![entry.id])?;
+        tx.commit()?;
+
+        Ok(Some(entry))
+    }
+
+    pub fn state(&self) -> Result<UndoState> {
+        Ok(UndoState {
+            undo_label: self.get_entry("WHERE undone = 0 ORDER BY id DESC")?.map(|e| e.label),
+            redo_label: self.get_entry("WHERE undone = 1 ORDER BY id ASC")?.map(|e| e.label),
+        })
+    }
+
+    fn get_entry(&self, clause: &str) -> Result<Option<JournalEntry>> {
+        let query = format!(
+            "SELECT id, label, before_state, after_state, undone, created_at FROM operation_journal {} LIMIT 1",
+            clause
+        );
+
+        let from_json = |idx: usize, text: String| {
+            serde_json::from_str(&text).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+            })
+        };
+
+        self.conn
+            .query_row(&query, [], |row| {
+                Ok(JournalEntry {
+                    id: row.get(0)?,
+                    label: row.get(1)?,
+                    before: from_json(2, row.get(2)?)?,
+                    after: from_json(3, row.get(3)?)?,
+                    undone: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            })
+            .optional()
+    }
+
+    /// Bring every entity to the given state. Must run inside a transaction:
+    /// foreign keys are checked at commit so parents and children can be
+    /// restored or removed in any order.
+    fn apply(&self, states: &[EntityState]) -> Result<()> {
+        self.conn.pragma_update(None, "defer_foreign_keys", true)?;
+
+        let task_repo = TaskRepository::new(self.conn);
+        let reminder_repo = ReminderRepository::new(self.conn);
+        let dependency_repo = DependencyRepository::new(self.conn);
+        let category_repo = CategoryRepository::new(self.conn);
+
+        // Categories first so restored tasks can point at them
+        for state in states {
+            if let EntityState::Category { id, state: Some(category) } = state {
+                if category_repo.get_by_id(id)?.is_some() {
+                    category_repo.update(category)?;
+                } else {
+                    category_repo.create(category)?;
+                }
+            }
+        }
+
+        // Tasks removed here count as trashed together
+        let now = chrono::Utc::now().naive_utc();
+        for state in states {
+            if let EntityState::Task { id, state } = state {
+                let exists = task_repo.get_by_id(id)?.is_some();
+                match state {
+                    Some(snapshot) => {
+                        // A row in the trash is brought back rather than re-inserted
+                        if task_repo.row_exists(id)? {
+                            task_repo.update(&snapshot.task)?;
+                            task_repo.restore_row(id)?;
+                        } else {
+                            task_repo.create(&snapshot.task)?;
+                        }
+                        reminder_repo.delete_for_task(id)?;
+                        for reminder in &snapshot.reminders {
+                            reminder_repo.create(reminder)?;
+                        }
+                        dependency_repo.set_for_task(id, &snapshot.dependencies)?;
+                    }
+                    None if exists => task_repo.trash_row(id, now)?,
+                    None => {}
+                }
+            }
+        }
+
+        // Removed categories go last, once the tasks that used them are in
+        // the trash; trashed rows let go of the category so it can be deleted
+        for state in states {
+            if let EntityState::Category { id, state: None } = state {
+                if category_repo.get_by_id(id)?.is_some() {
+                    self.conn.execute(
+                        "UPDATE tasks SET category_id = NULL WHERE category_id = ?1 AND deleted_at IS NOT NULL",
+                        params![id],
+                    )?;
+                    category_repo.delete(id)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,103 @@
 use rusqlite::{Connection, Result};
+use crate::utils::AppError;
 
-pub fn run_migrations(conn: &Connection) -> Result<()> {
+/// A single, numbered schema change. Each step runs exactly once, inside its
+/// own transaction, and bumps `PRAGMA user_version` to `version` on success.
+struct Migration {
+    version: u32,
+    description: &'static str,
+    up: fn(&Connection) -> Result<()>,
+}
+
+/// Ordered list of every schema change. Append new steps to the end with the
+/// next version number; never edit or reorder a step that has shipped.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        up: initial_schema,
+    },
+    Migration {
+        version: 2,
+        description: "task recurrence rules",
+        up: add_task_recurrence,
+    },
+    Migration {
+        version: 3,
+        description: "task reminders",
+        up: create_task_reminders,
+    },
+    Migration {
+        version: 4,
+        description: "undo/redo operation journal",
+        up: create_operation_journal,
+    },
+    Migration {
+        version: 5,
+        description: "trash bin and settings",
+        up: add_trash_and_settings,
+    },
+    Migration {
+        version: 6,
+        description: "full-text search index",
+        up: create_task_search_index,
+    },
+    Migration {
+        version: 7,
+        description: "task dependencies",
+        up: create_task_dependencies,
+    },
+    Migration {
+        version: 8,
+        description: "CalDAV sync state",
+        up: create_caldav_sync,
+    },
+    Migration {
+        version: 9,
+        description: "manual task order",
+        up: add_task_positions,
+    },
+];
+
+/// The schema version this build of the app knows how to work with.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+pub fn run_migrations(conn: &Connection) -> std::result::Result<(), AppError> {
+    let current = schema_version(conn)?;
+    let latest = latest_version();
+
+    // Refuse to touch a database written by a newer version of the app
+    if current > latest {
+        return Err(AppError::SchemaTooNew { found: current, supported: latest });
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let step_failed = |source| AppError::Migration {
+            version: migration.version,
+            description: migration.description,
+            source,
+        };
+
+        let tx = conn.unchecked_transaction().map_err(step_failed)?;
+        (migration.up)(&tx).map_err(step_failed)?;
+        tx.pragma_update(None, "user_version", migration.version)
+            .map_err(step_failed)?;
+        tx.commit().map_err(step_failed)?;
+    }
+
+    Ok(())
+}
+
+// Version 1: the tables that existed before versioned migrations. Everything
+// uses IF NOT EXISTS so databases created by older releases (which report
+// user_version 0) are adopted without changes.
+fn initial_schema(conn: &Connection) -> Result<()> {
     // Create categories table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS categories (
@@ -108,3 +205,206 @@ pub fn run_migrations(conn: &Connection) -> Result<()> {
 
     Ok(())
 }
+
+fn add_task_recurrence(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE tasks ADD COLUMN recurrence TEXT", [])?;
+    Ok(())
+}
+
+fn create_task_reminders(conn: &Connection) -> Result<()> {
+    // `minutes` is minutes before the due date for 'before_due' reminders and
+    // minutes after local midnight for 'on_due_day' reminders
+    conn.execute(
+        "CREATE TABLE task_reminders (
+            id TEXT PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            minutes INTEGER NOT NULL,
+            fired_for_due DATETIME,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX idx_task_reminders_task ON task_reminders(task_id)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn create_operation_journal(conn: &Connection) -> Result<()> {
+    // before_state/after_state hold JSON arrays of entity snapshots
+    conn.execute(
+        "CREATE TABLE operation_journal (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            label TEXT NOT NULL,
+            before_state TEXT NOT NULL,
+            after_state TEXT NOT NULL,
+            undone BOOLEAN NOT NULL DEFAULT FALSE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn add_trash_and_settings(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE tasks ADD COLUMN deleted_at DATETIME", [])?;
+
+    conn.execute(
+        "CREATE INDEX idx_tasks_deleted_at ON tasks(deleted_at)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn create_task_search_index(conn: &Connection) -> Result<()> {
+    // One FTS row per task, keyed by the task's id rather than its rowid:
+    // `tasks` has no INTEGER PRIMARY KEY, so VACUUM is free to renumber its
+    // rowids. Tags are flattened into a space-separated column so they are
+    // searchable alongside the text.
+    conn.execute(
+        "CREATE VIRTUAL TABLE tasks_fts USING fts5(
+            title,
+            description,
+            tags,
+            task_id UNINDEXED,
+            tokenize = 'unicode61 remove_diacritics 2',
+            prefix = '2 3'
+        )",
+        [],
+    )?;
+
+    conn.execute_batch(
+        "CREATE TRIGGER tasks_fts_insert AFTER INSERT ON tasks BEGIN
+            INSERT INTO tasks_fts (title, description, tags, task_id)
+            VALUES (
+                new.title,
+                COALESCE(new.description, ''),
+                COALESCE((SELECT group_concat(tag, ' ') FROM task_tags WHERE task_id = new.id), ''),
+                new.id
+            );
+        END;
+
+        CREATE TRIGGER tasks_fts_update AFTER UPDATE OF title, description ON tasks BEGIN
+            UPDATE tasks_fts SET title = new.title, description = COALESCE(new.description, '')
+            WHERE task_id = new.id;
+        END;
+
+        CREATE TRIGGER tasks_fts_delete AFTER DELETE ON tasks BEGIN
+            DELETE FROM tasks_fts WHERE task_id = old.id;
+        END;
+
+        CREATE TRIGGER task_tags_fts_insert AFTER INSERT ON task_tags BEGIN
+            UPDATE tasks_fts
+            SET tags = COALESCE((SELECT group_concat(tag, ' ') FROM task_tags WHERE task_id = new.task_id), '')
+            WHERE task_id = new.task_id;
+        END;
+
+        CREATE TRIGGER task_tags_fts_delete AFTER DELETE ON task_tags BEGIN
+            UPDATE tasks_fts
+            SET tags = COALESCE((SELECT group_concat(tag, ' ') FROM task_tags WHERE task_id = old.task_id), '')
+            WHERE task_id = old.task_id;
+        END;",
+    )?;
+
+    // Index everything that already exists
+    conn.execute(
+        "INSERT INTO tasks_fts (title, description, tags, task_id)
+         SELECT t.title, COALESCE(t.description, ''),
+                COALESCE((SELECT group_concat(tag, ' ') FROM task_tags WHERE task_id = t.id), ''),
+                t.id
+         FROM tasks t",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn create_task_dependencies(conn: &Connection) -> Result<()> {
+    // `task_id` cannot start until `depends_on_id` is done
+    conn.execute(
+        "CREATE TABLE task_dependencies (
+            task_id TEXT NOT NULL,
+            depends_on_id TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (task_id, depends_on_id),
+            CHECK (task_id <> depends_on_id),
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+            FOREIGN KEY (depends_on_id) REFERENCES tasks(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX idx_task_dependencies_depends_on ON task_dependencies(depends_on_id)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn create_caldav_sync(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE caldav_calendars (
+            url TEXT PRIMARY KEY,
+            category_id TEXT UNIQUE,
+            display_name TEXT NOT NULL,
+            sync_token TEXT,
+            synced_at DATETIME,
+            FOREIGN KEY (category_id) REFERENCES categories(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    // No foreign key to tasks: the row has to outlive a purged task so the
+    // next sync can delete it on the server
+    conn.execute(
+        "CREATE TABLE caldav_task_sync (
+            task_id TEXT PRIMARY KEY,
+            calendar_url TEXT NOT NULL,
+            href TEXT NOT NULL UNIQUE,
+            uid TEXT NOT NULL,
+            etag TEXT,
+            fingerprint TEXT NOT NULL,
+            synced_at DATETIME NOT NULL,
+            FOREIGN KEY (calendar_url) REFERENCES caldav_calendars(url) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn add_task_positions(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE tasks ADD COLUMN position INTEGER NOT NULL DEFAULT 0", [])?;
+
+    // Keep today's order: top-level tasks newest first, subtasks oldest first
+    // (see `Task::initial_position`)
+    conn.execute(
+        "UPDATE tasks SET position =
+            CAST(ROUND((julianday(created_at) - 2440587.5) * 86400000) AS INTEGER)
+            * CASE WHEN parent_id IS NULL THEN -1 ELSE 1 END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX idx_tasks_position ON tasks(parent_id, category_id, position)",
+        [],
+    )?;
+
+    Ok(())
+}
@@ -1,6 +1,11 @@
 pub mod connection;
+pub mod filter_query;
+pub mod journal;
 pub mod migrations;
 pub mod repository;
+pub mod search;
+pub mod taskwarrior;
 
 pub use connection::*;
+pub use journal::*;
 pub use repository::*;
@@ -1,5 +1,13 @@
-use rusqlite::{params, Connection, Result};
-use crate::models::{Task, Category, Priority, TaskFilter};
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::collections::HashMap;
+use crate::models::{Task, TaskNode, Category, Priority, TaskFilter, TaskSort, SortField, SortOrder, SortKey, TaskCursor, TaskPage, TaskCounts, Reminder, ReminderOffset, DueReminder, TaskSearchResult, CalDavAccount, CalDavCalendar, ConflictPolicy, TaskSyncState, TaskDependency, ApiSettings, DEFAULT_API_PORT};
+use crate::database::filter_query::{DateBound, DateField, FilterQuery, FilterTerm, SqlClause};
+use crate::database::atomically;
+use crate::database::search::{to_fts_query, HIGHLIGHT_END, HIGHLIGHT_START};
+
+/// The columns `TaskRepository::map_row` reads, in order
+const TASK_COLUMNS: &str = "id, title, description, completed, priority, due_date, category_id, parent_id, created_at, updated_at, recurrence, deleted_at, position";
 
 pub struct TaskRepository<'a> {
     conn: &'a Connection,
@@ -11,60 +19,48 @@ impl<'a> TaskRepository<'a> {
     }
 
     pub fn create(&self, task: &Task) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO tasks (id, title, description, completed, priority, due_date, category_id, parent_id, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            params![
-                task.id,
-                task.title,
-                task.description,
-                task.completed,
-                task.priority.to_string(),
-                task.due_date,
-                task.category_id,
-                task.parent_id,
-                task.created_at,
-                task.updated_at,
-            ],
-        )?;
-
-        // Insert tags
-        for tag in &task.tags {
+        atomically(self.conn, || {
             self.conn.execute(
-                "INSERT INTO task_tags (task_id, tag) VALUES (?1, ?2)",
-                params![task.id, tag],
+                "INSERT INTO tasks (id, title, description, completed, priority, due_date, category_id, parent_id, created_at, updated_at, recurrence, position)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    task.id,
+                    task.title,
+                    task.description,
+                    task.completed,
+                    task.priority.to_string(),
+                    task.due_date,
+                    task.category_id,
+                    task.parent_id,
+                    task.created_at,
+                    task.updated_at,
+                    task.recurrence.as_ref().map(|r| r.to_string()),
+                    task.position,
+                ],
             )?;
-        }
 
-        Ok(())
+            // Insert tags
+            for tag in &task.tags {
+                self.conn.execute(
+                    "INSERT INTO task_tags (task_id, tag) VALUES (?1, ?2)",
+                    params![task.id, tag],
+                )?;
+            }
+
+            Ok(())
+        })
     }
 
     pub fn get_by_id(&self, id: &str) -> Result<Option<Task>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, title, description, completed, priority, due_date, category_id, parent_id, created_at, updated_at
-             FROM tasks WHERE id = ?1",
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM tasks WHERE id = ?1 AND deleted_at IS NULL",
+            TASK_COLUMNS,
+        ))?;
 
-        let task_result = stmt.query_row(params![id], |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                completed: row.get(3)?,
-                priority: Priority::from_string(&row.get::<_, String>(4)?),
-                due_date: row.get(5)?,
-                category_id: row.get(6)?,
-                parent_id: row.get(7)?,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-                tags: vec![], // Will be populated below
-            })
-        });
-
-        match task_result {
+        match stmt.query_row(params![id], Self::map_row) {
             Ok(mut task) => {
-                // Get tags
-                task.tags = self.get_task_tags(&task.id)?;
+                self.load_tags(std::slice::from_mut(&mut task))?;
                 Ok(Some(task))
             }
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -73,8 +69,31 @@ impl<'a> TaskRepository<'a> {
     }
 
     pub fn get_all(&self, filter: Option<TaskFilter>) -> Result<Vec<Task>> {
-        let mut query = "SELECT id, title, description, completed, priority, due_date, category_id, parent_id, created_at, updated_at FROM tasks".to_string();
-        let mut conditions = Vec::new();
+        self.get_all_matching(filter, None)
+    }
+
+    /// Like `get_all`, additionally restricted by a compiled filter query.
+    pub fn get_all_matching(&self, filter: Option<TaskFilter>, clause: Option<&SqlClause>) -> Result<Vec<Task>> {
+        Ok(self.get_page(filter, clause, TaskSort::default(), None, None)?.tasks)
+    }
+
+    /// Tasks matching `filter` and `clause` in `sort` order: at most `limit`
+    /// of them (all without one), starting after `cursor`. Ties are broken
+    /// by ID so every task has a fixed place to page on from.
+    pub fn get_page(
+        &self,
+        filter: Option<TaskFilter>,
+        clause: Option<&SqlClause>,
+        sort: TaskSort,
+        limit: Option<usize>,
+        cursor: Option<&TaskCursor>,
+    ) -> Result<TaskPage> {
+        let sort_key = sort_key_sql(sort.field);
+        let mut query = format!(
+            "SELECT {}, {} FROM tasks",
+            TASK_COLUMNS, sort_key
+        );
+        let mut conditions = vec!["deleted_at IS NULL".to_string()];
         let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
         if let Some(ref f) = filter {
@@ -100,10 +119,15 @@ impl<'a> TaskRepository<'a> {
                 params.push(Box::new(parent_id.clone()));
             }
             if let Some(ref search_query) = f.search_query {
-                conditions.push("(title LIKE ? OR description LIKE ?)".to_string());
-                let search_pattern = format!("%{}%", search_query);
-                params.push(Box::new(search_pattern.clone()));
-                params.push(Box::new(search_pattern));
+                match to_fts_query(search_query).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))? {
+                    Some(fts_query) => {
+                        conditions.push("id IN (SELECT task_id FROM tasks_fts WHERE tasks_fts MATCH ?)".to_string());
+                        params.push(Box::new(fts_query));
+                    }
+                    // Nothing searchable in the query, so nothing can match
+                    None if !search_query.trim().is_empty() => conditions.push("0".to_string()),
+                    None => {}
+                }
             }
             if let Some(due_before) = f.parse_due_before() {
                 conditions.push("due_date <= ?".to_string());
@@ -115,238 +139,706 @@ impl<'a> TaskRepository<'a> {
             }
         }
 
-        if !conditions.is_empty() {
-            query.push_str(" WHERE ");
-            query.push_str(&conditions.join(" AND "));
+        if let Some(clause) = clause {
+            conditions.push(clause.sql.clone());
+            for value in &clause.params {
+                params.push(Box::new(value.clone()));
+            }
+        }
+
+        let (direction, comparison) = match sort.order {
+            SortOrder::Asc => ("ASC", ">"),
+            SortOrder::Desc => ("DESC", "<"),
+        };
+        if let Some(cursor) = cursor {
+            conditions.push(format!("({}, id) {} (?, ?)", sort_key, comparison));
+            params.push(Box::new(match &cursor.key {
+                SortKey::Integer(value) => Value::Integer(*value),
+                SortKey::Text(value) => Value::Text(value.clone()),
+            }));
+            params.push(Box::new(cursor.id.clone()));
         }
 
-        query.push_str(" ORDER BY created_at DESC");
+        query.push_str(" WHERE ");
+        query.push_str(&conditions.join(" AND "));
+
+        query.push_str(&format!(" ORDER BY {0} {1}, id {1}", sort_key, direction));
+
+        // One extra row tells whether there is another page
+        if let Some(limit) = limit {
+            query.push_str(" LIMIT ?");
+            params.push(Box::new(limit as i64 + 1));
+        }
 
         let mut stmt = self.conn.prepare(&query)?;
         let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
 
-        let task_iter = stmt.query_map(&param_refs[..], |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                completed: row.get(3)?,
-                priority: Priority::from_string(&row.get::<_, String>(4)?),
-                due_date: row.get(5)?,
-                category_id: row.get(6)?,
-                parent_id: row.get(7)?,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-                tags: vec![], // Will be populated below
-            })
-        })?;
+        let mut rows = stmt
+            .query_map(&param_refs[..], |row| {
+                let key = match row.get::<_, Value>(13)? {
+                    Value::Integer(value) => SortKey::Integer(value),
+                    Value::Text(value) => SortKey::Text(value),
+                    _ => SortKey::Integer(0),
+                };
+                Ok((Self::map_row(row)?, key))
+            })?
+            .collect::<Result<Vec<_>>>()?;
 
-        let mut tasks = Vec::new();
-        for task_result in task_iter {
-            let mut task = task_result?;
-            task.tags = self.get_task_tags(&task.id)?;
-            tasks.push(task);
-        }
+        let next_cursor = match limit {
+            Some(limit) if rows.len() > limit => {
+                rows.truncate(limit);
+                rows.last().map(|(task, key)| {
+                    TaskCursor { sort, key: key.clone(), id: task.id.clone() }.encode()
+                })
+            }
+            _ => None,
+        };
 
-        Ok(tasks)
+        let mut tasks: Vec<Task> = rows.into_iter().map(|(task, _)| task).collect();
+        self.load_tags(&mut tasks)?;
+        Ok(TaskPage { tasks, next_cursor })
     }
 
-    pub fn update(&self, task: &Task) -> Result<()> {
-        self.conn.execute(
-            "UPDATE tasks SET title = ?1, description = ?2, completed = ?3, priority = ?4, 
-             due_date = ?5, category_id = ?6, parent_id = ?7, updated_at = ?8 WHERE id = ?9",
-            params![
-                task.title,
-                task.description,
-                task.completed,
-                task.priority.to_string(),
-                task.due_date,
-                task.category_id,
-                task.parent_id,
-                task.updated_at,
-                task.id,
-            ],
+    /// Task counts for the sidebar views, over every live task.
+    pub fn get_counts(&self) -> Result<TaskCounts> {
+        let today = FilterQuery::Term(FilterTerm::Date {
+            field: DateField::Due,
+            bound: DateBound::On(chrono::Local::now().date_naive()),
+        })
+        .to_sql();
+        let overdue = FilterQuery::Term(FilterTerm::Overdue).to_sql();
+
+        let mut params = today.params;
+        params.extend(overdue.params);
+        let mut counts = self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(SUM({}), 0), COALESCE(SUM({}), 0),
+                        COALESCE(SUM(completed = 1), 0), COALESCE(SUM(completed = 0), 0),
+                        COALESCE(SUM(category_id IS NULL), 0)
+                 FROM tasks WHERE deleted_at IS NULL",
+                today.sql, overdue.sql
+            ),
+            rusqlite::params_from_iter(params),
+            |row| {
+                Ok(TaskCounts {
+                    all: row.get::<_, i64>(0)? as usize,
+                    today: row.get::<_, i64>(1)? as usize,
+                    overdue: row.get::<_, i64>(2)? as usize,
+                    completed: row.get::<_, i64>(3)? as usize,
+                    pending: row.get::<_, i64>(4)? as usize,
+                    no_category: row.get::<_, i64>(5)? as usize,
+                    by_category: HashMap::new(),
+                })
+            },
+        )?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT category_id, COUNT(*) FROM tasks
+             WHERE deleted_at IS NULL AND category_id IS NOT NULL GROUP BY category_id",
+        )?;
+        for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))? {
+            let (category_id, count) = row?;
+            counts.by_category.insert(category_id, count as usize);
+        }
+        Ok(counts)
+    }
+
+    /// Full-text search over title, description and tags, best matches first.
+    /// See `to_fts_query` for the accepted query syntax.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<TaskSearchResult>> {
+        let fts_query = match to_fts_query(query).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))? {
+            Some(q) => q,
+            None => return Ok(Vec::new()),
+        };
+
+        // Title matches weigh most, then tags, then the description
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, bm25(tasks_fts, 10.0, 1.0, 5.0) AS score,
+                    highlight(tasks_fts, 0, ?2, ?3),
+                    snippet(tasks_fts, 1, ?2, ?3, '…', 16)
+             FROM tasks_fts JOIN tasks t ON t.id = tasks_fts.task_id
+             WHERE tasks_fts MATCH ?1 AND t.deleted_at IS NULL
+             ORDER BY score
+             LIMIT ?4",
         )?;
 
-        // Update tags
-        self.conn.execute("DELETE FROM task_tags WHERE task_id = ?1", params![task.id])?;
-        for tag in &task.tags {
+        let hits = stmt
+            .query_map(
+                params![fts_query, HIGHLIGHT_START, HIGHLIGHT_END, limit as i64],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, f64>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                },
+            )?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut results = Vec::new();
+        for (id, score, title_highlight, description_snippet) in hits {
+            if let Some(task) = self.get_by_id(&id)? {
+                results.push(TaskSearchResult {
+                    task,
+                    // bm25 is lower-is-better; flip it so callers can sort descending
+                    score: -score,
+                    title_highlight,
+                    description_snippet: (!description_snippet.is_empty()).then_some(description_snippet),
+                });
+            }
+        }
+
+        Ok(results)
+    }
+
+    pub fn update(&self, task: &Task) -> Result<()> {
+        atomically(self.conn, || {
             self.conn.execute(
-                "INSERT INTO task_tags (task_id, tag) VALUES (?1, ?2)",
-                params![task.id, tag],
+                "UPDATE tasks SET title = ?1, description = ?2, completed = ?3, priority = ?4, 
+                 due_date = ?5, category_id = ?6, parent_id = ?7, updated_at = ?8, recurrence = ?9, position = ?10 WHERE id = ?11",
+                params![
+                    task.title,
+                    task.description,
+                    task.completed,
+                    task.priority.to_string(),
+                    task.due_date,
+                    task.category_id,
+                    task.parent_id,
+                    task.updated_at,
+                    task.recurrence.as_ref().map(|r| r.to_string()),
+                    task.position,
+                    task.id,
+                ],
             )?;
-        }
 
-        Ok(())
+            // Update tags
+            self.conn.execute("DELETE FROM task_tags WHERE task_id = ?1", params![task.id])?;
+            for tag in &task.tags {
+                self.conn.execute(
+                    "INSERT INTO task_tags (task_id, tag) VALUES (?1, ?2)",
+                    params![task.id, tag],
+                )?;
+            }
+
+            Ok(())
+        })
     }
 
+    /// Move a task to the trash. Its row, tags and reminders are kept so it
+    /// can be restored; trashed tasks are invisible to every other query and
+    /// their reminders no longer fire. Rows are removed for good by
+    /// `empty_trash` or `purge_trash_before`.
+    ///
+    /// Subtasks the task still has move up to its parent, so no live task is
+    /// left under a trashed one; use `delete_task_and_subtasks` to trash them
+    /// as well.
     pub fn delete(&self, id: &str) -> Result<()> {
-        self.conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])?;
+        atomically(self.conn, || {
+            let parent_id: Option<Option<String>> = self.conn.query_row(
+                "SELECT parent_id FROM tasks WHERE id = ?1 AND deleted_at IS NULL",
+                params![id],
+                |row| row.get(0),
+            ).optional()?;
+            let Some(parent_id) = parent_id else {
+                return Ok(());
+            };
+            
+            let now = chrono::Utc::now().naive_utc();
+            self.conn.execute(
+                "UPDATE tasks SET parent_id = ?1, updated_at = ?2 WHERE parent_id = ?3 AND deleted_at IS NULL",
+                params![parent_id, now, id],
+            )?;
+            self.trash_row(id, now)
+        })
+    }
+
+    /// Set the trashed flag on a single row, leaving any subtasks where they
+    /// are. Only for restoring a recorded state, where every row's state is
+    /// known. Rows trashed together should share `deleted_at` so `restore`
+    /// brings them back together.
+    pub fn trash_row(&self, id: &str, deleted_at: chrono::NaiveDateTime) -> Result<()> {
+        self.conn.execute(
+            "UPDATE tasks SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![deleted_at, id],
+        )?;
         Ok(())
     }
 
-    fn get_task_tags(&self, task_id: &str) -> Result<Vec<String>> {
-        let mut stmt = self.conn.prepare("SELECT tag FROM task_tags WHERE task_id = ?1")?;
-        let tag_iter = stmt.query_map(params![task_id], |row| {
-            Ok(row.get::<_, String>(0)?)
-        })?;
+    /// Whether a row exists for `id`, trashed or not.
+    pub fn row_exists(&self, id: &str) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM tasks WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
 
-        let mut tags = Vec::new();
-        for tag_result in tag_iter {
-            tags.push(tag_result?);
-        }
-        Ok(tags)
+    /// Clear the trashed flag on a single row.
+    pub fn restore_row(&self, id: &str) -> Result<()> {
+        self.conn.execute("UPDATE tasks SET deleted_at = NULL WHERE id = ?1", params![id])?;
+        Ok(())
     }
 
-    pub fn get_task_hierarchy(&self, root_id: Option<String>) -> Result<Vec<Task>> {
-        let mut tasks = Vec::new();
-        let mut ids_to_process = Vec::new();
+    /// Every task in the trash, most recently deleted first. Subtasks keep
+    /// their `parent_id` so the trash can be shown as a tree.
+    pub fn list_trash(&self) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM tasks WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, created_at ASC",
+            TASK_COLUMNS,
+        ))?;
 
-        // Start with direct children of root_id (or root tasks if None)
-        let filter = TaskFilter {
-            completed: None,
-            priority: None,
-            category_id: None,
-            parent_id: root_id.clone(),
-            search_query: None,
-            due_before: None,
-            due_after: None,
-            no_category: None,
-        };
+        let task_iter = stmt.query_map([], Self::map_row)?;
 
-        let direct_children = self.get_all(Some(filter))?;
-        
-        for child in direct_children {
-            ids_to_process.push(child.id.clone());
-            tasks.push(child);
-        }
+        let mut tasks = task_iter.collect::<Result<Vec<_>>>()?;
+        self.load_tags(&mut tasks)?;
+        Ok(tasks)
+    }
 
-        // Recursively get all descendants
-        while let Some(current_id) = ids_to_process.pop() {
-            let filter = TaskFilter {
-                completed: None,
-                priority: None,
-                category_id: None,
-                parent_id: Some(current_id),
-                search_query: None,
-                due_before: None,
-                due_after: None,
-                no_category: None,
-            };
+    /// Take a task out of the trash, along with the subtasks that were
+    /// trashed together with it. Subtasks trashed on their own, earlier or
+    /// later, stay in the trash. The restored task keeps its parent if that
+    /// parent is still alive and becomes a root task otherwise. Returns the
+    /// ids that were restored.
+    pub fn restore(&self, task_id: &str) -> Result<Vec<String>> {
+        atomically(self.conn, || {
+            let (parent_id, deleted_at): (Option<String>, chrono::NaiveDateTime) = self.conn.query_row(
+                "SELECT parent_id, deleted_at FROM tasks WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![task_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
 
-            let children = self.get_all(Some(filter))?;
-            for child in children {
-                ids_to_process.push(child.id.clone());
-                tasks.push(child);
+            if let Some(parent_id) = parent_id {
+                if self.get_by_id(&parent_id)?.is_none() {
+                    self.conn.execute(
+                        "UPDATE tasks SET parent_id = NULL WHERE id = ?1",
+                        params![task_id],
+                    )?;
+                }
             }
-        }
 
-        Ok(tasks)
+            // One delete gives the whole subtree the same timestamp
+            let mut stmt = self.conn.prepare(
+                "WITH RECURSIVE subtree(id) AS (
+                     SELECT ?1
+                     UNION
+                     SELECT t.id FROM tasks t JOIN subtree s ON t.parent_id = s.id
+                     WHERE t.deleted_at = ?2
+                 )
+                 SELECT id FROM subtree",
+            )?;
+            let restored = stmt
+                .query_map(params![task_id, deleted_at], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>>>()?;
+
+            let ids = serde_json::to_string(&restored).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            self.conn.execute(
+                "UPDATE tasks SET deleted_at = NULL WHERE id IN (SELECT value FROM json_each(?1))",
+                params![ids],
+            )?;
+
+            Ok(restored)
+        })
     }
 
-    pub fn calculate_task_progress(&self, task_id: &str) -> Result<crate::commands::task_commands::TaskProgress> {
-        // Get all subtasks recursively
-        let subtasks = self.get_task_hierarchy(Some(task_id.to_string()))?;
-        
-        if subtasks.is_empty() {
-            return Ok(crate::commands::task_commands::TaskProgress {
-                total_subtasks: 0,
-                completed_subtasks: 0,
-                progress_percentage: 0.0,
-                has_subtasks: false,
-            });
+    /// Permanently remove every task in the trash.
+    pub fn empty_trash(&self) -> Result<usize> {
+        self.purge_where("deleted_at IS NOT NULL", &[])
+    }
+
+    /// Permanently remove tasks that have been in the trash since before `cutoff`.
+    pub fn purge_trash_before(&self, cutoff: chrono::NaiveDateTime) -> Result<usize> {
+        self.purge_where("deleted_at IS NOT NULL AND deleted_at < ?1", &[&cutoff])
+    }
+
+    /// Apply the configured retention period to the trash.
+    pub fn purge_expired_trash(&self) -> Result<usize> {
+        let days = SettingsRepository::new(self.conn).trash_retention_days()?;
+        if days == 0 {
+            return Ok(0);
         }
+        let cutoff = chrono::Utc::now().naive_utc() - chrono::Duration::days(days as i64);
+        self.purge_trash_before(cutoff)
+    }
 
-        let total_subtasks = subtasks.len() as i32;
-        let completed_subtasks = subtasks.iter().filter(|t| t.completed).count() as i32;
-        let progress_percentage = if total_subtasks > 0 {
-            (completed_subtasks as f32 / total_subtasks as f32) * 100.0
-        } else {
-            0.0
-        };
+    fn purge_where(&self, condition: &str, params: &[&dyn rusqlite::ToSql]) -> Result<usize> {
+        atomically(self.conn, || {
+            // Anything left behind that points at a purged row becomes a root
+            // task, so the delete below never trips the parent foreign key
+            self.conn.execute(
+                &format!(
+                    "UPDATE tasks SET parent_id = NULL
+                     WHERE parent_id IN (SELECT id FROM tasks WHERE {0}) AND NOT ({0})",
+                    condition
+                ),
+                params,
+            )?;
+            self.conn.execute(
+                &format!("DELETE FROM task_reminders WHERE task_id IN (SELECT id FROM tasks WHERE {})", condition),
+                params,
+            )?;
+            self.conn.execute(&format!("DELETE FROM tasks WHERE {}", condition), params)
+        })
+    }
 
-        Ok(crate::commands::task_commands::TaskProgress {
-            total_subtasks,
-            completed_subtasks,
-            progress_percentage,
-            has_subtasks: true,
+    /// A task from a row of `TASK_COLUMNS`, without its tags.
+    fn map_row(row: &rusqlite::Row) -> Result<Task> {
+        Ok(Task {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            description: row.get(2)?,
+            completed: row.get(3)?,
+            priority: Priority::from_string(&row.get::<_, String>(4)?),
+            due_date: row.get(5)?,
+            category_id: row.get(6)?,
+            parent_id: row.get(7)?,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+            recurrence: row.get::<_, Option<String>>(10)?.and_then(|r| r.parse().ok()),
+            deleted_at: row.get(11)?,
+            position: row.get(12)?,
+            tags: vec![], // Filled in by `load_tags`
         })
     }
 
-    pub fn delete_task_and_subtasks(&self, task_id: &str) -> Result<()> {
-        // Get all subtasks recursively
-        let subtasks = self.get_task_hierarchy(Some(task_id.to_string()))?;
-        
-        // Delete all subtasks first
-        for subtask in subtasks {
-            self.delete(&subtask.id)?;
+    /// Fill in the tags of every task with one query, rather than one per
+    /// task. The IDs go in as a single JSON array, so any number fit.
+    fn load_tags(&self, tasks: &mut [Task]) -> Result<()> {
+        if tasks.is_empty() {
+            return Ok(());
+        }
+
+        let ids = serde_json::to_string(&tasks.iter().map(|t| &t.id).collect::<Vec<_>>())
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let mut stmt = self.conn.prepare(
+            "SELECT task_id, tag FROM task_tags
+             WHERE task_id IN (SELECT value FROM json_each(?1))
+             ORDER BY task_id, tag",
+        )?;
+        let rows = stmt.query_map(params![ids], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            let (task_id, tag) = row?;
+            tags.entry(task_id).or_default().push(tag);
+        }
+        for task in tasks {
+            task.tags = tags.remove(&task.id).unwrap_or_default();
         }
-        
-        // Delete the main task
-        self.delete(task_id)?;
-        
         Ok(())
     }
 
-    pub fn delete_task_and_promote_subtasks(&self, task_id: &str) -> Result<()> {
-        // Get the task to be deleted
-        let task = self.get_by_id(task_id)?
-            .ok_or_else(|| rusqlite::Error::QueryReturnedNoRows)?;
-        
-        // Get direct subtasks (only immediate children)
-        let direct_subtasks = self.get_direct_subtasks(task_id)?;
-        
-        // Update all direct subtasks to have the same parent as the task being deleted
-        for mut subtask in direct_subtasks {
-            subtask.parent_id = task.parent_id.clone();
-            subtask.updated_at = chrono::Utc::now().naive_utc();
-            self.update(&subtask)?;
+    /// Every task below `root_id`, or every task when it is `None`, in one
+    /// query. Parents come before their children, and siblings are in
+    /// manual order.
+    pub fn get_subtree(&self, root_id: Option<&str>) -> Result<Vec<TaskNode>> {
+        // The sort key is the chain of (position, id) pairs down to each
+        // task, with positions shifted to be non-negative and padded so they
+        // compare as text. A task already on the path ends it, so a parent
+        // cycle cannot recurse forever
+        let mut stmt = self.conn.prepare(&format!(
+            "WITH RECURSIVE subtree(id, depth, path, sort_key) AS (
+                 SELECT id, 0, json_array(id), printf('%020d', position + {shift}) || id
+                 FROM tasks WHERE parent_id IS ?1 AND deleted_at IS NULL
+                 UNION ALL
+                 SELECT t.id, s.depth + 1, json_insert(s.path, '$[#]', t.id),
+                        s.sort_key || '/' || printf('%020d', t.position + {shift}) || t.id
+                 FROM tasks t JOIN subtree s ON t.parent_id = s.id
+                 WHERE t.deleted_at IS NULL
+                   AND NOT EXISTS (SELECT 1 FROM json_each(s.path) WHERE value = t.id)
+             )
+             SELECT {columns}, depth, path FROM subtree JOIN tasks USING (id) ORDER BY sort_key",
+            shift = 1_i64 << 62,
+            columns = TASK_COLUMNS,
+        ))?;
+
+        let rows = stmt
+            .query_map(params![root_id], |row| {
+                let path: String = row.get(14)?;
+                Ok((Self::map_row(row)?, row.get::<_, u32>(13)?, path))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut tasks: Vec<Task> = Vec::with_capacity(rows.len());
+        let mut places = Vec::with_capacity(rows.len());
+        for (task, depth, path) in rows {
+            let path: Vec<String> = serde_json::from_str(&path)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(14, rusqlite::types::Type::Text, Box::new(e)))?;
+            tasks.push(task);
+            places.push((depth, path));
         }
-        
-        // Now delete the task (it should have no children anymore)
-        self.delete(task_id)?;
-        
-        Ok(())
+        self.load_tags(&mut tasks)?;
+
+        Ok(tasks
+            .into_iter()
+            .zip(places)
+            .map(|(task, (depth, path))| TaskNode { task, depth, path })
+            .collect())
     }
 
-    pub fn get_direct_subtasks(&self, parent_id: &str) -> Result<Vec<Task>> {
+    pub fn get_task_hierarchy(&self, root_id: Option<String>) -> Result<Vec<Task>> {
+        Ok(self.get_subtree(root_id.as_deref())?.into_iter().map(|node| node.task).collect())
+    }
+
+    /// The id of `task_id` followed by the ids of all of its descendants.
+    /// `UNION` rather than `UNION ALL` visits each task once, so this and
+    /// the other walks down the hierarchy end even on a parent cycle.
+    pub fn subtree_ids(&self, task_id: &str) -> Result<Vec<String>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, title, description, completed, priority, due_date, category_id, parent_id, created_at, updated_at
-             FROM tasks WHERE parent_id = ?1 ORDER BY created_at ASC",
+            "WITH RECURSIVE subtree(id) AS (
+                 SELECT ?1
+                 UNION
+                 SELECT t.id FROM tasks t JOIN subtree s ON t.parent_id = s.id
+                 WHERE t.deleted_at IS NULL
+             )
+             SELECT id FROM subtree",
         )?;
+        let ids = stmt.query_map(params![task_id], |row| row.get(0))?;
+        ids.collect()
+    }
 
-        let task_iter = stmt.query_map(params![parent_id], |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                completed: row.get(3)?,
-                priority: Priority::from_string(&row.get::<_, String>(4)?),
-                due_date: row.get(5)?,
-                category_id: row.get(6)?,
-                parent_id: row.get(7)?,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-                tags: vec![], // Will be populated below
-            })
+    /// Tasks that `task_id` depends on, leaving out any in the trash.
+    pub fn get_blockers(&self, task_id: &str) -> Result<Vec<Task>> {
+        let clause = SqlClause {
+            sql: "id IN (SELECT depends_on_id FROM task_dependencies WHERE task_id = ?)".to_string(),
+            params: vec![task_id.to_string().into()],
+        };
+        self.get_all_matching(None, Some(&clause))
+    }
+
+    /// Tasks that depend on `task_id`, leaving out any in the trash.
+    pub fn get_dependents(&self, task_id: &str) -> Result<Vec<Task>> {
+        let clause = SqlClause {
+            sql: "id IN (SELECT task_id FROM task_dependencies WHERE depends_on_id = ?)".to_string(),
+            params: vec![task_id.to_string().into()],
+        };
+        self.get_all_matching(None, Some(&clause))
+    }
+
+    pub fn calculate_task_progress(&self, task_id: &str) -> Result<crate::commands::task_commands::TaskProgress> {
+        let mut progress = self.get_tasks_progress(&[task_id.to_string()])?;
+        Ok(progress.remove(task_id).unwrap_or_default())
+    }
+
+    /// Subtask progress for each of `task_ids`, counting every level below
+    /// it, in a single query.
+    pub fn get_tasks_progress(
+        &self,
+        task_ids: &[String],
+    ) -> Result<HashMap<String, crate::commands::task_commands::TaskProgress>> {
+        let ids = serde_json::to_string(task_ids).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE below(root_id, id, completed) AS (
+                 SELECT j.value, t.id, t.completed
+                 FROM json_each(?1) j JOIN tasks t ON t.parent_id = j.value
+                 WHERE t.deleted_at IS NULL
+                 UNION
+                 SELECT b.root_id, t.id, t.completed FROM tasks t JOIN below b ON t.parent_id = b.id
+                 WHERE t.deleted_at IS NULL
+             )
+             SELECT root_id, COUNT(*), SUM(completed) FROM below GROUP BY root_id",
+        )?;
+        let counts = stmt.query_map(params![ids], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?, row.get::<_, i32>(2)?))
         })?;
 
-        let mut tasks = Vec::new();
-        for task_result in task_iter {
-            let mut task = task_result?;
-            task.tags = self.get_task_tags(&task.id)?;
-            tasks.push(task);
+        let mut progress: HashMap<_, _> = task_ids
+            .iter()
+            .map(|id| (id.clone(), crate::commands::task_commands::TaskProgress::default()))
+            .collect();
+        for count in counts {
+            let (task_id, total_subtasks, completed_subtasks) = count?;
+            progress.insert(task_id, crate::commands::task_commands::TaskProgress {
+                total_subtasks,
+                completed_subtasks,
+                progress_percentage: (completed_subtasks as f32 / total_subtasks as f32) * 100.0,
+                has_subtasks: true,
+            });
         }
+        Ok(progress)
+    }
+
+    /// Move a task and everything below it to the trash, all with the same
+    /// deletion time.
+    pub fn delete_task_and_subtasks(&self, task_id: &str) -> Result<()> {
+        self.conn.execute(
+            "WITH RECURSIVE subtree(id) AS (
+                 SELECT ?1
+                 UNION
+                 SELECT t.id FROM tasks t JOIN subtree s ON t.parent_id = s.id
+                 WHERE t.deleted_at IS NULL
+             )
+             UPDATE tasks SET deleted_at = ?2
+             WHERE id IN (SELECT id FROM subtree) AND deleted_at IS NULL",
+            params![task_id, chrono::Utc::now().naive_utc()],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_task_and_promote_subtasks(&self, task_id: &str) -> Result<()> {
+        self.get_by_id(task_id)?
+            .ok_or_else(|| rusqlite::Error::QueryReturnedNoRows)?;
+        
+        // `delete` hands direct subtasks on to the task's parent
+        self.delete(task_id)
+    }
+
+    /// Create the next instance of a recurring task together with a fresh,
+    /// incomplete copy of its subtask tree.
+    pub fn create_next_occurrence(&self, task: &Task) -> Result<Option<Task>> {
+        atomically(self.conn, || {
+            let next = match task.next_occurrence() {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+            self.create(&next)?;
 
+            // The hierarchy lists every parent before its children, so each copy
+            // can be attached to an already-copied parent
+            let mut new_ids = HashMap::new();
+            new_ids.insert(task.id.clone(), next.id.clone());
+
+            for subtask in self.get_task_hierarchy(Some(task.id.clone()))? {
+                let copy = Task {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    completed: false,
+                    parent_id: subtask.parent_id.as_ref().and_then(|p| new_ids.get(p).cloned()),
+                    created_at: next.created_at,
+                    updated_at: next.updated_at,
+                    ..subtask.clone()
+                };
+                self.create(&copy)?;
+                new_ids.insert(subtask.id, copy.id);
+            }
+
+            // Reminders follow the series onto each new instance
+            let reminder_repo = ReminderRepository::new(self.conn);
+            for (old_id, new_id) in &new_ids {
+                reminder_repo.copy_to_task(old_id, new_id)?;
+            }
+
+            Ok(Some(next))
+        })
+    }
+
+    /// Complete or reopen a task. Completing a recurring task hands the series
+    /// on to its next instance; returns the ids of the tasks created for it.
+    pub fn set_completed(&self, task: &mut Task, completed: bool) -> Result<Vec<String>> {
+        atomically(self.conn, || {
+            task.completed = completed;
+            task.updated_at = chrono::Utc::now().naive_utc();
+
+            let mut created = vec![];
+            if completed {
+                if let Some(next) = self.create_next_occurrence(task)? {
+                    task.recurrence = None;
+                    created = self.subtree_ids(&next.id)?;
+                }
+            }
+
+            self.update(task)?;
+            Ok(created)
+        })
+    }
+
+    pub fn get_direct_subtasks(&self, parent_id: &str) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM tasks WHERE parent_id = ?1 AND deleted_at IS NULL ORDER BY position ASC, created_at ASC",
+            TASK_COLUMNS,
+        ))?;
+
+        let task_iter = stmt.query_map(params![parent_id], Self::map_row)?;
+
+        let mut tasks = task_iter.collect::<Result<Vec<_>>>()?;
+        self.load_tags(&mut tasks)?;
         Ok(tasks)
     }
 
     pub fn has_subtasks(&self, task_id: &str) -> Result<bool> {
-        let mut stmt = self.conn.prepare("SELECT COUNT(*) FROM tasks WHERE parent_id = ?1")?;
+        let mut stmt = self.conn.prepare("SELECT COUNT(*) FROM tasks WHERE parent_id = ?1 AND deleted_at IS NULL")?;
         let count: i64 = stmt.query_row(params![task_id], |row| row.get(0))?;
         Ok(count > 0)
     }
 
+    /// IDs of the tasks with this parent and category, in manual order.
+    /// Tasks in the trash are left out.
+    pub fn sibling_ids(&self, parent_id: Option<&str>, category_id: Option<&str>) -> Result<Vec<String>> {
+        Ok(self.sibling_positions(parent_id, category_id)?.into_iter().map(|(id, _)| id).collect())
+    }
+
+    fn sibling_positions(&self, parent_id: Option<&str>, category_id: Option<&str>) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, position FROM tasks
+             WHERE parent_id IS ?1 AND category_id IS ?2 AND deleted_at IS NULL
+             ORDER BY position ASC, created_at ASC",
+        )?;
+        let rows = stmt.query_map(params![parent_id, category_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Put a task under `parent_id` (or at the top level), directly before
+    /// `before_id` or after `after_id`, or last without either. Anchors are
+    /// expected to be siblings in the target list. Only the moved task gets a
+    /// new position, unless its neighbours are too close together, in which
+    /// case just that list is renumbered.
+    pub fn move_task(&self, id: &str, parent_id: Option<&str>, before_id: Option<&str>, after_id: Option<&str>) -> Result<()> {
+        atomically(self.conn, || {
+            let Some(task) = self.get_by_id(id)? else {
+                return Err(rusqlite::Error::QueryReturnedNoRows);
+            };
+
+            let mut siblings = self.sibling_positions(parent_id, task.category_id.as_deref())?;
+            siblings.retain(|(sibling_id, _)| sibling_id != id);
+
+            let index = match (before_id, after_id) {
+                (Some(before_id), _) => siblings.iter().position(|(s, _)| s == before_id),
+                (None, Some(after_id)) => siblings.iter().position(|(s, _)| s == after_id).map(|i| i + 1),
+                (None, None) => None,
+            }
+            .unwrap_or(siblings.len());
+
+            let previous = index.checked_sub(1).map(|i| siblings[i].1);
+            let next = siblings.get(index).map(|(_, position)| *position);
+            let position = match (previous, next) {
+                (None, None) => Some(task.position),
+                (Some(previous), None) => previous.checked_add(POSITION_STEP),
+                (None, Some(next)) => next.checked_sub(POSITION_STEP),
+                (Some(previous), Some(next)) if next - previous >= 2 => Some(previous + (next - previous) / 2),
+                (Some(_), Some(_)) => None,
+            };
+
+            let position = match position {
+                Some(position) => position,
+                None => {
+                    // No room left between the neighbours: space the list out again
+                    siblings.insert(index, (id.to_string(), 0));
+                    let mut moved_position = 0;
+                    for (i, (sibling_id, old_position)) in siblings.iter().enumerate() {
+                        let new_position = (i as i64 + 1) * POSITION_STEP;
+                        if sibling_id == id {
+                            moved_position = new_position;
+                        } else if *old_position != new_position {
+                            self.conn.execute(
+                                "UPDATE tasks SET position = ?1 WHERE id = ?2",
+                                params![new_position, sibling_id],
+                            )?;
+                        }
+                    }
+                    moved_position
+                }
+            };
+
+            self.conn.execute(
+                "UPDATE tasks SET parent_id = ?1, position = ?2, updated_at = ?3 WHERE id = ?4",
+                params![parent_id, position, chrono::Utc::now().naive_utc(), id],
+            )?;
+            Ok(())
+        })
+    }
+
     pub fn get_root_tasks(&self) -> Result<Vec<Task>> {
         let _filter = TaskFilter {
             completed: None,
@@ -360,36 +852,31 @@ impl<'a> TaskRepository<'a> {
         };
 
         // Override the filter to get tasks with NULL parent_id
-        let query = "SELECT id, title, description, completed, priority, due_date, category_id, parent_id, created_at, updated_at FROM tasks WHERE parent_id IS NULL ORDER BY created_at DESC".to_string();
+        let query = format!("SELECT {} FROM tasks WHERE parent_id IS NULL AND deleted_at IS NULL ORDER BY position ASC, created_at DESC", TASK_COLUMNS);
         
         let mut stmt = self.conn.prepare(&query)?;
-        let task_iter = stmt.query_map([], |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                completed: row.get(3)?,
-                priority: Priority::from_string(&row.get::<_, String>(4)?),
-                due_date: row.get(5)?,
-                category_id: row.get(6)?,
-                parent_id: row.get(7)?,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-                tags: vec![], // Will be populated below
-            })
-        })?;
-
-        let mut tasks = Vec::new();
-        for task_result in task_iter {
-            let mut task = task_result?;
-            task.tags = self.get_task_tags(&task.id)?;
-            tasks.push(task);
-        }
+        let task_iter = stmt.query_map([], Self::map_row)?;
 
+        let mut tasks = task_iter.collect::<Result<Vec<_>>>()?;
+        self.load_tags(&mut tasks)?;
         Ok(tasks)
     }
 }
 
+/// The SQL value a sort orders by. Each is never NULL, so it can be compared
+/// against a cursor.
+fn sort_key_sql(field: SortField) -> &'static str {
+    match field {
+        SortField::Title => "LOWER(title)",
+        SortField::DueDate => "COALESCE(due_date, '9999-12-31 23:59:59')",
+        SortField::CreatedAt => "created_at",
+        SortField::UpdatedAt => "updated_at",
+        SortField::Priority => "CASE priority WHEN 'High' THEN 3 WHEN 'Low' THEN 1 ELSE 2 END",
+        SortField::Completed => "completed",
+        SortField::Position => "position",
+    }
+}
+
 pub struct CategoryRepository<'a> {
     conn: &'a Connection,
 }
@@ -464,3 +951,506 @@ impl<'a> CategoryRepository<'a> {
         Ok(())
     }
 }
+
+pub struct ReminderRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> ReminderRepository<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    pub fn create(&self, reminder: &Reminder) -> Result<()> {
+        let (kind, minutes) = reminder.offset.to_columns();
+        self.conn.execute(
+            "INSERT INTO task_reminders (id, task_id, kind, minutes, fired_for_due, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                reminder.id,
+                reminder.task_id,
+                kind,
+                minutes,
+                reminder.fired_for_due,
+                reminder.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Result<Option<Reminder>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, task_id, kind, minutes, fired_for_due, created_at
+             FROM task_reminders WHERE id = ?1",
+        )?;
+
+        match stmt.query_row(params![id], Self::map_row) {
+            Ok(reminder) => Ok(Some(reminder)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn get_for_task(&self, task_id: &str) -> Result<Vec<Reminder>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, task_id, kind, minutes, fired_for_due, created_at
+             FROM task_reminders WHERE task_id = ?1 ORDER BY created_at ASC",
+        )?;
+
+        let reminder_iter = stmt.query_map(params![task_id], Self::map_row)?;
+
+        let mut reminders = Vec::new();
+        for reminder_result in reminder_iter {
+            reminders.push(reminder_result?);
+        }
+        Ok(reminders)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM task_reminders WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn delete_for_task(&self, task_id: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM task_reminders WHERE task_id = ?1", params![task_id])?;
+        Ok(())
+    }
+
+    /// Give `to_task_id` an unfired copy of every reminder on `from_task_id`.
+    pub fn copy_to_task(&self, from_task_id: &str, to_task_id: &str) -> Result<()> {
+        atomically(self.conn, || {
+            for reminder in self.get_for_task(from_task_id)? {
+                self.create(&Reminder::new(to_task_id.to_string(), reminder.offset))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Reminders on open tasks whose fire time is at or before `now` and that
+    /// have not fired for the task's current due date. Anything missed while
+    /// the app was closed is included, so it can be caught up on startup.
+    pub fn get_due(&self, now: chrono::NaiveDateTime) -> Result<Vec<DueReminder>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT r.id, r.kind, r.minutes, t.id, t.title, t.due_date
+             FROM task_reminders r JOIN tasks t ON t.id = r.task_id
+             WHERE t.completed = 0 AND t.due_date IS NOT NULL AND t.deleted_at IS NULL
+               AND (r.fired_for_due IS NULL OR r.fired_for_due != t.due_date)",
+        )?;
+
+        let row_iter = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                ReminderOffset::from_columns(&row.get::<_, String>(1)?, row.get(2)?),
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, chrono::NaiveDateTime>(5)?,
+            ))
+        })?;
+
+        let mut due = Vec::new();
+        for row_result in row_iter {
+            let (reminder_id, offset, task_id, task_title, due_date) = row_result?;
+            if let Some(fire_at) = offset.fire_time(due_date).filter(|at| *at <= now) {
+                due.push(DueReminder { reminder_id, task_id, task_title, due_date, fire_at });
+            }
+        }
+        due.sort_by_key(|r| r.fire_at);
+        Ok(due)
+    }
+
+    fn map_row(row: &rusqlite::Row) -> Result<Reminder> {
+        Ok(Reminder {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            offset: ReminderOffset::from_columns(&row.get::<_, String>(2)?, row.get(3)?),
+            fired_for_due: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }
+
+    pub fn mark_fired(&self, id: &str, due_date: chrono::NaiveDateTime) -> Result<()> {
+        self.conn.execute(
+            "UPDATE task_reminders SET fired_for_due = ?1 WHERE id = ?2",
+            params![due_date, id],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct DependencyRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> DependencyRepository<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Record that `task_id` cannot start until `depends_on_id` is done.
+    /// Adding a link that already exists is a no-op.
+    pub fn add(&self, task_id: &str, depends_on_id: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_id, created_at) VALUES (?1, ?2, ?3)",
+            params![task_id, depends_on_id, chrono::Utc::now().naive_utc()],
+        )?;
+        Ok(())
+    }
+
+    /// Remove the link from `task_id` to `depends_on_id`. Returns whether
+    /// there was one.
+    pub fn remove(&self, task_id: &str, depends_on_id: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM task_dependencies WHERE task_id = ?1 AND depends_on_id = ?2",
+            params![task_id, depends_on_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// IDs of the tasks `task_id` depends on, oldest link first.
+    pub fn get_for_task(&self, task_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT depends_on_id FROM task_dependencies WHERE task_id = ?1 ORDER BY created_at ASC, depends_on_id ASC",
+        )?;
+        let ids = stmt.query_map(params![task_id], |row| row.get(0))?;
+        ids.collect()
+    }
+
+    /// Every link between two tasks that are not in the trash, oldest first.
+    pub fn get_all(&self) -> Result<Vec<TaskDependency>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.task_id, d.depends_on_id FROM task_dependencies d
+             JOIN tasks t ON t.id = d.task_id AND t.deleted_at IS NULL
+             JOIN tasks b ON b.id = d.depends_on_id AND b.deleted_at IS NULL
+             ORDER BY d.created_at ASC, d.task_id ASC, d.depends_on_id ASC",
+        )?;
+        let links = stmt.query_map([], |row| {
+            Ok(TaskDependency {
+                task_id: row.get(0)?,
+                depends_on_id: row.get(1)?,
+            })
+        })?;
+        links.collect()
+    }
+
+    /// Replace every link from `task_id` with `depends_on_ids`.
+    pub fn set_for_task(&self, task_id: &str, depends_on_ids: &[String]) -> Result<()> {
+        atomically(self.conn, || {
+            self.conn.execute("DELETE FROM task_dependencies WHERE task_id = ?1", params![task_id])?;
+            for depends_on_id in depends_on_ids {
+                self.add(task_id, depends_on_id)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Whether linking `task_id` to `depends_on_id` would close a loop, i.e.
+    /// `depends_on_id` already depends on `task_id`, directly or not.
+    pub fn would_create_cycle(&self, task_id: &str, depends_on_id: &str) -> Result<bool> {
+        if task_id == depends_on_id {
+            return Ok(true);
+        }
+        self.conn.query_row(
+            "WITH RECURSIVE upstream(id) AS (
+                SELECT depends_on_id FROM task_dependencies WHERE task_id = ?1
+                UNION
+                SELECT d.depends_on_id FROM task_dependencies d JOIN upstream u ON d.task_id = u.id
+            )
+            SELECT EXISTS (SELECT 1 FROM upstream WHERE id = ?2)",
+            params![depends_on_id, task_id],
+            |row| row.get(0),
+        )
+    }
+}
+
+pub struct CalDavRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> CalDavRepository<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    pub fn get_calendars(&self) -> Result<Vec<CalDavCalendar>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT url, category_id, display_name, sync_token, synced_at FROM caldav_calendars ORDER BY display_name",
+        )?;
+        let calendars = stmt.query_map([], |row| {
+            Ok(CalDavCalendar {
+                url: row.get(0)?,
+                category_id: row.get(1)?,
+                display_name: row.get(2)?,
+                sync_token: row.get(3)?,
+                synced_at: row.get(4)?,
+            })
+        })?;
+        calendars.collect()
+    }
+
+    pub fn save_calendar(&self, calendar: &CalDavCalendar) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO caldav_calendars (url, category_id, display_name, sync_token, synced_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(url) DO UPDATE SET category_id = excluded.category_id, display_name = excluded.display_name,
+                sync_token = excluded.sync_token, synced_at = excluded.synced_at",
+            params![calendar.url, calendar.category_id, calendar.display_name, calendar.sync_token, calendar.synced_at],
+        )?;
+        Ok(())
+    }
+
+    /// Forget a calendar and the sync state of every task in it.
+    pub fn delete_calendar(&self, url: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM caldav_calendars WHERE url = ?1", params![url])?;
+        Ok(())
+    }
+
+    pub fn get_task_states(&self) -> Result<Vec<TaskSyncState>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT task_id, calendar_url, href, uid, etag, fingerprint, synced_at FROM caldav_task_sync",
+        )?;
+        let states = stmt.query_map([], |row| {
+            Ok(TaskSyncState {
+                task_id: row.get(0)?,
+                calendar_url: row.get(1)?,
+                href: row.get(2)?,
+                uid: row.get(3)?,
+                etag: row.get(4)?,
+                fingerprint: row.get(5)?,
+                synced_at: row.get(6)?,
+            })
+        })?;
+        states.collect()
+    }
+
+    pub fn save_task_state(&self, state: &TaskSyncState) -> Result<()> {
+        atomically(self.conn, || {
+            // A task that moved calendars takes over the new href
+            self.conn.execute(
+                "DELETE FROM caldav_task_sync WHERE href = ?1 AND task_id <> ?2",
+                params![state.href, state.task_id],
+            )?;
+            self.conn.execute(
+                "INSERT INTO caldav_task_sync (task_id, calendar_url, href, uid, etag, fingerprint, synced_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(task_id) DO UPDATE SET calendar_url = excluded.calendar_url, href = excluded.href,
+                    uid = excluded.uid, etag = excluded.etag, fingerprint = excluded.fingerprint, synced_at = excluded.synced_at",
+                params![state.task_id, state.calendar_url, state.href, state.uid, state.etag, state.fingerprint, state.synced_at],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn delete_task_state(&self, task_id: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM caldav_task_sync WHERE task_id = ?1", params![task_id])?;
+        Ok(())
+    }
+
+    /// Forget every calendar and task sync state, e.g. when the account changes.
+    pub fn clear(&self) -> Result<()> {
+        atomically(self.conn, || {
+            self.conn.execute("DELETE FROM caldav_task_sync", [])?;
+            self.conn.execute("DELETE FROM caldav_calendars", [])?;
+            Ok(())
+        })
+    }
+}
+
+pub struct SettingsRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> SettingsRepository<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        match self.conn.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        ) {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Days a task stays in the trash before it is purged; 0 keeps it forever.
+    pub fn trash_retention_days(&self) -> Result<u32> {
+        Ok(self
+            .get(TRASH_RETENTION_DAYS_KEY)?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS))
+    }
+
+    pub fn set_trash_retention_days(&self, days: u32) -> Result<()> {
+        self.set(TRASH_RETENTION_DAYS_KEY, &days.to_string())
+    }
+
+    pub fn delete(&self, key: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM settings WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
+    /// The todo.txt file kept in sync with the app, if any.
+    pub fn todotxt_sync_path(&self) -> Result<Option<String>> {
+        self.get(TODOTXT_SYNC_PATH_KEY)
+    }
+
+    /// Choose (or with `None` stop syncing with) a todo.txt file. The record
+    /// of the last sync belongs to the old file and is dropped.
+    pub fn set_todotxt_sync_path(&self, path: Option<&str>) -> Result<()> {
+        atomically(self.conn, || {
+            self.delete(TODOTXT_SYNC_SNAPSHOT_KEY)?;
+            match path {
+                Some(path) => self.set(TODOTXT_SYNC_PATH_KEY, path),
+                None => self.delete(TODOTXT_SYNC_PATH_KEY),
+            }
+        })
+    }
+
+    /// The file contents written by the last todo.txt sync.
+    pub fn todotxt_sync_snapshot(&self) -> Result<Option<String>> {
+        self.get(TODOTXT_SYNC_SNAPSHOT_KEY)
+    }
+
+    pub fn set_todotxt_sync_snapshot(&self, text: &str) -> Result<()> {
+        self.set(TODOTXT_SYNC_SNAPSHOT_KEY, text)
+    }
+
+    /// The Markdown vault whose checkboxes are kept in sync, if any.
+    pub fn markdown_vault_path(&self) -> Result<Option<String>> {
+        self.get(MARKDOWN_VAULT_PATH_KEY)
+    }
+
+    /// Choose (or with `None` stop syncing with) a Markdown vault. The record
+    /// of the last sync belongs to the old vault and is dropped.
+    pub fn set_markdown_vault_path(&self, path: Option<&str>) -> Result<()> {
+        atomically(self.conn, || {
+            self.delete(MARKDOWN_SYNC_SNAPSHOT_KEY)?;
+            match path {
+                Some(path) => self.set(MARKDOWN_VAULT_PATH_KEY, path),
+                None => self.delete(MARKDOWN_VAULT_PATH_KEY),
+            }
+        })
+    }
+
+    /// The state of every tracked checkbox as of the last vault sync, as JSON.
+    pub fn markdown_sync_snapshot(&self) -> Result<Option<String>> {
+        self.get(MARKDOWN_SYNC_SNAPSHOT_KEY)
+    }
+
+    pub fn set_markdown_sync_snapshot(&self, json: &str) -> Result<()> {
+        self.set(MARKDOWN_SYNC_SNAPSHOT_KEY, json)
+    }
+
+    /// The CalDAV account to sync with, if one is set up.
+    pub fn caldav_account(&self) -> Result<Option<CalDavAccount>> {
+        let Some(url) = self.get(CALDAV_URL_KEY)? else {
+            return Ok(None);
+        };
+        Ok(Some(CalDavAccount {
+            url,
+            username: self.get(CALDAV_USERNAME_KEY)?.unwrap_or_default(),
+            password: self.get(CALDAV_PASSWORD_KEY)?.unwrap_or_default(),
+            conflict_policy: ConflictPolicy::from_string(&self.get(CALDAV_CONFLICT_POLICY_KEY)?.unwrap_or_default()),
+        }))
+    }
+
+    /// Set up (or with `None` stop syncing with) a CalDAV account. Calendars
+    /// and task sync state belong to the old server and are dropped when the
+    /// server or user changes.
+    pub fn set_caldav_account(&self, account: Option<&CalDavAccount>) -> Result<()> {
+        atomically(self.conn, || {
+            let current = self.caldav_account()?;
+            let same_server = match (&current, account) {
+                (Some(current), Some(account)) => current.url == account.url && current.username == account.username,
+                _ => false,
+            };
+            if !same_server {
+                CalDavRepository::new(self.conn).clear()?;
+            }
+
+            match account {
+                Some(account) => {
+                    self.set(CALDAV_URL_KEY, &account.url)?;
+                    self.set(CALDAV_USERNAME_KEY, &account.username)?;
+                    self.set(CALDAV_PASSWORD_KEY, &account.password)?;
+                    self.set(CALDAV_CONFLICT_POLICY_KEY, account.conflict_policy.as_str())
+                }
+                None => {
+                    for key in [CALDAV_URL_KEY, CALDAV_USERNAME_KEY, CALDAV_PASSWORD_KEY, CALDAV_CONFLICT_POLICY_KEY] {
+                        self.delete(key)?;
+                    }
+                    Ok(())
+                }
+            }
+        })
+    }
+
+    /// Settings of the local HTTP API. A token is generated the first time
+    /// they are read, so there is never an API without one.
+    pub fn api_settings(&self) -> Result<ApiSettings> {
+        let token = match self.get(API_TOKEN_KEY)? {
+            Some(token) => token,
+            None => {
+                let token = new_api_token();
+                self.set(API_TOKEN_KEY, &token)?;
+                token
+            }
+        };
+        Ok(ApiSettings {
+            enabled: self.get(API_ENABLED_KEY)?.as_deref() == Some("true"),
+            port: self.get(API_PORT_KEY)?.and_then(|p| p.parse().ok()).unwrap_or(DEFAULT_API_PORT),
+            token,
+        })
+    }
+
+    pub fn set_api_settings(&self, settings: &ApiSettings) -> Result<()> {
+        atomically(self.conn, || {
+            self.set(API_ENABLED_KEY, if settings.enabled { "true" } else { "false" })?;
+            self.set(API_PORT_KEY, &settings.port.to_string())?;
+            self.set(API_TOKEN_KEY, &settings.token)
+        })
+    }
+
+    /// Replace the API token, locking out every client that has the old one.
+    pub fn regenerate_api_token(&self) -> Result<String> {
+        let token = new_api_token();
+        self.set(API_TOKEN_KEY, &token)?;
+        Ok(token)
+    }
+}
+
+/// 244 random bits, hex encoded
+fn new_api_token() -> String {
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// Space left between neighbouring tasks when a list is renumbered, so many
+/// moves fit in before it has to happen again
+const POSITION_STEP: i64 = 1 << 16;
+
+const TRASH_RETENTION_DAYS_KEY: &str = "trash_retention_days";
+const TODOTXT_SYNC_PATH_KEY: &str = "todotxt_sync_path";
+const TODOTXT_SYNC_SNAPSHOT_KEY: &str = "todotxt_sync_snapshot";
+const MARKDOWN_VAULT_PATH_KEY: &str = "markdown_vault_path";
+const MARKDOWN_SYNC_SNAPSHOT_KEY: &str = "markdown_sync_snapshot";
+const CALDAV_URL_KEY: &str = "caldav_url";
+const CALDAV_USERNAME_KEY: &str = "caldav_username";
+const CALDAV_PASSWORD_KEY: &str = "caldav_password";
+const CALDAV_CONFLICT_POLICY_KEY: &str = "caldav_conflict_policy";
+const API_ENABLED_KEY: &str = "api_enabled";
+const API_PORT_KEY: &str = "api_port";
+const API_TOKEN_KEY: &str = "api_token";
+const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
@@ -0,0 +1,199 @@
+use std::fmt;
+
+/// Marks the start of a matched term in search highlights and snippets
+pub const HIGHLIGHT_START: &str = "\u{2}";
+/// Marks the end of a matched term in search highlights and snippets
+pub const HIGHLIGHT_END: &str = "\u{3}";
+
+/// A search that FTS5 cannot express: one where something is only
+/// excluded, with nothing to look for next to it (`-draft`, `a OR -b`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQueryError(pub String);
+
+impl fmt::Display for SearchQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for SearchQueryError {}
+
+/// Translate a user-typed search string into an FTS5 MATCH expression.
+///
+/// Supported syntax:
+/// - bare words match as prefixes (`rep` finds "report"), a trailing `*` is accepted
+/// - `"quoted phrases"` match exactly
+/// - `AND`, `OR`, `NOT` and parentheses work as in FTS5; terms are ANDed by default
+/// - `-word` excludes a term
+///
+/// Everything else is quoted, so arbitrary input never produces an FTS5
+/// syntax error. FTS5 only knows `a NOT b`, so exclusions are moved after
+/// the terms they are ANDed with (`-draft report` becomes `report NOT
+/// draft`); an exclusion with nothing to exclude from is an error rather
+/// than being dropped. Returns `None` when the input contains nothing
+/// searchable.
+pub fn to_fts_query(input: &str) -> Result<Option<String>, SearchQueryError> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut depth = 0;
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                depth += 1;
+                parts.push("(".to_string());
+            }
+            ')' => {
+                chars.next();
+                if depth > 0 {
+                    depth -= 1;
+                    parts.push(")".to_string());
+                }
+            }
+            '"' => {
+                chars.next();
+                let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                if let Some(term) = quote(&phrase, false) {
+                    parts.push(term);
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+
+                match word.as_str() {
+                    "AND" | "OR" | "NOT" => parts.push(word),
+                    _ => {
+                        let (negated, word) = match word.strip_prefix('-') {
+                            Some(rest) if !rest.is_empty() => (true, rest),
+                            _ => (false, word.as_str()),
+                        };
+                        if let Some(term) = quote(word.trim_end_matches('*'), true) {
+                            if negated {
+                                parts.push("NOT".to_string());
+                            }
+                            parts.push(term);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for _ in 0..depth {
+        parts.push(")".to_string());
+    }
+
+    let clauses = parse_clauses(&mut parts.into_iter());
+    if clauses.is_empty() {
+        return Ok(None);
+    }
+    render_clauses(&clauses).map(Some)
+}
+
+fn quote(text: &str, prefix: bool) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() || !text.chars().any(char::is_alphanumeric) {
+        return None;
+    }
+    let quoted = format!("\"{}\"", words.join(" ").replace('"', "\"\""));
+    Some(if prefix { format!("{}*", quoted) } else { quoted })
+}
+
+enum Node {
+    Term(String),
+    Group(Vec<Clause>),
+}
+
+/// Operands ANDed together, some of them negated. Clauses are ORed.
+#[derive(Default)]
+struct Clause {
+    include: Vec<Node>,
+    exclude: Vec<Node>,
+}
+
+/// Read tokens up to the `)` closing the current group, or the end. Operators
+/// missing an operand (e.g. a trailing `OR` while the user is still typing)
+/// and empty groups are dropped; they cannot turn an exclusion into a match.
+fn parse_clauses(tokens: &mut impl Iterator<Item = String>) -> Vec<Clause> {
+    let mut clauses = vec![Clause::default()];
+    let mut negated = false;
+
+    while let Some(token) = tokens.next() {
+        let node = match token.as_str() {
+            ")" => break,
+            "AND" => continue,
+            "OR" => {
+                clauses.push(Clause::default());
+                negated = false;
+                continue;
+            }
+            "NOT" => {
+                negated = true;
+                continue;
+            }
+            "(" => Node::Group(parse_clauses(tokens)),
+            _ => Node::Term(token),
+        };
+
+        let clause = clauses.last_mut().expect("there is always a clause");
+        match node {
+            Node::Group(inner) if inner.is_empty() => {}
+            // A group without OR is ANDed straight into this clause, so its
+            // exclusions can use this clause's terms
+            Node::Group(mut inner) if inner.len() == 1 && !negated => {
+                let inner = inner.pop().expect("group has one clause");
+                clause.include.extend(inner.include);
+                clause.exclude.extend(inner.exclude);
+            }
+            node if negated => clause.exclude.push(node),
+            node => clause.include.push(node),
+        }
+        negated = false;
+    }
+
+    clauses.retain(|c| !c.include.is_empty() || !c.exclude.is_empty());
+    clauses
+}
+
+fn render_clauses(clauses: &[Clause]) -> Result<String, SearchQueryError> {
+    let rendered = clauses.iter().map(render_clause).collect::<Result<Vec<_>, _>>()?;
+    Ok(rendered.join(" OR "))
+}
+
+fn render_clause(clause: &Clause) -> Result<String, SearchQueryError> {
+    if clause.include.is_empty() {
+        return Err(SearchQueryError(
+            "Nothing to exclude from: add a word to search for next to '-word' or NOT".to_string(),
+        ));
+    }
+
+    let include = clause.include.iter().map(render_node).collect::<Result<Vec<_>, _>>()?;
+    let mut out = if include.len() > 1 && !clause.exclude.is_empty() {
+        format!("({})", include.join(" "))
+    } else {
+        include.join(" ")
+    };
+    for node in &clause.exclude {
+        out.push_str(" NOT ");
+        out.push_str(&render_node(node)?);
+    }
+    Ok(out)
+}
+
+fn render_node(node: &Node) -> Result<String, SearchQueryError> {
+    match node {
+        Node::Term(term) => Ok(term.clone()),
+        Node::Group(clauses) => Ok(format!("({})", render_clauses(clauses)?)),
+    }
+}
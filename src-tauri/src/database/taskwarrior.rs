@@ -0,0 +1,452 @@
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use rusqlite::Connection;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::database::{CategoryRepository, DependencyRepository, TaskRepository};
+use crate::interop::{break_parent_cycles, ImportSummary};
+use crate::models::{Frequency, Priority, RecurrenceRule, Task, TaskFilter};
+use crate::utils::AppError;
+
+/// Timestamp format Taskwarrior uses in its JSON, always UTC
+const TASKWARRIOR_DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// One task as written by `task export` and read by `task import`. Unknown
+/// attributes (`id`, `urgency`, user-defined ones) are ignored on import.
+///
+/// `pluto_parent` is a user-defined attribute carrying the subtask
+/// hierarchy, which Taskwarrior has no field for; Taskwarrior keeps it
+/// untouched so a later import can restore the hierarchy.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskwarriorTask {
+    #[serde(default)]
+    pub uuid: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    /// Older Taskwarrior versions write a comma-separated string, newer ones a list
+    #[serde(default, deserialize_with = "deserialize_depends", skip_serializing_if = "Vec::is_empty")]
+    pub depends: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<TaskwarriorAnnotation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recur: Option<String>,
+    /// For a recurring instance, the UUID of its template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pluto_parent: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskwarriorAnnotation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    One(String),
+    Many(Vec<String>),
+}
+
+fn deserialize_depends<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(match Option::<StringOrList>::deserialize(deserializer)? {
+        Some(StringOrList::One(list)) => list.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
+        Some(StringOrList::Many(list)) => list,
+        None => Vec::new(),
+    })
+}
+
+/// Read `task export` output: a JSON array, or one object per line as
+/// written by older Taskwarrior versions.
+pub fn parse_taskwarrior(text: &str) -> Result<Vec<TaskwarriorTask>, AppError> {
+    let text = text.trim_start_matches('\u{feff}').trim();
+    if text.starts_with('[') {
+        return serde_json::from_str(text).map_err(|e| AppError::InvalidInput(format!("Not a Taskwarrior export: {}", e)));
+    }
+
+    let mut tasks = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim().trim_end_matches(',');
+        if line.is_empty() {
+            continue;
+        }
+        let task = serde_json::from_str(line)
+            .map_err(|e| AppError::InvalidInput(format!("Line {} is not a Taskwarrior task: {}", index + 1, e)))?;
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+/// Import Taskwarrior tasks in a single transaction. Tasks are keyed on
+/// their UUID, so importing the same export again updates rather than
+/// duplicates.
+///
+/// - `project` becomes the category (created if needed), `tags` the tags
+/// - priority H/M/L maps to high/medium/low; no priority is medium
+/// - `completed` tasks are imported as done and `deleted` ones skipped
+/// - annotations are joined into the description
+/// - `depends` becomes dependency links, skipping any that would form a cycle
+/// - of a recurring series only the open instance due last keeps the
+///   recurrence, so Pluto does not spawn one occurrence per instance; a
+///   template without instances in the file is imported as the recurring task
+pub fn import_taskwarrior(conn: &Connection, items: &[TaskwarriorTask]) -> Result<ImportSummary, AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let dependency_repo = DependencyRepository::new(conn);
+    let mut categories = CategoryRepository::new(conn).get_all()?;
+    let mut summary = ImportSummary::default();
+
+    let tx = conn.unchecked_transaction()?;
+    // Tasks may come before the tasks they depend on or belong to
+    tx.pragma_update(None, "defer_foreign_keys", true)?;
+
+    let templates_with_instances: HashSet<&str> = items.iter().filter_map(|t| t.parent.as_deref()).collect();
+
+    // The open instance due last in each series carries the recurrence
+    let mut series_heads: HashMap<&str, (&str, Option<NaiveDateTime>)> = HashMap::new();
+    for item in items.iter().filter(|t| t.status == "pending" || t.status == "waiting") {
+        if let Some(ref template) = item.parent {
+            let due = item.due.as_deref().and_then(parse_timestamp);
+            let head = series_heads.entry(template).or_insert((&item.uuid, due));
+            if due > head.1 {
+                *head = (&item.uuid, due);
+            }
+        }
+    }
+
+    let mut deleted = 0;
+    let mut tasks = Vec::new();
+    let mut depends = Vec::new();
+    let mut seen = HashSet::new();
+    for item in items {
+        if item.status == "deleted" {
+            deleted += 1;
+            continue;
+        }
+        if item.status == "recurring" && templates_with_instances.contains(item.uuid.as_str()) {
+            continue;
+        }
+        let title = item.description.trim();
+        if title.is_empty() {
+            summary.warnings.push(format!("Skipped a task without a description (UUID {})", item.uuid));
+            continue;
+        }
+
+        let id = if item.uuid.trim().is_empty() { uuid::Uuid::new_v4().to_string() } else { item.uuid.clone() };
+        if !seen.insert(id.clone()) {
+            summary.warnings.push(format!("Skipped '{}': UUID {} appears more than once", title, id));
+            continue;
+        }
+
+        let existing = task_repo.get_by_id(&id)?;
+        if existing.is_none() && task_repo.row_exists(&id)? {
+            summary.warnings.push(format!("Skipped '{}': the task is in the trash", title));
+            continue;
+        }
+
+        let now = Utc::now().naive_utc();
+        let created_at = item.entry.as_deref().and_then(parse_timestamp).unwrap_or(now);
+        let mut task = existing.clone().unwrap_or_else(|| Task {
+            id: id.clone(),
+            title: String::new(),
+            description: None,
+            completed: false,
+            priority: Priority::Medium,
+            due_date: None,
+            category_id: None,
+            tags: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+            created_at,
+            updated_at: now,
+            deleted_at: None,
+            position: Task::initial_position(created_at, false),
+        });
+
+        task.title = title.to_string();
+        let notes: Vec<&str> = item.annotations.iter().map(|a| a.description.trim()).filter(|d| !d.is_empty()).collect();
+        task.description = (!notes.is_empty()).then(|| notes.join("\n"));
+        task.completed = item.status == "completed";
+        task.priority = match item.priority.as_deref().map(|p| p.trim().to_ascii_uppercase()).as_deref() {
+            Some("H") => Priority::High,
+            Some("L") => Priority::Low,
+            _ => Priority::Medium,
+        };
+        task.due_date = item.due.as_deref().and_then(parse_timestamp).map(import_due);
+        task.updated_at = item.modified.as_deref().and_then(parse_timestamp).unwrap_or(now);
+
+        task.category_id = match item.project.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+            Some(project) => Some(summary.find_or_create_category(conn, &mut categories, project)?),
+            None => None,
+        };
+        task.tags = Vec::new();
+        for tag in &item.tags {
+            if !tag.trim().is_empty() && !task.tags.contains(tag) {
+                task.tags.push(tag.clone());
+            }
+        }
+
+        let carries_recurrence = match item.parent {
+            Some(ref template) => series_heads.get(template.as_str()).map(|(head, _)| *head == item.uuid).unwrap_or(false),
+            None => item.status == "recurring",
+        };
+        task.recurrence = match item.recur {
+            Some(ref recur) if carries_recurrence => match parse_recur(recur) {
+                Some(mut rule) => {
+                    rule.until = item.until.as_deref().and_then(parse_timestamp);
+                    Some(rule)
+                }
+                None => {
+                    summary.warnings.push(format!("'{}': recurrence '{}' not supported, imported as a one-off task", task.title, recur));
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        task.parent_id = item.pluto_parent.clone().filter(|p| p != &task.id);
+
+        depends.push((id, &item.depends));
+        tasks.push((task, existing.is_some()));
+    }
+    if deleted > 0 {
+        summary.warnings.push(format!("Skipped {} deleted task(s)", deleted));
+    }
+
+    // Parents must be in this file or already exist, and must not lead back
+    // to the task
+    let file_ids: HashSet<String> = tasks.iter().map(|(t, _)| t.id.clone()).collect();
+    for (task, _) in &mut tasks {
+        if let Some(ref parent_id) = task.parent_id {
+            if !file_ids.contains(parent_id) && task_repo.get_by_id(parent_id)?.is_none() {
+                summary.warnings.push(format!("'{}': parent {} not found, imported as a top-level task", task.title, parent_id));
+                task.parent_id = None;
+            }
+        }
+    }
+    break_parent_cycles(conn, tasks.iter_mut().map(|(t, _)| t), &mut summary.warnings)?;
+
+    for (task, exists) in &tasks {
+        if *exists {
+            task_repo.update(task)?;
+            summary.tasks_updated += 1;
+        } else {
+            task_repo.create(task)?;
+            summary.tasks_created += 1;
+        }
+        summary.task_ids.push(task.id.clone());
+    }
+
+    // Links go in once every task exists, so cycles can be checked against
+    // the links already added. The file only speaks for links between its
+    // own tasks; links to tasks outside it are kept.
+    let mut linked: HashSet<(String, String)> = HashSet::new();
+    for (id, _) in &depends {
+        for depends_on_id in dependency_repo.get_for_task(id)? {
+            if file_ids.contains(&depends_on_id) {
+                dependency_repo.remove(id, &depends_on_id)?;
+            } else {
+                linked.insert((id.clone(), depends_on_id));
+            }
+        }
+    }
+    for (id, depends_on) in depends {
+        for depends_on_id in depends_on {
+            if linked.contains(&(id.clone(), depends_on_id.clone())) {
+                continue;
+            }
+            if !file_ids.contains(depends_on_id) && task_repo.get_by_id(depends_on_id)?.is_none() {
+                summary.warnings.push(format!("Task {} depends on unknown task {}; link skipped", id, depends_on_id));
+            } else if dependency_repo.would_create_cycle(&id, depends_on_id)? {
+                summary.warnings.push(format!("Task {} depending on {} would form a cycle; link skipped", id, depends_on_id));
+            } else {
+                dependency_repo.add(&id, depends_on_id)?;
+                summary.dependencies_created += 1;
+            }
+        }
+    }
+
+    tx.commit()?;
+    Ok(summary)
+}
+
+/// Tasks matching `filter` as JSON that `task import` accepts. Returns the
+/// document and the number of tasks in it.
+pub fn export_taskwarrior(conn: &Connection, filter: Option<TaskFilter>) -> Result<(String, usize), AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let dependency_repo = DependencyRepository::new(conn);
+    let categories = CategoryRepository::new(conn).get_all()?;
+
+    let mut tasks = task_repo.get_all(filter)?;
+    tasks.sort_by_key(|t| t.created_at);
+    let exported: HashSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+
+    let mut items = Vec::new();
+    for task in &tasks {
+        // Taskwarrior only recurs tasks with a due date
+        let recurring = !task.completed && task.due_date.is_some() && task.recurrence.is_some();
+        let status = if task.completed {
+            "completed"
+        } else if recurring {
+            "recurring"
+        } else {
+            "pending"
+        };
+
+        items.push(TaskwarriorTask {
+            uuid: task.id.clone(),
+            description: task.title.clone(),
+            status: status.to_string(),
+            entry: Some(format_timestamp(task.created_at)),
+            modified: Some(format_timestamp(task.updated_at)),
+            end: task.completed.then(|| format_timestamp(task.updated_at)),
+            due: task.due_date.map(export_due).map(format_timestamp),
+            until: task.recurrence.as_ref().filter(|_| recurring).and_then(|r| r.until).map(format_timestamp),
+            project: task
+                .category_id
+                .as_ref()
+                .and_then(|id| categories.iter().find(|c| &c.id == id))
+                .map(|c| c.name.clone()),
+            tags: task.tags.clone(),
+            priority: Some(
+                match task.priority {
+                    Priority::High => "H",
+                    Priority::Medium => "M",
+                    Priority::Low => "L",
+                }
+                .to_string(),
+            ),
+            depends: dependency_repo
+                .get_for_task(&task.id)?
+                .into_iter()
+                .filter(|id| exported.contains(id.as_str()))
+                .collect(),
+            annotations: task
+                .description
+                .iter()
+                .filter(|d| !d.trim().is_empty())
+                .map(|d| TaskwarriorAnnotation {
+                    entry: Some(format_timestamp(task.created_at)),
+                    description: d.clone(),
+                })
+                .collect(),
+            recur: task.recurrence.as_ref().filter(|_| recurring).map(format_recur),
+            parent: None,
+            pluto_parent: task.parent_id.clone(),
+        });
+    }
+
+    let json = serde_json::to_string_pretty(&items).map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    Ok((json, items.len()))
+}
+
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim(), TASKWARRIOR_DATE_FORMAT)
+        .ok()
+        .or_else(|| DateTime::parse_from_rfc3339(value.trim()).ok().map(|dt| dt.naive_utc()))
+}
+
+fn format_timestamp(utc: NaiveDateTime) -> String {
+    utc.format(TASKWARRIOR_DATE_FORMAT).to_string()
+}
+
+/// Taskwarrior stores a date without a time as local midnight; Pluto stores
+/// it as midnight UTC.
+fn import_due(utc: NaiveDateTime) -> NaiveDateTime {
+    let local = Local.from_utc_datetime(&utc).naive_local();
+    if local.time() == NaiveTime::MIN {
+        local
+    } else {
+        utc
+    }
+}
+
+fn export_due(due: NaiveDateTime) -> NaiveDateTime {
+    if due.time() != NaiveTime::MIN {
+        return due;
+    }
+    Local
+        .from_local_datetime(&due)
+        .earliest()
+        .map(|local| local.naive_utc())
+        .unwrap_or(due)
+}
+
+/// Named periods (`weekly`, `quarterly`, `weekdays`, …) and `<n><unit>`
+/// durations such as `2w` or `3mo`. Sub-day periods are not supported.
+fn parse_recur(value: &str) -> Option<RecurrenceRule> {
+    let value = value.trim().to_ascii_lowercase();
+    let (frequency, interval) = match value.as_str() {
+        "daily" | "day" => (Frequency::Daily, 1),
+        "weekly" | "week" => (Frequency::Weekly, 1),
+        "biweekly" | "fortnight" => (Frequency::Weekly, 2),
+        "monthly" | "month" => (Frequency::Monthly, 1),
+        "bimonthly" => (Frequency::Monthly, 2),
+        "quarterly" | "quarter" => (Frequency::Monthly, 3),
+        "semiannual" => (Frequency::Monthly, 6),
+        "yearly" | "annual" | "year" => (Frequency::Yearly, 1),
+        "biannual" | "biyearly" => (Frequency::Yearly, 2),
+        "weekdays" => {
+            let mut rule = RecurrenceRule::new(Frequency::Weekly);
+            rule.by_weekday = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+            return Some(rule);
+        }
+        _ => {
+            let split = value.find(|c: char| !c.is_ascii_digit())?;
+            let (count, unit) = value.split_at(split);
+            let count: u32 = if count.is_empty() { 1 } else { count.parse().ok()? };
+            match unit {
+                "d" | "day" | "days" => (Frequency::Daily, count),
+                "w" | "wk" | "wks" | "week" | "weeks" => (Frequency::Weekly, count),
+                "mo" | "mos" | "mth" | "mths" | "month" | "months" => (Frequency::Monthly, count),
+                "q" | "qtr" | "qtrs" | "quarter" | "quarters" => (Frequency::Monthly, count.checked_mul(3)?),
+                "y" | "yr" | "yrs" | "year" | "years" => (Frequency::Yearly, count),
+                _ => return None,
+            }
+        }
+    };
+    if interval == 0 {
+        return None;
+    }
+    let mut rule = RecurrenceRule::new(frequency);
+    rule.interval = interval;
+    Some(rule)
+}
+
+/// The closest Taskwarrior period. Weekday lists other than Monday to
+/// Friday, month days and COUNT have no equivalent and recur on the due
+/// date's cycle instead.
+fn format_recur(rule: &RecurrenceRule) -> String {
+    let weekdays = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+    match (rule.frequency, rule.interval) {
+        (Frequency::Weekly, 1) if rule.by_weekday == weekdays => "weekdays".to_string(),
+        (Frequency::Daily, 1) => "daily".to_string(),
+        (Frequency::Daily, n) => format!("{}d", n),
+        (Frequency::Weekly, 1) => "weekly".to_string(),
+        (Frequency::Weekly, n) => format!("{}w", n),
+        (Frequency::Monthly, 1) => "monthly".to_string(),
+        (Frequency::Monthly, n) => format!("{}mo", n),
+        (Frequency::Yearly, 1) => "yearly".to_string(),
+        (Frequency::Yearly, n) => format!("{}y", n),
+    }
+}
@@ -0,0 +1,334 @@
+use chrono::{NaiveDateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::database::{CategoryRepository, DependencyRepository, ReminderRepository, TaskRepository};
+use crate::interop::{ExportSummary, ImportSummary};
+use crate::models::{Category, Reminder, Task, TaskDependency};
+use crate::utils::AppError;
+
+/// Identifies a Pluto backup file
+pub const BACKUP_FORMAT: &str = "pluto-todo-backup";
+/// Bump when the document shape changes; older versions must stay readable
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Most problems listed when a backup fails validation
+const MAX_REPORTED_PROBLEMS: usize = 20;
+
+/// A portable copy of every category, task (tags and hierarchy included),
+/// reminder and dependency. Tasks in the trash are not part of a backup,
+/// and neither are settings, so no passwords or tokens end up in one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    pub categories: Vec<Category>,
+    pub tasks: Vec<Task>,
+    #[serde(default)]
+    pub reminders: Vec<Reminder>,
+    #[serde(default)]
+    pub dependencies: Vec<TaskDependency>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Add the backup to what is already there
+    Merge,
+    /// Wipe every task, category, reminder, dependency and the undo history
+    /// first
+    Replace,
+}
+
+impl Backup {
+    pub fn export(conn: &Connection) -> Result<Self, AppError> {
+        let task_repo = TaskRepository::new(conn);
+        let reminder_repo = ReminderRepository::new(conn);
+
+        let mut tasks = task_repo.get_all(None)?;
+        tasks.sort_by_key(|t| t.created_at);
+
+        let mut reminders = Vec::new();
+        for task in &tasks {
+            reminders.extend(reminder_repo.get_for_task(&task.id)?);
+        }
+
+        Ok(Backup {
+            format: BACKUP_FORMAT.to_string(),
+            version: BACKUP_VERSION,
+            exported_at: Utc::now().naive_utc(),
+            categories: CategoryRepository::new(conn).get_all()?,
+            tasks,
+            reminders,
+            dependencies: DependencyRepository::new(conn).get_all()?,
+        })
+    }
+
+    pub fn summary(&self) -> ExportSummary {
+        ExportSummary {
+            tasks: self.tasks.len(),
+            categories: self.categories.len(),
+        }
+    }
+
+    /// Parse a backup document, checking the format marker and version
+    /// before looking at the contents.
+    pub fn from_json(json: &str) -> Result<Self, AppError> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| AppError::InvalidInput(format!("Not a valid JSON document: {}", e)))?;
+
+        if value.get("format").and_then(|f| f.as_str()) != Some(BACKUP_FORMAT) {
+            return Err(AppError::InvalidInput("Not a Pluto backup file".to_string()));
+        }
+        match value.get("version").and_then(|v| v.as_u64()) {
+            Some(v) if v >= 1 && v <= BACKUP_VERSION as u64 => {}
+            Some(v) if v > BACKUP_VERSION as u64 => {
+                return Err(AppError::InvalidInput(format!(
+                    "Backup version {} is newer than this app supports ({}); please update Pluto",
+                    v, BACKUP_VERSION
+                )))
+            }
+            _ => return Err(AppError::InvalidInput("Backup has no valid version".to_string())),
+        }
+
+        serde_json::from_value(value).map_err(|e| AppError::InvalidInput(format!("Malformed backup: {}", e)))
+    }
+
+    pub fn to_json(&self) -> Result<String, AppError> {
+        serde_json::to_string_pretty(self).map_err(|e| AppError::InvalidInput(e.to_string()))
+    }
+
+    /// Every reason the backup cannot be imported in `mode`. References may
+    /// point at rows already in the database only when merging.
+    pub fn validate(&self, conn: &Connection, mode: ImportMode) -> Result<Vec<String>, AppError> {
+        let task_repo = TaskRepository::new(conn);
+        let category_repo = CategoryRepository::new(conn);
+        let mut problems = Vec::new();
+
+        let mut category_ids = HashSet::new();
+        for category in &self.categories {
+            if category.id.trim().is_empty() {
+                problems.push(format!("Category '{}' has no ID", category.name));
+            } else if !category_ids.insert(category.id.as_str()) {
+                problems.push(format!("Category ID {} appears more than once", category.id));
+            }
+            if category.name.trim().is_empty() {
+                problems.push(format!("Category {} has no name", category.id));
+            }
+        }
+
+        let mut task_ids = HashSet::new();
+        for task in &self.tasks {
+            if task.id.trim().is_empty() {
+                problems.push(format!("Task '{}' has no ID", task.title));
+            } else if !task_ids.insert(task.id.as_str()) {
+                problems.push(format!("Task ID {} appears more than once", task.id));
+            }
+            if task.title.trim().is_empty() {
+                problems.push(format!("Task {} has no title", task.id));
+            }
+        }
+
+        let merging = mode == ImportMode::Merge;
+        for task in &self.tasks {
+            if let Some(ref category_id) = task.category_id {
+                let known = category_ids.contains(category_id.as_str())
+                    || (merging && category_repo.get_by_id(category_id)?.is_some());
+                if !known {
+                    problems.push(format!("Task '{}' refers to unknown category {}", task.title, category_id));
+                }
+            }
+            if let Some(ref parent_id) = task.parent_id {
+                let known = task_ids.contains(parent_id.as_str())
+                    || (merging && task_repo.get_by_id(parent_id)?.is_some());
+                if !known {
+                    problems.push(format!("Task '{}' refers to unknown parent task {}", task.title, parent_id));
+                }
+            }
+        }
+
+        // Walking up from each task must leave the backup or reach a root
+        let parents: HashMap<&str, &str> = self
+            .tasks
+            .iter()
+            .filter_map(|t| Some((t.id.as_str(), t.parent_id.as_deref()?)))
+            .collect();
+        for task in &self.tasks {
+            let mut seen = HashSet::new();
+            let mut current = task.id.as_str();
+            while let Some(&parent) = parents.get(current) {
+                if !seen.insert(current) {
+                    problems.push(format!("Task '{}' is part of a parent/subtask cycle", task.title));
+                    break;
+                }
+                current = parent;
+            }
+        }
+
+        let mut reminder_ids = HashSet::new();
+        for reminder in &self.reminders {
+            if !reminder_ids.insert(reminder.id.as_str()) {
+                problems.push(format!("Reminder ID {} appears more than once", reminder.id));
+            }
+            if !task_ids.contains(reminder.task_id.as_str()) {
+                problems.push(format!("Reminder {} refers to unknown task {}", reminder.id, reminder.task_id));
+            }
+        }
+
+        let mut links = HashSet::new();
+        let mut depends_on: HashMap<&str, Vec<&str>> = HashMap::new();
+        for link in &self.dependencies {
+            let (task_id, depends_on_id) = (link.task_id.as_str(), link.depends_on_id.as_str());
+            if !task_ids.contains(task_id) {
+                problems.push(format!("Dependency refers to unknown task {}", task_id));
+            }
+            let known = task_ids.contains(depends_on_id)
+                || (merging && task_repo.get_by_id(depends_on_id)?.is_some());
+            if !known {
+                problems.push(format!("Dependency of {} refers to unknown task {}", task_id, depends_on_id));
+            }
+            if task_id == depends_on_id {
+                problems.push(format!("Task {} depends on itself", task_id));
+            } else if !links.insert((task_id, depends_on_id)) {
+                problems.push(format!("Dependency of {} on {} appears more than once", task_id, depends_on_id));
+            } else {
+                depends_on.entry(task_id).or_default().push(depends_on_id);
+            }
+        }
+
+        // Following the links from any task must never lead back to it
+        let mut starts: Vec<&str> = depends_on.keys().copied().collect();
+        starts.sort_unstable();
+        let mut cleared = HashSet::new();
+        if starts.into_iter().any(|id| leads_back(id, &depends_on, &mut Vec::new(), &mut cleared)) {
+            problems.push("Task dependencies form a cycle".to_string());
+        }
+
+        Ok(problems)
+    }
+
+    /// Validate the whole document, then write it in a single transaction.
+    /// In merge mode IDs are kept unless already taken, in which case the row
+    /// gets a new ID and references to it are rewritten; categories that
+    /// match an existing one by ID or name are reused rather than duplicated.
+    pub fn import(&self, conn: &Connection, mode: ImportMode) -> Result<ImportSummary, AppError> {
+        let problems = self.validate(conn, mode)?;
+        if !problems.is_empty() {
+            let mut message = problems.iter().take(MAX_REPORTED_PROBLEMS).cloned().collect::<Vec<_>>().join("; ");
+            if problems.len() > MAX_REPORTED_PROBLEMS {
+                message.push_str(&format!(" (and {} more)", problems.len() - MAX_REPORTED_PROBLEMS));
+            }
+            return Err(AppError::InvalidInput(message));
+        }
+
+        let task_repo = TaskRepository::new(conn);
+        let category_repo = CategoryRepository::new(conn);
+        let reminder_repo = ReminderRepository::new(conn);
+        let mut summary = ImportSummary::default();
+
+        let tx = conn.unchecked_transaction()?;
+        // Parents and children are inserted in file order
+        tx.pragma_update(None, "defer_foreign_keys", true)?;
+
+        if mode == ImportMode::Replace {
+            tx.execute("DELETE FROM task_dependencies", [])?;
+            tx.execute("DELETE FROM task_reminders", [])?;
+            tx.execute("DELETE FROM task_tags", [])?;
+            tx.execute("DELETE FROM tasks", [])?;
+            tx.execute("DELETE FROM categories", [])?;
+            // Undo entries would refer to rows that no longer exist
+            tx.execute("DELETE FROM operation_journal", [])?;
+        }
+
+        let existing_categories = category_repo.get_all()?;
+        let mut category_map: HashMap<&str, String> = HashMap::new();
+        for category in &self.categories {
+            let existing = existing_categories
+                .iter()
+                .find(|c| c.id == category.id)
+                .or_else(|| existing_categories.iter().find(|c| c.name.eq_ignore_ascii_case(&category.name)));
+
+            match existing {
+                Some(existing) => {
+                    category_map.insert(&category.id, existing.id.clone());
+                    summary.categories_matched += 1;
+                }
+                None => {
+                    category_repo.create(category)?;
+                    category_map.insert(&category.id, category.id.clone());
+                    summary.categories_created += 1;
+                    summary.category_ids.push(category.id.clone());
+                }
+            }
+        }
+
+        let mut task_map: HashMap<&str, String> = HashMap::new();
+        for task in &self.tasks {
+            let id = if task_repo.row_exists(&task.id)? {
+                summary.remapped_ids += 1;
+                uuid::Uuid::new_v4().to_string()
+            } else {
+                task.id.clone()
+            };
+            task_map.insert(&task.id, id);
+        }
+
+        for task in &self.tasks {
+            let mut task = task.clone();
+            task.id = task_map[task.id.as_str()].clone();
+            task.category_id = task.category_id.map(|id| category_map.get(id.as_str()).cloned().unwrap_or(id));
+            task.parent_id = task.parent_id.map(|id| task_map.get(id.as_str()).cloned().unwrap_or(id));
+            task.deleted_at = None;
+
+            task_repo.create(&task)?;
+            summary.tasks_created += 1;
+            summary.task_ids.push(task.id);
+        }
+
+        for reminder in &self.reminders {
+            let mut reminder = reminder.clone();
+            if reminder_repo.get_by_id(&reminder.id)?.is_some() {
+                reminder.id = uuid::Uuid::new_v4().to_string();
+                summary.remapped_ids += 1;
+            }
+            reminder.task_id = task_map[reminder.task_id.as_str()].clone();
+            reminder_repo.create(&reminder)?;
+            summary.reminders_created += 1;
+        }
+
+        // A blocking task outside the backup is one already in the database
+        let dependency_repo = DependencyRepository::new(conn);
+        for link in &self.dependencies {
+            let depends_on_id = task_map.get(link.depends_on_id.as_str()).cloned().unwrap_or_else(|| link.depends_on_id.clone());
+            dependency_repo.add(&task_map[link.task_id.as_str()], &depends_on_id)?;
+            summary.dependencies_created += 1;
+        }
+
+        tx.commit()?;
+        Ok(summary)
+    }
+}
+
+/// Whether following `depends_on` links from `id` reaches a task on `path`.
+/// Tasks in `cleared` are known to lead nowhere back.
+fn leads_back<'a>(
+    id: &'a str,
+    depends_on: &HashMap<&'a str, Vec<&'a str>>,
+    path: &mut Vec<&'a str>,
+    cleared: &mut HashSet<&'a str>,
+) -> bool {
+    if path.contains(&id) {
+        return true;
+    }
+    if cleared.contains(id) {
+        return false;
+    }
+    path.push(id);
+    let found = depends_on.get(id).into_iter().flatten().any(|&next| leads_back(next, depends_on, path, cleared));
+    path.pop();
+    if !found {
+        cleared.insert(id);
+    }
+    found
+}
@@ -0,0 +1,756 @@
+use chrono::{NaiveDateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use crate::database::{CalDavRepository, CategoryRepository, Database, Journal, SettingsRepository, TaskRepository};
+use crate::interop::caldav_client::{CalDavClient, DavTransport, HttpTransport, RemoteItem, SyncChanges, WriteResult};
+use crate::interop::ical::{self, CalendarItem, ItemKind};
+use crate::interop::ImportSummary;
+use crate::models::{CalDavAccount, CalDavCalendar, Category, ConflictPolicy, EntityState, Priority, Task, TaskSyncState};
+use crate::utils::AppError;
+
+/// What a CalDAV sync changed on either side.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CalDavSyncReport {
+    /// Tasks and categories created or updated in Pluto
+    pub summary: ImportSummary,
+    /// Tasks moved to the trash because they were deleted on the server
+    pub tasks_deleted: usize,
+    pub calendars_created: usize,
+    pub items_created: usize,
+    pub items_updated: usize,
+    pub items_deleted: usize,
+    pub conflicts: Vec<String>,
+}
+
+impl CalDavSyncReport {
+    /// Whether the sync changed anything in the app.
+    pub fn changed_app(&self) -> bool {
+        !self.summary.task_ids.is_empty() || !self.summary.category_ids.is_empty() || self.tasks_deleted > 0
+    }
+}
+
+/// Sync with the account set up in settings; `None` when sync is turned off.
+/// Blocks until the server has answered every request, so callers should
+/// run it off the async runtime.
+pub fn sync_configured(db: &Mutex<Database>) -> Result<Option<CalDavSyncReport>, AppError> {
+    let account = SettingsRepository::new(&lock(db)?.connection).caldav_account()?;
+    match account {
+        Some(account) => {
+            let transport = HttpTransport::new(&account.username, &account.password);
+            Ok(Some(sync_account(db, &transport, &account)?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Held for a whole sync, so a manual sync and the background one take turns
+static SYNC_RUNNING: Mutex<()> = Mutex::new(());
+
+fn lock(db: &Mutex<Database>) -> Result<MutexGuard<'_, Database>, AppError> {
+    db.lock().map_err(|e| AppError::Server(format!("Database lock error: {}", e)))
+}
+
+/// Two-way sync of every category with a calendar collection on the server,
+/// one VTODO per task.
+///
+/// Remote changes are found with the collection's sync token (a full ETag
+/// listing when the server has none or rejects it), local ones by comparing
+/// each task with the fingerprint stored when it was last synced. Writes are
+/// conditional on the ETag, so an item changed on the server in between is
+/// treated as a conflict and settled by the account's `ConflictPolicy`.
+///
+/// Sync state is saved after every item rather than in one transaction: the
+/// server cannot roll back, so an interrupted sync keeps what it did and the
+/// next one carries on. The app-side changes are recorded as one undoable
+/// operation all the same.
+///
+/// The database is locked only to read or write it, never while waiting on
+/// the server, so the app and the API stay usable during a slow sync. Tasks
+/// edited meanwhile are picked up by the next sync.
+pub fn sync_account(
+    db: &Mutex<Database>,
+    transport: &dyn DavTransport,
+    account: &CalDavAccount,
+) -> Result<CalDavSyncReport, AppError> {
+    let _running = SYNC_RUNNING.lock().unwrap_or_else(PoisonError::into_inner);
+    let (states, categories) = {
+        let db_lock = lock(db)?;
+        (
+            CalDavRepository::new(&db_lock.connection).get_task_states()?,
+            CategoryRepository::new(&db_lock.connection).get_all()?,
+        )
+    };
+
+    let mut sync = CalDavSync {
+        db,
+        client: CalDavClient::new(transport, &account.url),
+        policy: account.conflict_policy,
+        states: states.into_iter().map(|s| (s.task_id.clone(), s)).collect(),
+        categories,
+        calendars: Vec::new(),
+        pending_parents: Vec::new(),
+        touched: Vec::new(),
+        before: Vec::new(),
+        report: CalDavSyncReport::default(),
+    };
+    let result = sync.run();
+
+    // Whatever part of the sync happened stays undoable
+    let db_lock = lock(db)?;
+    let journal = Journal::new(&db_lock.connection);
+    let mut after = journal.capture_categories(&sync.report.summary.category_ids)?;
+    after.extend(journal.capture_tasks(&sync.touched)?);
+    journal.record("Sync CalDAV", sync.before, after)?;
+
+    result.map(|_| sync.report)
+}
+
+/// The synced fields of a task, to tell whether it changed since the last sync
+fn fingerprint(task: &Task) -> String {
+    serde_json::json!([
+        task.title,
+        task.description,
+        task.completed,
+        task.priority,
+        task.due_date,
+        task.category_id,
+        task.tags,
+        task.parent_id,
+        task.recurrence.as_ref().map(|r| r.to_string()),
+    ])
+    .to_string()
+}
+
+/// Percent-encode a UID for use as a resource name. UIDs that came from
+/// other clients may contain `/`, `?`, spaces or anything else.
+fn encode_path_segment(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+struct CalDavSync<'a> {
+    db: &'a Mutex<Database>,
+    client: CalDavClient<'a>,
+    policy: ConflictPolicy,
+    /// By task ID
+    states: HashMap<String, TaskSyncState>,
+    categories: Vec<Category>,
+    calendars: Vec<CalDavCalendar>,
+    /// Tasks whose parent UID could not be resolved when they were pulled
+    pending_parents: Vec<(String, String)>,
+    /// Tasks the sync changed, and their state before its first change, for
+    /// the undo history
+    touched: Vec<String>,
+    before: Vec<EntityState>,
+    report: CalDavSyncReport,
+}
+
+/// Which side of a conflict is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Winner {
+    Server,
+    Local,
+}
+
+impl<'a> CalDavSync<'a> {
+    fn run(&mut self) -> Result<(), AppError> {
+        self.map_calendars()?;
+
+        // Deletions are handled once every calendar has been read, so a task
+        // moved between calendars on the server is not lost on the way
+        let mut removed = Vec::new();
+        for calendar in self.calendars.clone() {
+            if calendar.category_id.is_some() {
+                removed.extend(self.pull(&calendar)?);
+            }
+        }
+        {
+            let db_lock = lock(self.db)?;
+            self.resolve_parents(&db_lock.connection)?;
+            for url in removed {
+                self.remote_removed(&db_lock.connection, &url)?;
+            }
+        }
+
+        self.push()
+    }
+
+    /// Note a task's state before the sync first changes it.
+    fn touch(&mut self, conn: &Connection, id: &str) -> Result<(), AppError> {
+        if !self.touched.iter().any(|t| t == id) {
+            let id = id.to_string();
+            self.before.extend(Journal::new(conn).capture_tasks(std::slice::from_ref(&id))?);
+            self.touched.push(id);
+        }
+        Ok(())
+    }
+
+    /// Pair every task calendar on the server with a category, creating
+    /// categories for new calendars and calendars for new categories.
+    fn map_calendars(&mut self) -> Result<(), AppError> {
+        let home = self.client.calendar_home()?;
+        let remote = self.client.list_calendars(&home)?;
+        let db_lock = lock(self.db)?;
+        let repo = CalDavRepository::new(&db_lock.connection);
+        let mut calendars = repo.get_calendars()?;
+
+        let (kept, gone): (Vec<_>, Vec<_>) = calendars.into_iter().partition(|c| remote.iter().any(|r| r.url == c.url));
+        calendars = kept;
+        for calendar in gone {
+            self.report.summary.warnings.push(format!(
+                "Calendar '{}' was removed from the server; its tasks will be uploaded again",
+                calendar.display_name
+            ));
+            repo.delete_calendar(&calendar.url)?;
+            self.states.retain(|_, s| s.calendar_url != calendar.url);
+        }
+
+        for calendar in remote {
+            if calendars.iter().any(|c| c.url == calendar.url) {
+                continue;
+            }
+            let category_id = self.category_for_calendar(&db_lock.connection, &calendar.display_name, &calendars)?;
+            let calendar = CalDavCalendar {
+                url: calendar.url,
+                category_id: Some(category_id),
+                display_name: calendar.display_name,
+                sync_token: None,
+                synced_at: None,
+            };
+            repo.save_calendar(&calendar)?;
+            calendars.push(calendar);
+        }
+        drop(db_lock);
+
+        for category in self.categories.clone() {
+            if calendars.iter().any(|c| c.category_id.as_deref() == Some(category.id.as_str())) {
+                continue;
+            }
+            let url = self.client.create_calendar(&home, &category.name)?;
+            let calendar = CalDavCalendar {
+                url,
+                category_id: Some(category.id.clone()),
+                display_name: category.name.clone(),
+                sync_token: None,
+                synced_at: None,
+            };
+            CalDavRepository::new(&lock(self.db)?.connection).save_calendar(&calendar)?;
+            calendars.push(calendar);
+            self.report.calendars_created += 1;
+        }
+
+        self.calendars = calendars;
+        Ok(())
+    }
+
+    /// The category a new calendar is synced with: the one of the same name
+    /// if no other calendar has it, otherwise a new one.
+    fn category_for_calendar(
+        &mut self,
+        conn: &Connection,
+        name: &str,
+        calendars: &[CalDavCalendar],
+    ) -> Result<String, AppError> {
+        let taken = |id: &String| calendars.iter().any(|c| c.category_id.as_ref() == Some(id));
+        if let Some(category) = self.categories.iter().find(|c| c.name.eq_ignore_ascii_case(name)) {
+            if !taken(&category.id) {
+                return Ok(category.id.clone());
+            }
+        }
+
+        // Two calendars with the same name get "Name", "Name 2", ...
+        let mut candidate = name.to_string();
+        let mut n = 1;
+        while self.categories.iter().any(|c| c.name.eq_ignore_ascii_case(&candidate)) {
+            n += 1;
+            candidate = format!("{} {}", name, n);
+        }
+        Ok(self.report.summary.find_or_create_category(conn, &mut self.categories, &candidate)?)
+    }
+
+    /// Apply the changes made on the server since the last sync. Returns the
+    /// URLs of deleted items, which are handled after every calendar.
+    fn pull(&mut self, calendar: &CalDavCalendar) -> Result<Vec<String>, AppError> {
+        let (changed, removed, token) = match self.client.sync_collection(&calendar.url, calendar.sync_token.as_deref())? {
+            SyncChanges::Changes { token, changed, removed } => (changed, removed, Some(token)),
+            SyncChanges::TokenExpired | SyncChanges::Unsupported => {
+                let listed = self.client.list_items(&calendar.url)?;
+                let urls: HashSet<&String> = listed.iter().map(|(url, _)| url).collect();
+                let removed = self
+                    .states
+                    .values()
+                    .filter(|s| s.calendar_url == calendar.url && !urls.contains(&s.href))
+                    .map(|s| s.href.clone())
+                    .collect();
+                (listed, removed, None)
+            }
+        };
+
+        // Items whose ETag we already know are our own writes
+        let changed: Vec<String> = changed
+            .into_iter()
+            .filter(|(url, etag)| match self.state_by_href(url) {
+                Some(state) => etag.is_none() || state.etag != *etag,
+                None => true,
+            })
+            .map(|(url, _)| url)
+            .collect();
+
+        let items = self.client.fetch_items(&calendar.url, &changed)?;
+        let db_lock = lock(self.db)?;
+        for item in items {
+            self.remote_changed(&db_lock.connection, calendar, item)?;
+        }
+
+        let mut calendar = calendar.clone();
+        calendar.sync_token = token;
+        calendar.synced_at = Some(Utc::now().naive_utc());
+        CalDavRepository::new(&db_lock.connection).save_calendar(&calendar)?;
+        Ok(removed)
+    }
+
+    /// Take over an item that is new or changed on the server.
+    fn remote_changed(&mut self, conn: &Connection, calendar: &CalDavCalendar, remote: RemoteItem) -> Result<(), AppError> {
+        let items = match ical::parse_ics(&remote.data) {
+            Ok(items) => items,
+            Err(e) => {
+                self.report.summary.warnings.push(format!("Skipped {}: {}", remote.url, e));
+                return Ok(());
+            }
+        };
+        // Events share calendars with tasks on many servers
+        let Some(item) = items.into_iter().find(|i| i.kind == ItemKind::Todo) else {
+            return Ok(());
+        };
+        let uid = item.uid().unwrap_or_else(|| remote.url.clone());
+        let task_repo = TaskRepository::new(conn);
+
+        // Known by URL, by UID (moved between calendars on the server), or a
+        // task we uploaded whose sync state was never saved
+        let state = self
+            .state_by_href(&remote.url)
+            .or_else(|| self.states.values().find(|s| s.uid == uid).cloned());
+        let Some(mut state) = state else {
+            if task_repo.row_exists(&uid)? {
+                let state = TaskSyncState {
+                    task_id: uid.clone(),
+                    calendar_url: calendar.url.clone(),
+                    href: remote.url.clone(),
+                    uid: uid.clone(),
+                    etag: remote.etag.clone(),
+                    fingerprint: String::new(),
+                    synced_at: Utc::now().naive_utc(),
+                };
+                return self.settle(conn, calendar, state, &item, remote.etag);
+            }
+            return self.create_from_remote(conn, calendar, &remote, &item, &uid);
+        };
+
+        state.calendar_url = calendar.url.clone();
+        state.href = remote.url.clone();
+        self.settle(conn, calendar, state, &item, remote.etag)
+    }
+
+    /// Reconcile a task with its changed server copy. Without local changes
+    /// the server's version is taken; otherwise the conflict policy decides.
+    fn settle(
+        &mut self,
+        conn: &Connection,
+        calendar: &CalDavCalendar,
+        mut state: TaskSyncState,
+        item: &CalendarItem,
+        etag: Option<String>,
+    ) -> Result<(), AppError> {
+        let task_repo = TaskRepository::new(conn);
+        let task = task_repo.get_by_id(&state.task_id)?;
+        let locally_changed = task.as_ref().is_none_or(|t| fingerprint(t) != state.fingerprint);
+        let title = item.summary().unwrap_or_else(|| state.uid.clone());
+
+        let winner = if !locally_changed {
+            Winner::Server
+        } else {
+            let winner = match (self.policy, &task) {
+                (ConflictPolicy::ServerWins, _) => Winner::Server,
+                (ConflictPolicy::LocalWins, _) => Winner::Local,
+                // An edit beats a deletion
+                (ConflictPolicy::NewestWins, None) => Winner::Server,
+                (ConflictPolicy::NewestWins, Some(task)) => match item.last_modified() {
+                    Some(modified) if modified > task.updated_at => Winner::Server,
+                    _ => Winner::Local,
+                },
+            };
+            self.report.conflicts.push(format!(
+                "'{}' changed both in Pluto and on the server; kept the {} version",
+                title,
+                if winner == Winner::Server { "server's" } else { "Pluto" }
+            ));
+            winner
+        };
+
+        // The local version is written back with the new ETag by `push`
+        state.etag = etag;
+        if winner == Winner::Local {
+            return self.save_state(conn, state);
+        }
+
+        self.touch(conn, &state.task_id)?;
+        let mut task = match task {
+            Some(task) => task,
+            None if task_repo.row_exists(&state.task_id)? => {
+                task_repo.restore_row(&state.task_id)?;
+                task_repo.get_by_id(&state.task_id)?.ok_or(AppError::TaskNotFound)?
+            }
+            None => new_task(&state.task_id, item),
+        };
+        let existed = task_repo.row_exists(&task.id)?;
+        self.apply_item(conn, calendar, &mut task, item);
+        if existed {
+            task_repo.update(&task)?;
+            self.report.summary.tasks_updated += 1;
+        } else {
+            task_repo.create(&task)?;
+            self.report.summary.tasks_created += 1;
+        }
+        self.report.summary.task_ids.push(task.id.clone());
+
+        state.fingerprint = fingerprint(&task);
+        state.synced_at = Utc::now().naive_utc();
+        self.save_state(conn, state)
+    }
+
+    fn create_from_remote(
+        &mut self,
+        conn: &Connection,
+        calendar: &CalDavCalendar,
+        remote: &RemoteItem,
+        item: &CalendarItem,
+        uid: &str,
+    ) -> Result<(), AppError> {
+        if item.summary().is_none() {
+            self.report.summary.warnings.push(format!("Skipped an item without a summary ({})", remote.url));
+            return Ok(());
+        }
+        let task_repo = TaskRepository::new(conn);
+        // Other apps' UIDs become task IDs where possible, like in .ics imports
+        let id = if uid.len() <= 255 && !uid.chars().any(char::is_whitespace) && !task_repo.row_exists(uid)? {
+            uid.to_string()
+        } else {
+            uuid::Uuid::new_v4().to_string()
+        };
+
+        self.touch(conn, &id)?;
+        let mut task = new_task(&id, item);
+        self.apply_item(conn, calendar, &mut task, item);
+        task_repo.create(&task)?;
+        self.report.summary.tasks_created += 1;
+        self.report.summary.task_ids.push(task.id.clone());
+
+        self.save_state(conn, TaskSyncState {
+            task_id: task.id.clone(),
+            calendar_url: calendar.url.clone(),
+            href: remote.url.clone(),
+            uid: uid.to_string(),
+            etag: remote.etag.clone(),
+            fingerprint: fingerprint(&task),
+            synced_at: Utc::now().naive_utc(),
+        })
+    }
+
+    /// Copy an item onto a task. The calendar decides the category; the
+    /// other CATEGORIES are tags.
+    fn apply_item(&mut self, conn: &Connection, calendar: &CalDavCalendar, task: &mut Task, item: &CalendarItem) {
+        if let Some(title) = item.summary() {
+            task.title = title;
+        }
+        item.apply_fields(task, &mut self.report.summary.warnings);
+        task.category_id = calendar.category_id.clone();
+
+        let category_name = self
+            .categories
+            .iter()
+            .find(|c| Some(&c.id) == calendar.category_id.as_ref())
+            .map(|c| c.name.clone());
+        let pluto_category = item.pluto_category();
+        task.tags = Vec::new();
+        for name in item.category_names() {
+            let is_category = [category_name.as_ref(), pluto_category.as_ref(), Some(&calendar.display_name)]
+                .into_iter()
+                .flatten()
+                .any(|c| c.eq_ignore_ascii_case(&name));
+            if !is_category && !task.tags.contains(&name) {
+                task.tags.push(name);
+            }
+        }
+        task.tags.sort();
+
+        task.parent_id = None;
+        if let Some(parent_uid) = item.parent_uid() {
+            match self.task_for_uid(conn, &parent_uid) {
+                Some(parent_id) if parent_id != task.id => task.parent_id = Some(parent_id),
+                _ => self.pending_parents.push((task.id.clone(), parent_uid)),
+            }
+        }
+        task.updated_at = Utc::now().naive_utc();
+    }
+
+    /// Link subtasks whose parent arrived later in the same sync.
+    fn resolve_parents(&mut self, conn: &Connection) -> Result<(), AppError> {
+        let task_repo = TaskRepository::new(conn);
+        for (task_id, parent_uid) in std::mem::take(&mut self.pending_parents) {
+            let Some(mut task) = task_repo.get_by_id(&task_id)? else { continue };
+            match self.task_for_uid(conn, &parent_uid) {
+                Some(parent_id) if parent_id != task.id && task_repo.get_by_id(&parent_id)?.is_some() => {
+                    self.touch(conn, &task_id)?;
+                    task.parent_id = Some(parent_id);
+                    task_repo.update(&task)?;
+                    if let Some(mut state) = self.states.get(&task_id).cloned() {
+                        state.fingerprint = fingerprint(&task);
+                        self.save_state(conn, state)?;
+                    }
+                }
+                _ => self
+                    .report
+                    .summary
+                    .warnings
+                    .push(format!("'{}': parent {} not found, kept as a top-level task", task.title, parent_uid)),
+            }
+        }
+        Ok(())
+    }
+
+    /// An item was deleted on the server. The task goes to the trash unless
+    /// it was changed in Pluto and the policy keeps local edits, in which
+    /// case it is uploaded again.
+    fn remote_removed(&mut self, conn: &Connection, url: &str) -> Result<(), AppError> {
+        let Some(state) = self.state_by_href(url) else {
+            return Ok(());
+        };
+        let task_repo = TaskRepository::new(conn);
+        let repo = CalDavRepository::new(conn);
+
+        match task_repo.get_by_id(&state.task_id)? {
+            Some(task) if fingerprint(&task) != state.fingerprint && self.policy != ConflictPolicy::ServerWins => {
+                self.report.conflicts.push(format!(
+                    "'{}' was deleted on the server but changed in Pluto; uploaded it again",
+                    task.title
+                ));
+            }
+            Some(task) => {
+                // Its subtasks move up a level
+                self.touch(conn, &task.id)?;
+                for subtask in task_repo.get_direct_subtasks(&task.id)? {
+                    self.touch(conn, &subtask.id)?;
+                }
+                task_repo.delete(&task.id)?;
+                self.report.tasks_deleted += 1;
+            }
+            None => {}
+        }
+
+        repo.delete_task_state(&state.task_id)?;
+        self.states.remove(&state.task_id);
+        Ok(())
+    }
+
+    /// Upload tasks that are new or changed in Pluto and delete the items of
+    /// tasks that were deleted or moved out of synced categories.
+    fn push(&mut self) -> Result<(), AppError> {
+        let tasks = TaskRepository::new(&lock(self.db)?.connection).get_all(None)?;
+        let live: HashSet<String> = tasks.iter().map(|t| t.id.clone()).collect();
+
+        for task in &tasks {
+            let calendar = task
+                .category_id
+                .as_ref()
+                .and_then(|id| self.calendars.iter().find(|c| c.category_id.as_ref() == Some(id)))
+                .cloned();
+            let state = self.states.get(&task.id).cloned();
+
+            match (calendar, state) {
+                (None, None) => {}
+                (None, Some(state)) => self.delete_item(state)?,
+                (Some(calendar), None) => self.upload_new(&calendar, task, task.id.clone())?,
+                (Some(calendar), Some(state)) if state.calendar_url != calendar.url => {
+                    self.upload_new(&calendar, task, state.uid.clone())?;
+                    self.delete_item(state)?;
+                }
+                (Some(calendar), Some(state)) => {
+                    if fingerprint(task) != state.fingerprint {
+                        self.upload_change(&calendar, task, state)?;
+                    }
+                }
+            }
+        }
+
+        let deleted: Vec<TaskSyncState> = self.states.values().filter(|s| !live.contains(&s.task_id)).cloned().collect();
+        for state in deleted {
+            self.delete_item(state)?;
+        }
+        Ok(())
+    }
+
+    fn upload_new(&mut self, calendar: &CalDavCalendar, task: &Task, uid: String) -> Result<(), AppError> {
+        let url = format!("{}{}.ics", calendar.url, encode_path_segment(&uid));
+        match self.client.put(&url, &self.render(task, &uid), None)? {
+            WriteResult::Done { etag } => {
+                self.report.items_created += 1;
+                self.save_state(&lock(self.db)?.connection, TaskSyncState {
+                    task_id: task.id.clone(),
+                    calendar_url: calendar.url.clone(),
+                    href: url,
+                    uid,
+                    etag,
+                    fingerprint: fingerprint(task),
+                    synced_at: Utc::now().naive_utc(),
+                })
+            }
+            WriteResult::PreconditionFailed => {
+                self.report.summary.warnings.push(format!(
+                    "'{}' could not be uploaded: {} already exists on the server",
+                    task.title, url
+                ));
+                Ok(())
+            }
+        }
+    }
+
+    /// Write back a task changed in Pluto. If the server's copy changed in
+    /// the meantime, the conflict is settled first and the write retried
+    /// once if Pluto's version is kept.
+    fn upload_change(&mut self, calendar: &CalDavCalendar, task: &Task, mut state: TaskSyncState) -> Result<(), AppError> {
+        for attempt in 0..2 {
+            match self.client.put(&state.href, &self.render(task, &state.uid), state.etag.as_deref())? {
+                WriteResult::Done { etag } => {
+                    self.report.items_updated += 1;
+                    state.etag = etag;
+                    state.fingerprint = fingerprint(task);
+                    state.synced_at = Utc::now().naive_utc();
+                    return self.save_state(&lock(self.db)?.connection, state);
+                }
+                WriteResult::PreconditionFailed if attempt == 0 => {
+                    let Some(remote) = self.client.fetch_items(&calendar.url, std::slice::from_ref(&state.href))?.into_iter().next() else {
+                        // Deleted on the server meanwhile: upload as new
+                        CalDavRepository::new(&lock(self.db)?.connection).delete_task_state(&task.id)?;
+                        self.states.remove(&task.id);
+                        return self.upload_new(calendar, task, state.uid.clone());
+                    };
+                    let db_lock = lock(self.db)?;
+                    self.remote_changed(&db_lock.connection, calendar, remote)?;
+                    // Taking the server's version makes it the synced state
+                    let current = TaskRepository::new(&db_lock.connection).get_by_id(&task.id)?;
+                    match (self.states.get(&task.id), current) {
+                        (Some(settled), Some(current)) if settled.fingerprint != fingerprint(&current) => {
+                            state = settled.clone()
+                        }
+                        _ => return Ok(()),
+                    }
+                }
+                WriteResult::PreconditionFailed => {}
+            }
+        }
+        self.report
+            .summary
+            .warnings
+            .push(format!("'{}' kept changing on the server; it will be synced next time", task.title));
+        Ok(())
+    }
+
+    /// Delete a task's item on the server. If it was changed there since the
+    /// last sync, the conflict is settled like any other: an edit beats a
+    /// deletion unless the policy keeps local changes.
+    fn delete_item(&mut self, state: TaskSyncState) -> Result<(), AppError> {
+        let etag = match self.policy {
+            ConflictPolicy::LocalWins => None,
+            _ => state.etag.as_deref(),
+        };
+        if let WriteResult::Done { .. } = self.client.delete(&state.href, etag)? {
+            self.report.items_deleted += 1;
+            CalDavRepository::new(&lock(self.db)?.connection).delete_task_state(&state.task_id)?;
+            self.states.remove(&state.task_id);
+            return Ok(());
+        }
+
+        let calendar = self.calendars.iter().find(|c| c.url == state.calendar_url).cloned();
+        let remote = match calendar {
+            Some(ref calendar) => self.client.fetch_items(&calendar.url, std::slice::from_ref(&state.href))?.into_iter().next(),
+            None => None,
+        };
+        match (calendar, remote) {
+            (Some(calendar), Some(remote)) => self.remote_changed(&lock(self.db)?.connection, &calendar, remote),
+            // Gone after all
+            _ => {
+                CalDavRepository::new(&lock(self.db)?.connection).delete_task_state(&state.task_id)?;
+                self.states.remove(&state.task_id);
+                Ok(())
+            }
+        }
+    }
+
+    fn render(&self, task: &Task, uid: &str) -> String {
+        let category = task
+            .category_id
+            .as_ref()
+            .and_then(|id| self.categories.iter().find(|c| &c.id == id));
+        let parent_uid = task
+            .parent_id
+            .as_ref()
+            .map(|id| self.states.get(id).map(|s| s.uid.clone()).unwrap_or_else(|| id.clone()));
+        ical::calendar_document(ical::vtodo_lines(task, category, uid, parent_uid.as_deref(), Utc::now().naive_utc()))
+    }
+
+    fn state_by_href(&self, url: &str) -> Option<TaskSyncState> {
+        self.states.values().find(|s| s.href == url).cloned()
+    }
+
+    /// The task an item's UID refers to: synced tasks by their stored UID,
+    /// others by ID
+    fn task_for_uid(&self, conn: &Connection, uid: &str) -> Option<String> {
+        self.states
+            .values()
+            .find(|s| s.uid == uid)
+            .map(|s| s.task_id.clone())
+            .or_else(|| {
+                TaskRepository::new(conn)
+                    .get_by_id(uid)
+                    .ok()
+                    .flatten()
+                    .map(|t| t.id)
+            })
+    }
+
+    fn save_state(&mut self, conn: &Connection, state: TaskSyncState) -> Result<(), AppError> {
+        CalDavRepository::new(conn).save_task_state(&state)?;
+        self.states.retain(|id, s| s.href != state.href || *id == state.task_id);
+        self.states.insert(state.task_id.clone(), state);
+        Ok(())
+    }
+}
+
+fn new_task(id: &str, item: &CalendarItem) -> Task {
+    let now: NaiveDateTime = Utc::now().naive_utc();
+    let created_at = item.created_at().unwrap_or(now);
+    Task {
+        id: id.to_string(),
+        title: String::new(),
+        description: None,
+        completed: false,
+        priority: Priority::Medium,
+        due_date: None,
+        category_id: None,
+        tags: Vec::new(),
+        parent_id: None,
+        recurrence: None,
+        created_at,
+        updated_at: now,
+        deleted_at: None,
+        position: Task::initial_position(created_at, false),
+    }
+}
@@ -0,0 +1,531 @@
+use base64::Engine;
+use std::time::Duration;
+use crate::utils::AppError;
+
+const DAV_NS: &str = "DAV:";
+const CALDAV_NS: &str = "urn:ietf:params:xml:ns:caldav";
+/// How long a single request may take before the sync gives up
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// One HTTP request to a WebDAV server.
+#[derive(Debug, Clone)]
+pub struct DavRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+/// The server's answer. Error statuses are responses too; only failing to
+/// reach the server at all is an `Err`.
+#[derive(Debug, Clone)]
+pub struct DavResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl DavResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Sends requests to a CalDAV server. The sync engine only talks to the
+/// server through this, so it can be run against an in-process mock as well
+/// as a real server.
+pub trait DavTransport {
+    fn send(&self, request: DavRequest) -> Result<DavResponse, AppError>;
+}
+
+/// HTTP(S) with basic authentication.
+pub struct HttpTransport {
+    agent: ureq::Agent,
+    authorization: Option<String>,
+}
+
+impl HttpTransport {
+    pub fn new(username: &str, password: &str) -> Self {
+        let authorization = (!username.is_empty()).then(|| {
+            let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+            format!("Basic {}", credentials)
+        });
+        HttpTransport {
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            authorization,
+        }
+    }
+}
+
+impl DavTransport for HttpTransport {
+    fn send(&self, request: DavRequest) -> Result<DavResponse, AppError> {
+        let mut http = self.agent.request(&request.method, &request.url);
+        if let Some(ref authorization) = self.authorization {
+            http = http.set("Authorization", authorization);
+        }
+        for (name, value) in &request.headers {
+            http = http.set(name, value);
+        }
+
+        let result = match request.body {
+            Some(ref body) => http.send_string(body),
+            None => http.call(),
+        };
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => return Err(AppError::Server(format!("Could not reach {}: {}", request.url, e))),
+        };
+
+        let status = response.status();
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| response.header(&name).map(|value| (name.clone(), value.to_string())))
+            .collect();
+        let body = response.into_string()?;
+        Ok(DavResponse { status, headers, body })
+    }
+}
+
+/// A calendar collection that can hold tasks.
+#[derive(Debug, Clone)]
+pub struct RemoteCalendar {
+    pub url: String,
+    pub display_name: String,
+}
+
+/// A calendar object resource and its contents.
+#[derive(Debug, Clone)]
+pub struct RemoteItem {
+    pub url: String,
+    pub etag: Option<String>,
+    pub data: String,
+}
+
+/// What changed in a collection since a sync token.
+#[derive(Debug, Clone)]
+pub enum SyncChanges {
+    Changes {
+        token: String,
+        /// URL and ETag of every member added or changed
+        changed: Vec<(String, Option<String>)>,
+        removed: Vec<String>,
+    },
+    /// The server no longer accepts the token; a full listing is needed
+    TokenExpired,
+    /// The server does not support RFC 6578 sync
+    Unsupported,
+}
+
+/// Outcome of a conditional write.
+#[derive(Debug, Clone)]
+pub enum WriteResult {
+    Done { etag: Option<String> },
+    /// The resource changed (or appeared) on the server since we last saw it
+    PreconditionFailed,
+}
+
+/// A `<response>` of a multistatus body: the resource, its overall status
+/// if the server gave one, and its properties found with status 200.
+#[derive(Debug, Clone, Default)]
+struct MultiStatusEntry {
+    href: String,
+    status: Option<u16>,
+    props: Vec<Prop>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Prop {
+    name: String,
+    text: String,
+    /// `(local name, name attribute)` of each child element
+    children: Vec<(String, Option<String>)>,
+    /// Every `<href>` inside the property
+    hrefs: Vec<String>,
+}
+
+impl MultiStatusEntry {
+    fn prop(&self, name: &str) -> Option<&Prop> {
+        self.props.iter().find(|p| p.name == name)
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.prop(name).map(|p| p.text.trim().to_string()).filter(|t| !t.is_empty())
+    }
+
+    fn is_collection(&self) -> bool {
+        self.prop("resourcetype").is_some_and(|p| p.children.iter().any(|(n, _)| n == "collection"))
+    }
+
+    fn is_calendar(&self) -> bool {
+        self.prop("resourcetype").is_some_and(|p| p.children.iter().any(|(n, _)| n == "calendar"))
+    }
+}
+
+/// Talks CalDAV (RFC 4791) and collection sync (RFC 6578) to one server.
+/// URLs handed in and out are absolute.
+pub struct CalDavClient<'a> {
+    transport: &'a dyn DavTransport,
+    base_url: String,
+}
+
+impl<'a> CalDavClient<'a> {
+    pub fn new(transport: &'a dyn DavTransport, base_url: &str) -> Self {
+        CalDavClient {
+            transport,
+            base_url: base_url.trim().to_string(),
+        }
+    }
+
+    /// Find the collection that holds the user's calendars: the home set of
+    /// the current principal, or the base URL itself if the server does not
+    /// say.
+    pub fn calendar_home(&self) -> Result<String, AppError> {
+        let props = "<d:current-user-principal/><c:calendar-home-set/>";
+        let entries = self.propfind(&self.base_url, "0", props)?;
+        if let Some(home) = entries.iter().find_map(|e| e.prop("calendar-home-set")?.hrefs.first().cloned()) {
+            return Ok(collection_url(&self.absolute(&home)));
+        }
+
+        let principal = entries.iter().find_map(|e| e.prop("current-user-principal")?.hrefs.first().cloned());
+        if let Some(principal) = principal {
+            let principal = self.absolute(&principal);
+            let entries = self.propfind(&principal, "0", "<c:calendar-home-set/>")?;
+            if let Some(home) = entries.iter().find_map(|e| e.prop("calendar-home-set")?.hrefs.first().cloned()) {
+                return Ok(collection_url(&self.absolute(&home)));
+            }
+        }
+
+        Ok(collection_url(&self.base_url))
+    }
+
+    /// Every calendar in `home` (or `home` itself, if it is one) that can
+    /// hold VTODOs.
+    pub fn list_calendars(&self, home: &str) -> Result<Vec<RemoteCalendar>, AppError> {
+        let props = "<d:resourcetype/><d:displayname/><c:supported-calendar-component-set/>";
+        let entries = self.propfind(home, "1", props)?;
+
+        Ok(entries
+            .into_iter()
+            .filter(|e| e.is_calendar())
+            .filter(|e| match e.prop("supported-calendar-component-set") {
+                // Servers that leave it out accept every component
+                Some(set) if !set.children.is_empty() => {
+                    set.children.iter().any(|(_, name)| name.as_deref().is_some_and(|n| n.eq_ignore_ascii_case("VTODO")))
+                }
+                _ => true,
+            })
+            .map(|e| {
+                let url = collection_url(&self.absolute(&e.href));
+                let display_name = e.text("displayname").unwrap_or_else(|| last_segment(&url));
+                RemoteCalendar { url, display_name }
+            })
+            .collect())
+    }
+
+    /// Create a calendar for tasks called `name` inside `home`.
+    pub fn create_calendar(&self, home: &str, name: &str) -> Result<String, AppError> {
+        let url = format!("{}{}/", collection_url(home), uuid::Uuid::new_v4());
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<c:mkcalendar xmlns:d="{DAV_NS}" xmlns:c="{CALDAV_NS}">
+  <d:set><d:prop>
+    <d:displayname>{}</d:displayname>
+    <c:supported-calendar-component-set><c:comp name="VTODO"/></c:supported-calendar-component-set>
+  </d:prop></d:set>
+</c:mkcalendar>"#,
+            escape_xml(name)
+        );
+        let response = self.send("MKCALENDAR", &url, &[("Content-Type", "application/xml; charset=utf-8")], Some(body))?;
+        if !response.is_success() {
+            return Err(unexpected("MKCALENDAR", &url, &response));
+        }
+        Ok(url)
+    }
+
+    /// Members of `calendar` added, changed or removed since `token`, or
+    /// all of them when there is no token yet.
+    pub fn sync_collection(&self, calendar: &str, token: Option<&str>) -> Result<SyncChanges, AppError> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:sync-collection xmlns:d="{DAV_NS}">
+  <d:sync-token>{}</d:sync-token>
+  <d:sync-level>1</d:sync-level>
+  <d:prop><d:getetag/></d:prop>
+</d:sync-collection>"#,
+            escape_xml(token.unwrap_or(""))
+        );
+        let response = self.send(
+            "REPORT",
+            calendar,
+            &[("Content-Type", "application/xml; charset=utf-8"), ("Depth", "1")],
+            Some(body),
+        )?;
+
+        match response.status {
+            207 => {}
+            403 | 409 | 410 if token.is_some() => return Ok(SyncChanges::TokenExpired),
+            400 | 403 | 405 | 501 => return Ok(SyncChanges::Unsupported),
+            _ => return Err(unexpected("REPORT", calendar, &response)),
+        }
+
+        let document = parse_xml(&response.body)?;
+        let Some(token) = document
+            .descendants()
+            .find(|n| n.is_element() && n.tag_name().name() == "sync-token" && n.parent().is_some_and(|p| p.tag_name().name() == "multistatus"))
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+        else {
+            return Ok(SyncChanges::Unsupported);
+        };
+
+        let mut changed = Vec::new();
+        let mut removed = Vec::new();
+        for entry in multistatus(&document) {
+            let url = self.absolute(&entry.href);
+            if collection_url(&url) == collection_url(calendar) || entry.is_collection() {
+                continue;
+            }
+            match entry.status {
+                Some(404) => removed.push(url),
+                _ => changed.push((url, entry.text("getetag"))),
+            }
+        }
+        Ok(SyncChanges::Changes { token, changed, removed })
+    }
+
+    /// URL and ETag of every member of `calendar`.
+    pub fn list_items(&self, calendar: &str) -> Result<Vec<(String, Option<String>)>, AppError> {
+        let entries = self.propfind(calendar, "1", "<d:resourcetype/><d:getetag/>")?;
+        Ok(entries
+            .into_iter()
+            .filter(|e| !e.is_collection())
+            .map(|e| (self.absolute(&e.href), e.text("getetag")))
+            .collect())
+    }
+
+    /// The contents of the given members of `calendar`. Members that have
+    /// disappeared in the meantime are left out.
+    pub fn fetch_items(&self, calendar: &str, urls: &[String]) -> Result<Vec<RemoteItem>, AppError> {
+        if urls.is_empty() {
+            return Ok(Vec::new());
+        }
+        let hrefs: String = urls
+            .iter()
+            .map(|url| format!("  <d:href>{}</d:href>\n", escape_xml(path_of(url))))
+            .collect();
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-multiget xmlns:d="{DAV_NS}" xmlns:c="{CALDAV_NS}">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+{}</c:calendar-multiget>"#,
+            hrefs
+        );
+        let response = self.send(
+            "REPORT",
+            calendar,
+            &[("Content-Type", "application/xml; charset=utf-8"), ("Depth", "1")],
+            Some(body),
+        )?;
+        if response.status != 207 {
+            return Err(unexpected("REPORT", calendar, &response));
+        }
+
+        let document = parse_xml(&response.body)?;
+        Ok(multistatus(&document)
+            .into_iter()
+            .filter_map(|entry| {
+                let data = entry.prop("calendar-data")?.text.clone();
+                Some(RemoteItem {
+                    url: self.absolute(&entry.href),
+                    etag: entry.text("getetag"),
+                    data,
+                })
+            })
+            .collect())
+    }
+
+    /// Write an iCalendar object. With `etag` the write only succeeds if the
+    /// resource is unchanged; without, only if it does not exist yet.
+    pub fn put(&self, url: &str, ics: &str, etag: Option<&str>) -> Result<WriteResult, AppError> {
+        let condition = match etag {
+            Some(etag) => ("If-Match", etag),
+            None => ("If-None-Match", "*"),
+        };
+        let response = self.send("PUT", url, &[("Content-Type", "text/calendar; charset=utf-8"), condition], Some(ics.to_string()))?;
+        match response.status {
+            412 => Ok(WriteResult::PreconditionFailed),
+            _ if response.is_success() => {
+                let etag = match response.header("ETag") {
+                    Some(etag) => Some(etag.to_string()),
+                    // Servers may leave it out when they changed the data
+                    None => self.etag(url)?,
+                };
+                Ok(WriteResult::Done { etag })
+            }
+            _ => Err(unexpected("PUT", url, &response)),
+        }
+    }
+
+    /// Delete a resource, only if it is unchanged when `etag` is given.
+    /// Deleting something already gone succeeds.
+    pub fn delete(&self, url: &str, etag: Option<&str>) -> Result<WriteResult, AppError> {
+        let headers: Vec<(&str, &str)> = etag.map(|etag| ("If-Match", etag)).into_iter().collect();
+        let response = self.send("DELETE", url, &headers, None)?;
+        match response.status {
+            412 => Ok(WriteResult::PreconditionFailed),
+            404 => Ok(WriteResult::Done { etag: None }),
+            _ if response.is_success() => Ok(WriteResult::Done { etag: None }),
+            _ => Err(unexpected("DELETE", url, &response)),
+        }
+    }
+
+    fn etag(&self, url: &str) -> Result<Option<String>, AppError> {
+        Ok(self.propfind(url, "0", "<d:getetag/>")?.into_iter().find_map(|e| e.text("getetag")))
+    }
+
+    fn propfind(&self, url: &str, depth: &str, props: &str) -> Result<Vec<MultiStatusEntry>, AppError> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="{DAV_NS}" xmlns:c="{CALDAV_NS}">
+  <d:prop>{}</d:prop>
+</d:propfind>"#,
+            props
+        );
+        let response = self.send(
+            "PROPFIND",
+            url,
+            &[("Content-Type", "application/xml; charset=utf-8"), ("Depth", depth)],
+            Some(body),
+        )?;
+        if response.status != 207 {
+            return Err(unexpected("PROPFIND", url, &response));
+        }
+        Ok(multistatus(&parse_xml(&response.body)?))
+    }
+
+    fn send(&self, method: &str, url: &str, headers: &[(&str, &str)], body: Option<String>) -> Result<DavResponse, AppError> {
+        self.transport.send(DavRequest {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            body,
+        })
+    }
+
+    /// Resolve an href from a response against the server
+    fn absolute(&self, href: &str) -> String {
+        if href.starts_with("http://") || href.starts_with("https://") {
+            return href.to_string();
+        }
+        if href.starts_with('/') {
+            return format!("{}{}", origin_of(&self.base_url), href);
+        }
+        format!("{}{}", collection_url(&self.base_url), href)
+    }
+}
+
+fn unexpected(method: &str, url: &str, response: &DavResponse) -> AppError {
+    match response.status {
+        401 | 403 => AppError::Server(format!("{} {} was refused ({}); check the user name and password", method, url, response.status)),
+        status => AppError::Server(format!("{} {} failed with status {}", method, url, status)),
+    }
+}
+
+fn parse_xml(body: &str) -> Result<roxmltree::Document<'_>, AppError> {
+    roxmltree::Document::parse(body).map_err(|e| AppError::Server(format!("Malformed response from the server: {}", e)))
+}
+
+fn multistatus(document: &roxmltree::Document) -> Vec<MultiStatusEntry> {
+    document
+        .descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == "response" && n.tag_name().namespace() == Some(DAV_NS))
+        .map(|response| {
+            let mut entry = MultiStatusEntry::default();
+            for child in response.children().filter(|n| n.is_element()) {
+                match child.tag_name().name() {
+                    "href" => entry.href = child.text().unwrap_or("").trim().to_string(),
+                    "status" => entry.status = parse_status(child.text().unwrap_or("")),
+                    "propstat" => {
+                        let ok = child
+                            .children()
+                            .find(|n| n.tag_name().name() == "status")
+                            .and_then(|n| parse_status(n.text().unwrap_or("")))
+                            .is_none_or(|s| s == 200);
+                        if !ok {
+                            continue;
+                        }
+                        for prop in child.children().filter(|n| n.tag_name().name() == "prop").flat_map(|p| p.children()) {
+                            if !prop.is_element() {
+                                continue;
+                            }
+                            entry.props.push(Prop {
+                                name: prop.tag_name().name().to_string(),
+                                text: prop.descendants().filter(|n| n.is_text()).filter_map(|n| n.text()).collect(),
+                                children: prop
+                                    .children()
+                                    .filter(|n| n.is_element())
+                                    .map(|n| (n.tag_name().name().to_string(), n.attribute("name").map(str::to_string)))
+                                    .collect(),
+                                hrefs: prop
+                                    .descendants()
+                                    .filter(|n| n.is_element() && n.tag_name().name() == "href")
+                                    .filter_map(|n| n.text().map(|t| t.trim().to_string()))
+                                    .collect(),
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            entry
+        })
+        .collect()
+}
+
+/// `HTTP/1.1 404 Not Found` -> 404
+fn parse_status(line: &str) -> Option<u16> {
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// `https://host:port` of a URL
+fn origin_of(url: &str) -> &str {
+    let after_scheme = url.find("://").map(|i| i + 3).unwrap_or(0);
+    match url[after_scheme..].find('/') {
+        Some(i) => &url[..after_scheme + i],
+        None => url,
+    }
+}
+
+/// The path of a URL, which is what servers expect in an `<href>`
+fn path_of(url: &str) -> &str {
+    let origin = origin_of(url);
+    match &url[origin.len()..] {
+        "" => "/",
+        path => path,
+    }
+}
+
+fn collection_url(url: &str) -> String {
+    if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{}/", url)
+    }
+}
+
+fn last_segment(url: &str) -> String {
+    url.trim_end_matches('/').rsplit('/').next().unwrap_or(url).to_string()
+}
@@ -0,0 +1,468 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::database::{CategoryRepository, TaskRepository};
+use crate::interop::ImportSummary;
+use crate::models::{CreateTaskRequest, Priority, RecurrenceRule, Task, TaskFilter};
+use crate::utils::AppError;
+
+/// Columns written by `export_csv`, in order
+const EXPORT_HEADERS: [&str; 13] = [
+    "id", "title", "description", "completed", "priority", "due_date", "category",
+    "tags", "parent_id", "parent_title", "recurrence", "created_at", "updated_at",
+];
+
+/// Rows shown in the mapping preview
+const PREVIEW_ROWS: usize = 5;
+
+/// A data row and the line it starts on
+type CsvRow = (usize, Vec<String>);
+
+/// A row that passed validation, before parents and categories are resolved
+struct ParsedRow {
+    line: usize,
+    task: Task,
+    category: Option<String>,
+    file_id: Option<String>,
+    parent: Option<String>,
+}
+
+/// Which CSV column (by zero-based index) feeds each task field. Only
+/// `title` is required.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvColumnMapping {
+    pub title: Option<usize>,
+    pub description: Option<usize>,
+    pub completed: Option<usize>,
+    pub priority: Option<usize>,
+    pub due_date: Option<usize>,
+    pub category: Option<usize>,
+    pub tags: Option<usize>,
+    /// The row's own ID within the file, used to resolve `parent`
+    pub id: Option<usize>,
+    /// The parent's ID or title, either within the file or an existing task
+    pub parent: Option<usize>,
+    pub recurrence: Option<usize>,
+}
+
+/// The first rows of a CSV file and a guess at how its columns map to task
+/// fields, for the mapping step of an import.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvPreview {
+    pub headers: Vec<String>,
+    pub sample_rows: Vec<Vec<String>>,
+    pub total_rows: usize,
+    pub suggested_mapping: CsvColumnMapping,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvRowError {
+    /// Line number in the file, counting the header as line 1
+    pub line: usize,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+/// Outcome of a CSV import. In a dry run nothing is written and `summary`
+/// stays empty; otherwise valid rows are imported and the rows listed in
+/// `errors` are skipped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvImportReport {
+    pub dry_run: bool,
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub errors: Vec<CsvRowError>,
+    /// Category names in the file that do not exist yet and will be (or were) created
+    pub new_categories: Vec<String>,
+    pub summary: ImportSummary,
+}
+
+/// Write tasks matching `filter` as CSV with a header row. Dates are ISO
+/// 8601 in UTC, tags are comma-separated within their cell.
+pub fn export_csv(conn: &Connection, filter: Option<TaskFilter>) -> Result<(String, usize), AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let categories = CategoryRepository::new(conn).get_all()?;
+    let tasks = task_repo.get_all(filter)?;
+
+    // Parents may fall outside the filter, so look them up as needed
+    let mut parent_titles: HashMap<String, String> = tasks.iter().map(|t| (t.id.clone(), t.title.clone())).collect();
+    for task in &tasks {
+        if let Some(ref parent_id) = task.parent_id {
+            if !parent_titles.contains_key(parent_id) {
+                if let Some(parent) = task_repo.get_by_id(parent_id)? {
+                    parent_titles.insert(parent.id, parent.title);
+                }
+            }
+        }
+    }
+
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer.write_record(EXPORT_HEADERS).map_err(csv_error)?;
+
+    let iso = |dt: NaiveDateTime| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    for task in &tasks {
+        let category = task
+            .category_id
+            .as_ref()
+            .and_then(|id| categories.iter().find(|c| &c.id == id))
+            .map(|c| c.name.clone())
+            .unwrap_or_default();
+        let due = match task.due_date {
+            // Date-only due dates are stored as midnight UTC
+            Some(due) if due.time() == NaiveTime::MIN => due.format("%Y-%m-%d").to_string(),
+            Some(due) => iso(due),
+            None => String::new(),
+        };
+        let parent_id = task.parent_id.clone().unwrap_or_default();
+        let parent_title = parent_titles.get(&parent_id).cloned().unwrap_or_default();
+
+        writer
+            .write_record([
+                task.id.clone(),
+                task.title.clone(),
+                task.description.clone().unwrap_or_default(),
+                task.completed.to_string(),
+                task.priority.to_string(),
+                due,
+                category,
+                task.tags.join(", "),
+                parent_id,
+                parent_title,
+                task.recurrence.as_ref().map(|r| r.to_string()).unwrap_or_default(),
+                iso(task.created_at),
+                iso(task.updated_at),
+            ])
+            .map_err(csv_error)?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    let text = String::from_utf8(bytes).map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    Ok((text, tasks.len()))
+}
+
+pub fn preview_csv(text: &str) -> Result<CsvPreview, AppError> {
+    let (headers, rows) = read_rows(text)?;
+    Ok(CsvPreview {
+        suggested_mapping: suggest_mapping(&headers),
+        sample_rows: rows.iter().take(PREVIEW_ROWS).map(|(_, r)| r.clone()).collect(),
+        total_rows: rows.len(),
+        headers,
+    })
+}
+
+/// Validate every row against `mapping` and, unless `dry_run`, create a task
+/// for each valid row in one transaction. Categories named in the file that
+/// do not exist yet are created.
+pub fn import_csv(conn: &Connection, text: &str, mapping: &CsvColumnMapping, dry_run: bool) -> Result<CsvImportReport, AppError> {
+    let (headers, rows) = read_rows(text)?;
+    let title_column = mapping
+        .title
+        .ok_or_else(|| AppError::InvalidInput("Choose the column that holds the task title".to_string()))?;
+    for column in [
+        Some(title_column), mapping.description, mapping.completed, mapping.priority, mapping.due_date,
+        mapping.category, mapping.tags, mapping.id, mapping.parent, mapping.recurrence,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if column >= headers.len() {
+            return Err(AppError::InvalidInput(format!("The file has no column {}", column + 1)));
+        }
+    }
+
+    let task_repo = TaskRepository::new(conn);
+    let mut categories = CategoryRepository::new(conn).get_all()?;
+    let mut report = CsvImportReport { dry_run, total_rows: rows.len(), ..Default::default() };
+
+    let cell = |row: &Vec<String>, column: Option<usize>| -> Option<String> {
+        column.and_then(|c| row.get(c)).map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+    };
+    let column_name = |column: Option<usize>| column.and_then(|c| headers.get(c)).cloned();
+
+    // First pass: parse every row on its own
+    let mut parsed: Vec<ParsedRow> = Vec::new();
+    for (line, row) in &rows {
+        let mut errors = Vec::new();
+        let mut fail = |column: Option<usize>, message: String| {
+            errors.push(CsvRowError { line: *line, column: column_name(column), message });
+        };
+
+        let title = cell(row, Some(title_column));
+        if title.is_none() {
+            fail(Some(title_column), "Title is empty".to_string());
+        }
+
+        let completed = match cell(row, mapping.completed) {
+            Some(value) => parse_completed(&value).unwrap_or_else(|| {
+                fail(mapping.completed, format!("'{}' is not a yes/no value", value));
+                false
+            }),
+            None => false,
+        };
+        let priority = match cell(row, mapping.priority) {
+            Some(value) => parse_priority(&value).unwrap_or_else(|| {
+                fail(mapping.priority, format!("Unknown priority '{}'", value));
+                Priority::Medium
+            }),
+            None => Priority::Medium,
+        };
+        let due_date = cell(row, mapping.due_date).and_then(|value| {
+            let parsed = parse_date(&value);
+            if parsed.is_none() {
+                fail(mapping.due_date, format!("'{}' is not a date (use YYYY-MM-DD or ISO 8601)", value));
+            }
+            parsed
+        });
+        let recurrence = cell(row, mapping.recurrence).and_then(|value| match value.parse::<RecurrenceRule>() {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                fail(mapping.recurrence, e);
+                None
+            }
+        });
+        let tags = cell(row, mapping.tags)
+            .map(|value| {
+                let mut tags: Vec<String> = Vec::new();
+                for tag in value.split([',', ';']).map(str::trim).filter(|t| !t.is_empty()) {
+                    if !tags.iter().any(|t| t == tag) {
+                        tags.push(tag.to_string());
+                    }
+                }
+                tags
+            })
+            .unwrap_or_default();
+
+        if !errors.is_empty() {
+            report.errors.extend(errors);
+            continue;
+        }
+
+        let mut task = Task::new(CreateTaskRequest {
+            title: title.unwrap_or_default(),
+            description: cell(row, mapping.description),
+            priority: None,
+            due_date: None,
+            category_id: None,
+            tags: Some(tags),
+            parent_id: None,
+            recurrence: None,
+        })
+        .expect("a request without a recurrence is always valid");
+        task.completed = completed;
+        task.priority = priority;
+        task.due_date = due_date;
+        task.recurrence = recurrence;
+
+        parsed.push(ParsedRow {
+            line: *line,
+            task,
+            category: cell(row, mapping.category),
+            file_id: cell(row, mapping.id),
+            parent: cell(row, mapping.parent),
+        });
+    }
+
+    // Second pass: resolve parents against the file, then existing tasks
+    let by_file_id: HashMap<String, usize> = parsed
+        .iter()
+        .enumerate()
+        .filter_map(|(i, row)| Some((row.file_id.clone()?, i)))
+        .collect();
+    let mut by_title: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, row) in parsed.iter().enumerate() {
+        by_title.entry(row.task.title.to_lowercase()).or_default().push(i);
+    }
+
+    let mut parent_of: Vec<Option<usize>> = vec![None; parsed.len()];
+    let mut invalid: HashSet<usize> = HashSet::new();
+    for i in 0..parsed.len() {
+        let line = parsed[i].line;
+        let parent = match parsed[i].parent.clone() {
+            Some(parent) => parent,
+            None => continue,
+        };
+
+        if let Some(&j) = by_file_id.get(&parent) {
+            parent_of[i] = Some(j);
+        } else if let Some(matches) = by_title.get(&parent.to_lowercase()) {
+            if matches.len() > 1 {
+                report.errors.push(CsvRowError {
+                    line,
+                    column: column_name(mapping.parent),
+                    message: format!("More than one row is titled '{}'", parent),
+                });
+                invalid.insert(i);
+            } else {
+                parent_of[i] = Some(matches[0]);
+            }
+        } else if task_repo.get_by_id(&parent)?.is_some() {
+            parsed[i].task.parent_id = Some(parent);
+        } else {
+            report.errors.push(CsvRowError {
+                line,
+                column: column_name(mapping.parent),
+                message: format!("Parent '{}' not found", parent),
+            });
+            invalid.insert(i);
+        }
+    }
+
+    // A row is only importable if its whole chain of parents is
+    for i in 0..parsed.len() {
+        let mut seen = HashSet::new();
+        let mut current = i;
+        while let Some(parent) = parent_of[current] {
+            if !seen.insert(current) {
+                report.errors.push(CsvRowError {
+                    line: parsed[i].line,
+                    column: column_name(mapping.parent),
+                    message: "Row is part of a parent/subtask cycle".to_string(),
+                });
+                invalid.insert(i);
+                break;
+            }
+            if invalid.contains(&parent) {
+                report.errors.push(CsvRowError {
+                    line: parsed[i].line,
+                    column: column_name(mapping.parent),
+                    message: format!("Parent on line {} has errors", parsed[parent].line),
+                });
+                invalid.insert(i);
+                break;
+            }
+            current = parent;
+        }
+    }
+    report.errors.sort_by_key(|e| e.line);
+
+    for (i, row) in parsed.iter().enumerate() {
+        if invalid.contains(&i) {
+            continue;
+        }
+        if let Some(ref name) = row.category {
+            let known = categories.iter().any(|c| c.name.eq_ignore_ascii_case(name))
+                || report.new_categories.iter().any(|n| n.eq_ignore_ascii_case(name));
+            if !known {
+                report.new_categories.push(name.clone());
+            }
+        }
+    }
+    report.valid_rows = parsed.len() - invalid.len();
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    // Children may come before their parents in the file
+    tx.pragma_update(None, "defer_foreign_keys", true)?;
+
+    let mut summary = ImportSummary::default();
+    let ids: Vec<String> = parsed.iter().map(|row| row.task.id.clone()).collect();
+    for (i, row) in parsed.iter().enumerate() {
+        if invalid.contains(&i) {
+            continue;
+        }
+        let mut task = row.task.clone();
+        if let Some(parent) = parent_of[i] {
+            task.parent_id = Some(ids[parent].clone());
+        }
+        task.category_id = match row.category {
+            Some(ref name) => Some(summary.find_or_create_category(conn, &mut categories, name)?),
+            None => None,
+        };
+
+        task_repo.create(&task)?;
+        summary.tasks_created += 1;
+        summary.task_ids.push(task.id);
+    }
+    tx.commit()?;
+
+    report.summary = summary;
+    Ok(report)
+}
+
+fn csv_error(e: ::csv::Error) -> AppError {
+    AppError::InvalidInput(format!("CSV error: {}", e))
+}
+
+/// The header row and every data row with its line number. Rows may have
+/// fewer or more cells than the header.
+fn read_rows(text: &str) -> Result<(Vec<String>, Vec<CsvRow>), AppError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut reader = ::csv::ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+
+    let headers: Vec<String> = reader.headers().map_err(csv_error)?.iter().map(|h| h.trim().to_string()).collect();
+    if headers.iter().all(|h| h.is_empty()) {
+        return Err(AppError::InvalidInput("The file has no header row".to_string()));
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let line = record.position().map(|p| p.line() as usize).unwrap_or(rows.len() + 2);
+        let row: Vec<String> = record.iter().map(str::to_string).collect();
+        if row.iter().any(|c| !c.trim().is_empty()) {
+            rows.push((line, row));
+        }
+    }
+    Ok((headers, rows))
+}
+
+/// Guess the mapping from header names, accepting the columns written by
+/// `export_csv` and common names used by other apps.
+fn suggest_mapping(headers: &[String]) -> CsvColumnMapping {
+    let find = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            headers.iter().position(|h| h.to_lowercase().replace(['_', '-'], " ") == *name)
+        })
+    };
+
+    CsvColumnMapping {
+        title: find(&["title", "name", "task", "task name", "summary", "subject", "content"]),
+        description: find(&["description", "notes", "note", "details", "body"]),
+        completed: find(&["completed", "done", "complete", "status", "is completed"]),
+        priority: find(&["priority", "importance"]),
+        due_date: find(&["due date", "due", "deadline", "due on", "date"]),
+        category: find(&["category", "list", "project", "folder"]),
+        tags: find(&["tags", "labels", "tag", "label"]),
+        id: find(&["id", "task id", "uid"]),
+        parent: find(&["parent id", "parent", "parent title", "parent task"]),
+        recurrence: find(&["recurrence", "rrule", "repeat"]),
+    }
+}
+
+fn parse_completed(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" | "x" | "done" | "completed" | "complete" => Some(true),
+        "false" | "no" | "n" | "0" | "open" | "pending" | "todo" | "needs-action" | "not started" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_priority(value: &str) -> Option<Priority> {
+    match value.to_lowercase().as_str() {
+        "high" | "h" | "1" | "urgent" | "important" => Some(Priority::High),
+        "medium" | "m" | "2" | "normal" | "none" => Some(Priority::Medium),
+        "low" | "l" | "3" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// ISO 8601 with an offset is taken as-is; a plain date becomes midnight UTC
+/// like dates picked in the task form; a date and time without an offset is
+/// read as local time.
+fn parse_date(value: &str) -> Option<NaiveDateTime> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.naive_utc());
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0);
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .and_then(|local| Local.from_local_datetime(&local).earliest())
+        .map(|dt| dt.naive_utc())
+}
@@ -0,0 +1,102 @@
+use serde::Deserialize;
+use serde_json::Value;
+use crate::interop::services::{parse_service_date, ServiceImportReport, ServiceTask};
+use crate::utils::AppError;
+
+pub const GOOGLE_TASKS_SOURCE: &str = "Google Tasks";
+
+#[derive(Debug, Clone, Deserialize)]
+struct TaskList {
+    #[serde(default)]
+    title: String,
+    #[serde(default, alias = "tasks")]
+    items: Vec<GoogleTask>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TaskLink {
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    link: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GoogleTask {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    parent: Option<String>,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    created: Option<String>,
+    #[serde(default)]
+    deleted: bool,
+    #[serde(default)]
+    links: Vec<TaskLink>,
+    #[serde(default)]
+    assignment_info: Option<Value>,
+}
+
+/// Parse `Tasks.json` from a Google Takeout archive: a `tasks#taskLists`
+/// object whose `items` are lists, each holding its tasks in `items`.
+/// Subtasks point at their parent with `parent`.
+pub fn parse_google_tasks(text: &str, report: &mut ServiceImportReport) -> Result<Vec<ServiceTask>, AppError> {
+    let value: Value = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+        .map_err(|e| AppError::InvalidInput(format!("Not a Google Tasks export: {}", e)))?;
+    let lists = match value {
+        Value::Object(map) if map.get("kind").and_then(Value::as_str) == Some("tasks#taskList") => {
+            Value::Array(vec![Value::Object(map)])
+        }
+        Value::Object(mut map) => map.remove("items").unwrap_or(Value::Null),
+        other => other,
+    };
+    let lists: Vec<TaskList> = serde_json::from_value(lists)
+        .map_err(|e| AppError::InvalidInput(format!("Not a Google Tasks export: {}", e)))?;
+
+    let mut tasks = Vec::new();
+    for (list_index, list) in lists.into_iter().enumerate() {
+        for (task_index, item) in list.items.into_iter().enumerate() {
+            if item.deleted {
+                report.skip(&item.title, "Deleted in Google Tasks");
+                continue;
+            }
+
+            let mut description = item.notes.unwrap_or_default();
+            for link in &item.links {
+                let Some(url) = link.link.as_deref().filter(|l| !l.is_empty()) else { continue };
+                if !description.is_empty() {
+                    description.push('\n');
+                }
+                match link.description.as_deref().filter(|d| !d.is_empty()) {
+                    Some(label) => description.push_str(&format!("{}: {}", label, url)),
+                    None => description.push_str(url),
+                }
+            }
+            if item.assignment_info.is_some() {
+                report.lossy("assignment", "Tasks assigned from Docs or Chat lose their link to the document");
+            }
+
+            tasks.push(ServiceTask {
+                source_id: item.id.unwrap_or_else(|| format!("{}:{}", list_index, task_index)),
+                parent: item.parent,
+                list: Some(list.title.clone()),
+                title: item.title,
+                description: Some(description),
+                completed: item.status.as_deref() == Some("completed"),
+                // Google Tasks has no due times; the API writes the day as midnight UTC
+                due_date: item.due.as_deref().and_then(|d| parse_service_date(d, true)),
+                created_at: item.created.as_deref().and_then(|d| parse_service_date(d, false)),
+                ..Default::default()
+            });
+        }
+    }
+    Ok(tasks)
+}
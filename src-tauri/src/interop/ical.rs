@@ -0,0 +1,511 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+use rusqlite::Connection;
+use std::collections::HashSet;
+use crate::database::{CategoryRepository, TaskRepository};
+use crate::interop::{break_parent_cycles, ImportSummary};
+use crate::models::{Category, Priority, RecurrenceRule, Task};
+use crate::utils::AppError;
+
+const PRODID: &str = "-//Pluto//Pluto Todo//EN";
+/// Longest content line allowed by RFC 5545 before folding, in octets
+const MAX_LINE_OCTETS: usize = 75;
+
+/// A property line of an iCalendar component, e.g.
+/// `DUE;VALUE=DATE:20261101`. `value` is still escaped.
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Todo,
+    Event,
+}
+
+/// A VTODO or VEVENT read from an `.ics` file.
+#[derive(Debug, Clone)]
+pub struct CalendarItem {
+    pub kind: ItemKind,
+    properties: Vec<Property>,
+}
+
+impl CalendarItem {
+    fn get(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.get(name).map(|p| unescape(&p.value)).filter(|v| !v.trim().is_empty())
+    }
+
+    pub fn uid(&self) -> Option<String> {
+        self.text("UID")
+    }
+
+    pub fn summary(&self) -> Option<String> {
+        self.text("SUMMARY")
+    }
+
+    pub fn created_at(&self) -> Option<NaiveDateTime> {
+        self.get("CREATED").and_then(parse_date_time)
+    }
+
+    pub fn last_modified(&self) -> Option<NaiveDateTime> {
+        self.get("LAST-MODIFIED").and_then(parse_date_time)
+    }
+
+    /// The category Pluto wrote explicitly, if this item came from Pluto
+    pub fn pluto_category(&self) -> Option<String> {
+        self.text("X-PLUTO-CATEGORY")
+    }
+
+    /// Every CATEGORIES entry, across all CATEGORIES lines
+    pub fn category_names(&self) -> Vec<String> {
+        self.properties
+            .iter()
+            .filter(|p| p.name == "CATEGORIES")
+            .flat_map(|p| split_list(&p.value))
+            .filter(|n| !n.trim().is_empty())
+            .collect()
+    }
+
+    pub fn parent_uid(&self) -> Option<String> {
+        self.properties
+            .iter()
+            .find(|p| p.name == "RELATED-TO" && p.param("RELTYPE").map(|r| r.eq_ignore_ascii_case("PARENT")).unwrap_or(true))
+            .map(|p| unescape(&p.value))
+    }
+
+    /// Copy the description, priority, due date, status and recurrence onto
+    /// `task`. The title, category, tags and parent are left to the caller,
+    /// since they depend on where the item came from.
+    pub fn apply_fields(&self, task: &mut Task, warnings: &mut Vec<String>) {
+        task.description = self.text("DESCRIPTION");
+        task.priority = match self.get("PRIORITY").and_then(|p| p.value.trim().parse::<u32>().ok()) {
+            Some(1..=4) => Priority::High,
+            Some(6..=9) => Priority::Low,
+            _ => Priority::Medium,
+        };
+
+        let due = match self.kind {
+            ItemKind::Todo => self.get("DUE").or_else(|| self.get("DTSTART")),
+            ItemKind::Event => self.get("DTSTART"),
+        };
+        task.due_date = due.and_then(parse_date_time);
+
+        let status = self.get("STATUS").map(|p| p.value.trim().to_ascii_uppercase());
+        task.completed = matches!(status.as_deref(), Some("COMPLETED") | Some("CANCELLED")) || self.get("COMPLETED").is_some();
+
+        task.recurrence = match self.get("RRULE") {
+            Some(p) => match p.value.parse::<RecurrenceRule>() {
+                Ok(rule) => Some(rule),
+                Err(e) => {
+                    warnings.push(format!("'{}': recurrence dropped ({})", task.title, e));
+                    None
+                }
+            },
+            None => None,
+        };
+    }
+}
+
+/// Render tasks as an iCalendar document of VTODO components.
+///
+/// - priority maps to PRIORITY 1 (high), 5 (medium) and 9 (low)
+/// - the category name and tags are listed in CATEGORIES; the category is
+///   also written to X-PLUTO-CATEGORY so an import can tell it apart
+/// - subtasks point at their parent with RELATED-TO;RELTYPE=PARENT
+/// - the task ID is the UID, so re-importing updates rather than duplicates
+pub fn export_ics(tasks: &[Task], categories: &[Category]) -> String {
+    let now = Utc::now().naive_utc();
+    let mut lines = Vec::new();
+
+    for task in tasks {
+        let category = task
+            .category_id
+            .as_ref()
+            .and_then(|id| categories.iter().find(|c| &c.id == id));
+        lines.extend(vtodo_lines(task, category, &task.id, task.parent_id.as_deref(), now));
+    }
+
+    calendar_document(lines)
+}
+
+/// The lines of one VTODO for `task`, written under `uid` and pointing at
+/// its parent by `parent_uid`. Servers that sync items keep their own UIDs,
+/// which need not match task IDs.
+pub fn vtodo_lines(
+    task: &Task,
+    category: Option<&Category>,
+    uid: &str,
+    parent_uid: Option<&str>,
+    dtstamp: NaiveDateTime,
+) -> Vec<String> {
+    let mut lines = vec!["BEGIN:VTODO".to_string()];
+    lines.push(format!("UID:{}", escape(uid)));
+    lines.push(format!("DTSTAMP:{}", format_utc(dtstamp)));
+    lines.push(format!("CREATED:{}", format_utc(task.created_at)));
+    lines.push(format!("LAST-MODIFIED:{}", format_utc(task.updated_at)));
+    lines.push(format!("SUMMARY:{}", escape(&task.title)));
+    if let Some(ref description) = task.description {
+        if !description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(description)));
+        }
+    }
+    if let Some(due) = task.due_date {
+        // Date-only due dates are stored as midnight UTC
+        if due.time() == NaiveTime::MIN {
+            lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+        } else {
+            lines.push(format!("DUE:{}", format_utc(due)));
+        }
+    }
+    lines.push(format!(
+        "PRIORITY:{}",
+        match task.priority {
+            Priority::High => 1,
+            Priority::Medium => 5,
+            Priority::Low => 9,
+        }
+    ));
+    if task.completed {
+        lines.push("STATUS:COMPLETED".to_string());
+        lines.push("PERCENT-COMPLETE:100".to_string());
+        lines.push(format!("COMPLETED:{}", format_utc(task.updated_at)));
+    } else {
+        lines.push("STATUS:NEEDS-ACTION".to_string());
+    }
+
+    let mut names: Vec<String> = category.iter().map(|c| escape(&c.name)).collect();
+    names.extend(task.tags.iter().map(|t| escape(t)));
+    if !names.is_empty() {
+        lines.push(format!("CATEGORIES:{}", names.join(",")));
+    }
+    if let Some(category) = category {
+        lines.push(format!("X-PLUTO-CATEGORY:{}", escape(&category.name)));
+    }
+
+    if let Some(parent_uid) = parent_uid {
+        lines.push(format!("RELATED-TO;RELTYPE=PARENT:{}", escape(parent_uid)));
+    }
+    if let Some(ref rule) = task.recurrence {
+        lines.push(format!("RRULE:{}", rule));
+    }
+    lines.push("END:VTODO".to_string());
+    lines
+}
+
+/// Wrap component lines in a VCALENDAR and fold them.
+pub fn calendar_document(components: Vec<String>) -> String {
+    let mut out = String::new();
+    for line in ["BEGIN:VCALENDAR".to_string(), "VERSION:2.0".to_string(), format!("PRODID:{}", PRODID)]
+        .into_iter()
+        .chain(components)
+        .chain(["END:VCALENDAR".to_string()])
+    {
+        out.push_str(&fold(&line));
+    }
+    out
+}
+
+/// Read every VTODO and VEVENT in an iCalendar document. Components nested
+/// inside them (such as VALARM) are skipped.
+pub fn parse_ics(text: &str) -> Result<Vec<CalendarItem>, AppError> {
+    let mut items = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut current: Option<CalendarItem> = None;
+
+    for (number, line) in unfold(text).into_iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let property = parse_line(&line)
+            .ok_or_else(|| AppError::InvalidInput(format!("Malformed iCalendar line {}: {}", number + 1, line)))?;
+
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.to_ascii_uppercase();
+                if stack.is_empty() && component != "VCALENDAR" {
+                    return Err(AppError::InvalidInput("Not an iCalendar file".to_string()));
+                }
+                if stack.len() == 1 {
+                    current = match component.as_str() {
+                        "VTODO" => Some(CalendarItem { kind: ItemKind::Todo, properties: Vec::new() }),
+                        "VEVENT" => Some(CalendarItem { kind: ItemKind::Event, properties: Vec::new() }),
+                        _ => None,
+                    };
+                }
+                stack.push(component);
+            }
+            "END" => {
+                if stack.pop().as_deref() != Some(property.value.to_ascii_uppercase().as_str()) {
+                    return Err(AppError::InvalidInput(format!("Unbalanced END:{} on line {}", property.value, number + 1)));
+                }
+                if stack.len() == 1 {
+                    items.extend(current.take());
+                }
+            }
+            _ => {
+                if stack.len() == 2 {
+                    if let Some(ref mut item) = current {
+                        item.properties.push(property);
+                    }
+                }
+            }
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(AppError::InvalidInput(format!("Missing END:{}", stack.last().unwrap())));
+    }
+    Ok(items)
+}
+
+/// Create or update a task for every item. An item whose UID matches an
+/// existing task updates it, so exporting and re-importing is stable;
+/// otherwise the UID becomes the new task's ID. Runs in one transaction.
+pub fn import_items(conn: &Connection, items: &[CalendarItem]) -> Result<ImportSummary, AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let mut categories = CategoryRepository::new(conn).get_all()?;
+    let mut summary = ImportSummary::default();
+
+    let tx = conn.unchecked_transaction()?;
+    // Subtasks may come before their parents
+    tx.pragma_update(None, "defer_foreign_keys", true)?;
+
+    let mut tasks = Vec::new();
+    let mut seen = HashSet::new();
+    for item in items {
+        let title = match item.summary() {
+            Some(title) => title,
+            None => {
+                summary.warnings.push(format!("Skipped an item without a summary (UID {})", item.uid().unwrap_or_default()));
+                continue;
+            }
+        };
+        let uid = item.uid().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        if !seen.insert(uid.clone()) {
+            summary.warnings.push(format!("Skipped '{}': UID {} appears more than once", title, uid));
+            continue;
+        }
+
+        let existing = task_repo.get_by_id(&uid)?;
+        if existing.is_none() && task_repo.row_exists(&uid)? {
+            summary.warnings.push(format!("Skipped '{}': the task is in the trash", title));
+            continue;
+        }
+
+        let now = Utc::now().naive_utc();
+        let created_at = item.created_at().unwrap_or(now);
+        let mut task = existing.clone().unwrap_or_else(|| Task {
+            id: uid.clone(),
+            title: String::new(),
+            description: None,
+            completed: false,
+            priority: Priority::Medium,
+            due_date: None,
+            category_id: None,
+            tags: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+            created_at,
+            updated_at: now,
+            deleted_at: None,
+            position: Task::initial_position(created_at, false),
+        });
+
+        task.title = title;
+        task.updated_at = now;
+        item.apply_fields(&mut task, &mut summary.warnings);
+
+        // The category is whichever CATEGORIES entry names one of ours (or
+        // the one Pluto marked explicitly); everything else is a tag
+        let names = item.category_names();
+        let category_name = item
+            .pluto_category()
+            .or_else(|| names.iter().find(|n| categories.iter().any(|c| c.name.eq_ignore_ascii_case(n))).cloned());
+        task.category_id = match category_name {
+            Some(ref name) => Some(summary.find_or_create_category(conn, &mut categories, name)?),
+            None => None,
+        };
+        task.tags = Vec::new();
+        for name in names {
+            let is_category = category_name.as_ref().map(|c| c.eq_ignore_ascii_case(&name)).unwrap_or(false);
+            if !is_category && !task.tags.contains(&name) {
+                task.tags.push(name);
+            }
+        }
+
+        task.parent_id = item.parent_uid().filter(|uid| uid != &task.id);
+
+        tasks.push((task, existing.is_some()));
+    }
+
+    // Parents must be in this file or already exist, and must not lead back
+    // to the task
+    let file_ids: HashSet<String> = tasks.iter().map(|(t, _)| t.id.clone()).collect();
+    for (task, _) in &mut tasks {
+        if let Some(ref parent_id) = task.parent_id {
+            if !file_ids.contains(parent_id) && task_repo.get_by_id(parent_id)?.is_none() {
+                summary.warnings.push(format!("'{}': parent {} not found, imported as a top-level task", task.title, parent_id));
+                task.parent_id = None;
+            }
+        }
+    }
+    break_parent_cycles(conn, tasks.iter_mut().map(|(t, _)| t), &mut summary.warnings)?;
+
+    for (task, exists) in &tasks {
+        if *exists {
+            task_repo.update(task)?;
+            summary.tasks_updated += 1;
+        } else {
+            task_repo.create(task)?;
+            summary.tasks_created += 1;
+        }
+        summary.task_ids.push(task.id.clone());
+    }
+
+    tx.commit()?;
+    Ok(summary)
+}
+
+fn format_utc(dt: NaiveDateTime) -> String {
+    dt.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// DATE values become midnight UTC like date-only due dates from the task
+/// form; UTC times are taken as-is and floating or TZID times are read as
+/// local time.
+fn parse_date_time(property: &Property) -> Option<NaiveDateTime> {
+    let value = property.value.trim();
+    if property.param("VALUE").map(|v| v.eq_ignore_ascii_case("DATE")).unwrap_or(false) || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok()?.and_hms_opt(0, 0, 0);
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok();
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Local
+        .from_local_datetime(&local.with_nanosecond(0)?)
+        .earliest()
+        .map(|dt| dt.naive_utc())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Split a comma-separated list value, honouring `\,` escapes.
+fn split_list(value: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            current.push('\\');
+            current.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == ',' {
+            parts.push(unescape(&current).trim().to_string());
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    parts.push(unescape(&current).trim().to_string());
+    parts
+}
+
+/// Fold a content line to at most 75 octets per physical line, as RFC 5545
+/// requires, without splitting a UTF-8 character.
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match raw.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+/// Split `NAME;PARAM=VALUE;PARAM="quoted:value":VALUE` into its parts.
+fn parse_line(line: &str) -> Option<Property> {
+    let mut in_quotes = false;
+    let mut colon = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => {
+                colon = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let colon = colon?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.trim().to_ascii_uppercase(), v.trim_matches('"').to_string()))
+        .collect();
+
+    Some(Property { name, params, value: value.to_string() })
+}
@@ -0,0 +1,605 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use crate::database::{Journal, SettingsRepository, TaskRepository};
+use crate::interop::{due_day, local_day, ImportSummary};
+use crate::models::{EntityState, Priority, Task};
+use crate::utils::AppError;
+
+/// A `- [ ] …` checkbox line in a note. The text after the checkbox keeps
+/// anything Pluto does not understand (other emoji fields, links) in the
+/// title, so rewriting the line does not lose it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkbox {
+    /// Everything before the `[`, i.e. the indentation and list marker
+    pub prefix: String,
+    /// Indentation width, with tabs counting as four spaces
+    pub indent: usize,
+    /// The character between the brackets
+    pub status: char,
+    pub title: String,
+    pub priority: Priority,
+    /// `📅 2026-10-20`
+    pub due: Option<NaiveDate>,
+    /// `✅ 2026-10-21`, added when a task is completed
+    pub done_on: Option<NaiveDate>,
+    pub tags: Vec<String>,
+    /// Obsidian block ID (`^…` at the end of the line), which is the task ID
+    pub block_id: Option<String>,
+}
+
+impl Checkbox {
+    /// Parse a checkbox list item; any other line gives `None`.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let body = line.trim_start();
+        let leading = &line[..line.len() - body.len()];
+        let indent = leading.chars().map(|c| if c == '\t' { 4 } else { 1 }).sum();
+
+        let rest = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| body.strip_prefix(marker))
+            .or_else(|| {
+                let digits = body.find(|c: char| !c.is_ascii_digit())?;
+                (digits > 0).then_some(())?;
+                body[digits..].strip_prefix(". ").or_else(|| body[digits..].strip_prefix(") "))
+            })?;
+        let mut chars = rest.chars();
+        if chars.next() != Some('[') {
+            return None;
+        }
+        let status = chars.next()?;
+        let text = chars.as_str().strip_prefix("] ").or_else(|| chars.as_str().strip_prefix(']').filter(|t| t.is_empty()))?;
+
+        let mut checkbox = Checkbox {
+            prefix: line[..line.len() - rest.len()].to_string(),
+            indent,
+            status,
+            title: String::new(),
+            priority: Priority::Medium,
+            due: None,
+            done_on: None,
+            tags: Vec::new(),
+            block_id: None,
+        };
+
+        let mut tokens: Vec<&str> = text.split_whitespace().collect();
+        if let Some(id) = tokens.last().and_then(|t| t.strip_prefix('^')).filter(|id| is_block_id(id)) {
+            checkbox.block_id = Some(id.to_string());
+            tokens.pop();
+        }
+
+        let mut words = Vec::new();
+        let mut index = 0;
+        while index < tokens.len() {
+            let token = tokens[index];
+            let date = tokens.get(index + 1).and_then(|t| NaiveDate::parse_from_str(t, "%Y-%m-%d").ok());
+            match token {
+                "📅" | "📆" | "🗓" | "🗓️" if date.is_some() => {
+                    checkbox.due = date;
+                    index += 2;
+                    continue;
+                }
+                "✅" if date.is_some() => {
+                    checkbox.done_on = date;
+                    index += 2;
+                    continue;
+                }
+                "🔺" | "⏫" => checkbox.priority = Priority::High,
+                "🔼" => checkbox.priority = Priority::Medium,
+                "🔽" | "⏬" => checkbox.priority = Priority::Low,
+                _ => match token.strip_prefix('#').filter(|t| is_tag(t)) {
+                    Some(tag) => {
+                        if !checkbox.tags.iter().any(|t| t == tag) {
+                            checkbox.tags.push(tag.to_string());
+                        }
+                    }
+                    None => words.push(token),
+                },
+            }
+            index += 1;
+        }
+        checkbox.title = words.join(" ");
+
+        Some(checkbox)
+    }
+
+    pub fn completed(&self) -> bool {
+        is_done_status(self.status)
+    }
+
+    /// The line for `task`, keeping this line's indentation and list marker.
+    /// A status character that already means the right thing is kept, as is
+    /// an existing completion date.
+    pub fn render(&self, task: &Task, line_ending: &str) -> String {
+        let status = if is_done_status(self.status) == task.completed {
+            self.status
+        } else if task.completed {
+            'x'
+        } else {
+            ' '
+        };
+
+        let mut parts = vec![task.title.split_whitespace().collect::<Vec<_>>().join(" ")];
+        match task.priority {
+            Priority::High => parts.push("⏫".to_string()),
+            Priority::Medium => {}
+            Priority::Low => parts.push("🔽".to_string()),
+        }
+        parts.extend(task.tags.iter().map(|t| format!("#{}", tag_name(t))));
+        if let Some(due) = task.due_date {
+            parts.push(format!("📅 {}", due_day(due).format("%Y-%m-%d")));
+        }
+        if task.completed {
+            let done_on = self.done_on.filter(|_| self.completed()).unwrap_or_else(|| local_day(task.updated_at));
+            parts.push(format!("✅ {}", done_on.format("%Y-%m-%d")));
+        }
+        parts.push(format!("^{}", task.id));
+
+        let text: Vec<String> = parts.into_iter().filter(|p| !p.is_empty()).collect();
+        format!("{}[{}] {}{}", self.prefix, status, text.join(" "), line_ending)
+    }
+
+    fn state(&self) -> LineState {
+        LineState {
+            completed: self.completed(),
+            title: self.title.clone(),
+            priority: self.priority,
+            due: self.due,
+            tags: self.tags.iter().map(|t| tag_name(t)).collect(),
+        }
+    }
+}
+
+/// The fields a checkbox line and a task share, used to tell which side
+/// changed since the last sync.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct LineState {
+    completed: bool,
+    title: String,
+    priority: Priority,
+    due: Option<NaiveDate>,
+    tags: Vec<String>,
+}
+
+impl LineState {
+    fn of_task(task: &Task) -> Self {
+        LineState {
+            completed: task.completed,
+            title: task.title.split_whitespace().collect::<Vec<_>>().join(" "),
+            priority: task.priority,
+            due: task.due_date.map(due_day),
+            tags: task.tags.iter().map(|t| tag_name(t)).collect(),
+        }
+    }
+}
+
+/// What a vault sync changed on each side.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarkdownSyncReport {
+    pub path: String,
+    pub files_scanned: usize,
+    pub files_written: usize,
+    /// Tasks created or updated from the notes
+    pub summary: ImportSummary,
+    /// Tasks moved to the trash because their line was removed from a note
+    pub tasks_deleted: usize,
+    pub lines_updated: usize,
+    pub lines_removed: usize,
+    /// Tasks changed on both sides since the last sync, and which side won
+    pub conflicts: Vec<String>,
+}
+
+impl MarkdownSyncReport {
+    /// Whether the sync changed anything in the app.
+    pub fn changed_app(&self) -> bool {
+        !self.summary.task_ids.is_empty() || self.tasks_deleted > 0
+    }
+}
+
+/// A note read from the vault, split into lines that keep their endings.
+struct Note {
+    path: PathBuf,
+    original: String,
+    lines: Vec<String>,
+    modified: Option<NaiveDateTime>,
+}
+
+/// A checkbox found in a note.
+struct Entry {
+    note: usize,
+    line: usize,
+    checkbox: Checkbox,
+    id: String,
+    /// The closest less-indented checkbox above it in the same list
+    parent: Option<usize>,
+    removed: bool,
+}
+
+/// Two-way sync between the checkbox lines of every `.md` file under `root`
+/// (hidden folders such as `.obsidian` are skipped) and Pluto tasks.
+///
+/// - every checkbox becomes a task, nested under the checkbox it is
+///   indented beneath; new lines get the task ID appended as a block ID
+/// - the title, completion, priority (⏫/🔼/🔽), `📅` due date and `#tags`
+///   sync both ways; the note's indentation always decides the parent
+/// - a line changed only in the note updates its task and a task changed
+///   only in the app rewrites its line; when both changed the more recently
+///   modified side wins and the conflict is reported
+/// - a line removed from its note moves the task to the trash, and a task
+///   deleted in the app has its line removed
+///
+/// Only tasks that came from the vault are tracked; other tasks are never
+/// written to a note. App changes are recorded as one undoable operation.
+pub fn sync_vault(conn: &Connection, root: &Path) -> Result<MarkdownSyncReport, AppError> {
+    let settings = SettingsRepository::new(conn);
+    let journal = Journal::new(conn);
+
+    if !root.is_dir() {
+        return Err(AppError::InvalidInput(format!("{} is not a folder", root.display())));
+    }
+
+    let mut paths = Vec::new();
+    collect_notes(root, &mut paths)?;
+    paths.sort();
+    let mut notes = Vec::new();
+    for path in paths {
+        let text = std::fs::read_to_string(&path)?;
+        let modified = std::fs::metadata(&path)?.modified().ok().map(|m| DateTime::<Utc>::from(m).naive_utc());
+        notes.push(Note {
+            lines: text.split_inclusive('\n').map(str::to_string).collect(),
+            original: text,
+            path,
+            modified,
+        });
+    }
+
+    let base: HashMap<String, LineState> = match settings.markdown_sync_snapshot()? {
+        Some(json) => serde_json::from_str(&json).unwrap_or_default(),
+        None => HashMap::new(),
+    };
+
+    let mut report = MarkdownSyncReport {
+        path: root.display().to_string(),
+        files_scanned: notes.len(),
+        ..Default::default()
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    tx.pragma_update(None, "defer_foreign_keys", true)?;
+
+    let mut before = Vec::new();
+    let (snapshot, touched) = reconcile(conn, &mut notes, &base, &mut report, &mut before)?;
+
+    // Notes are written before committing so a failed write leaves the app
+    // and the snapshot untouched
+    for note in &notes {
+        let text: String = note.lines.concat();
+        if text != note.original {
+            std::fs::write(&note.path, text)?;
+            report.files_written += 1;
+        }
+    }
+    let json = serde_json::to_string(&snapshot).map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    settings.set_markdown_sync_snapshot(&json)?;
+
+    tx.commit()?;
+
+    let mut after = journal.capture_categories(&report.summary.category_ids)?;
+    after.extend(journal.capture_tasks(&touched)?);
+    journal.record("Sync Markdown vault", before, after)?;
+
+    Ok(report)
+}
+
+/// Sync with the vault chosen in settings; `None` when sync is turned off.
+pub fn sync_configured(conn: &Connection) -> Result<Option<MarkdownSyncReport>, AppError> {
+    match SettingsRepository::new(conn).markdown_vault_path()? {
+        Some(path) => Ok(Some(sync_vault(conn, Path::new(&path))?)),
+        None => Ok(None),
+    }
+}
+
+/// Merge the notes with the app, write the app side and rewrite the lines of
+/// `notes` in place. Returns the new snapshot and the tasks written, whose
+/// earlier state is added to `before`.
+fn reconcile(
+    conn: &Connection,
+    notes: &mut [Note],
+    base: &HashMap<String, LineState>,
+    report: &mut MarkdownSyncReport,
+    before: &mut Vec<EntityState>,
+) -> Result<(HashMap<String, LineState>, Vec<String>), AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let mut tasks: HashMap<String, Task> = task_repo.get_all(None)?.into_iter().map(|t| (t.id.clone(), t)).collect();
+
+    let mut entries = scan_entries(notes);
+    let mut created = Vec::new();
+    let mut changed = HashSet::new();
+    let mut seen = HashSet::new();
+
+    for entry in entries.iter_mut() {
+        let file_state = entry.checkbox.state();
+        let modified = notes[entry.note].modified;
+
+        let block_id = entry.checkbox.block_id.clone();
+        let duplicate = block_id.as_ref().map(|id| !seen.insert(id.clone())).unwrap_or(false);
+        if duplicate {
+            report.summary.warnings.push(format!(
+                "{}: '{}' repeats another line's block ID and was given its own task",
+                notes[entry.note].path.display(),
+                entry.checkbox.title
+            ));
+        }
+
+        let id = match block_id.filter(|_| !duplicate) {
+            Some(id) => id,
+            None => {
+                entry.id = create_from_line(&mut tasks, &mut created, &entry.checkbox, uuid::Uuid::new_v4().to_string());
+                seen.insert(entry.id.clone());
+                continue;
+            }
+        };
+        entry.id = id.clone();
+        let base_state = base.get(&id);
+
+        if let Some(task) = tasks.get_mut(&id) {
+            let app_state = LineState::of_task(task);
+            if file_state == app_state {
+                continue;
+            }
+
+            let file_changed = base_state != Some(&file_state);
+            let app_changed = base_state != Some(&app_state);
+            let file_wins = match (file_changed, app_changed) {
+                (true, false) => true,
+                (false, _) => false,
+                (true, true) => {
+                    let wins = modified.map(|m| m > task.updated_at).unwrap_or(false);
+                    report.conflicts.push(format!(
+                        "'{}' changed in both places; kept the {} version",
+                        task.title,
+                        if wins { "note's" } else { "app's" }
+                    ));
+                    wins
+                }
+            };
+            if file_wins {
+                apply_checkbox(&entry.checkbox, task);
+                changed.insert(id);
+            }
+            continue;
+        }
+
+        // The task is not in the app (any more)
+        match base_state {
+            Some(base_state) if *base_state == file_state => {
+                entry.removed = true;
+                report.lines_removed += 1;
+                continue;
+            }
+            Some(_) => {
+                report.conflicts.push(format!("'{}' was deleted in the app but edited in a note; kept the edit", entry.checkbox.title));
+            }
+            None => {}
+        }
+
+        // A trashed task keeps its ID, so the line comes back as a new task
+        let new_id = if task_repo.row_exists(&id)? { uuid::Uuid::new_v4().to_string() } else { id };
+        entry.id = create_from_line(&mut tasks, &mut created, &entry.checkbox, new_id);
+        seen.insert(entry.id.clone());
+    }
+
+    // Tracked tasks whose line has gone
+    let mut trashed = Vec::new();
+    for (id, base_state) in base {
+        if seen.contains(id) {
+            continue;
+        }
+        if let Some(task) = tasks.get(id) {
+            if LineState::of_task(task) == *base_state {
+                trashed.push(id.clone());
+            } else {
+                report.conflicts.push(format!("'{}' was removed from its note but changed in the app; kept it in the app only", task.title));
+            }
+        }
+    }
+    for id in &trashed {
+        tasks.remove(id);
+    }
+
+    // The note decides the hierarchy; removed lines hand their children up
+    for index in 0..entries.len() {
+        if entries[index].removed {
+            continue;
+        }
+        let mut parent = entries[index].parent;
+        while let Some(p) = parent.filter(|&p| entries[p].removed) {
+            parent = entries[p].parent;
+        }
+        let parent_id = parent.map(|p| entries[p].id.clone());
+        let task = tasks.get_mut(&entries[index].id).expect("every kept line has a task");
+        if task.parent_id != parent_id {
+            task.parent_id = parent_id;
+            task.updated_at = Utc::now().naive_utc();
+            if !created.contains(&task.id) {
+                changed.insert(task.id.clone());
+            }
+        }
+    }
+
+    // Only the tasks written below change, along with the subtasks that
+    // trashing a task moves up a level
+    let mut touched: Vec<String> = changed.iter().chain(&trashed).cloned().collect();
+    for id in &trashed {
+        touched.extend(task_repo.get_direct_subtasks(id)?.into_iter().map(|t| t.id));
+    }
+    before.extend(Journal::new(conn).capture_tasks(&touched)?);
+    touched.extend(created.iter().cloned());
+
+    for id in &created {
+        task_repo.create(&tasks[id])?;
+        report.summary.tasks_created += 1;
+        report.summary.task_ids.push(id.clone());
+    }
+    for id in &changed {
+        task_repo.update(&tasks[id])?;
+        report.summary.tasks_updated += 1;
+        report.summary.task_ids.push(id.clone());
+    }
+    for id in &trashed {
+        task_repo.delete(id)?;
+        report.tasks_deleted += 1;
+    }
+
+    // Rewrite lines back to front so removals do not shift later indexes
+    let mut snapshot = HashMap::new();
+    for entry in entries.iter().rev() {
+        let lines = &mut notes[entry.note].lines;
+        if entry.removed {
+            lines.remove(entry.line);
+            continue;
+        }
+        let task = &tasks[&entry.id];
+        let original = &lines[entry.line];
+        let ending = &original[original.trim_end_matches(['\r', '\n']).len()..];
+        let line = entry.checkbox.render(task, ending);
+        if line != *original {
+            report.lines_updated += 1;
+            lines[entry.line] = line;
+        }
+        snapshot.insert(entry.id.clone(), LineState::of_task(task));
+    }
+
+    Ok((snapshot, touched))
+}
+
+/// Every checkbox in the notes, with the checkbox it is nested under.
+/// Checkboxes inside fenced code blocks are ignored.
+fn scan_entries(notes: &[Note]) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for (note_index, note) in notes.iter().enumerate() {
+        let mut in_code = false;
+        // Indentation and entry index of the open checkboxes above this line
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        for (line_index, line) in note.lines.iter().enumerate() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_code = !in_code;
+                stack.clear();
+                continue;
+            }
+            if in_code {
+                continue;
+            }
+            let checkbox = match Checkbox::parse(line) {
+                Some(checkbox) => checkbox,
+                None => {
+                    // Anything but a blank or indented line ends the list
+                    if !trimmed.is_empty() && trimmed.len() == line.len() {
+                        stack.clear();
+                    }
+                    continue;
+                }
+            };
+            while stack.last().map(|&(indent, _)| indent >= checkbox.indent).unwrap_or(false) {
+                stack.pop();
+            }
+            let parent = stack.last().map(|&(_, entry)| entry);
+            stack.push((checkbox.indent, entries.len()));
+            entries.push(Entry {
+                note: note_index,
+                line: line_index,
+                checkbox,
+                id: String::new(),
+                parent,
+                removed: false,
+            });
+        }
+    }
+    entries
+}
+
+fn create_from_line(tasks: &mut HashMap<String, Task>, created: &mut Vec<String>, checkbox: &Checkbox, id: String) -> String {
+    let now = Utc::now().naive_utc();
+    let mut task = Task {
+        id: id.clone(),
+        title: String::new(),
+        description: None,
+        completed: false,
+        priority: Priority::Medium,
+        due_date: None,
+        category_id: None,
+        tags: Vec::new(),
+        parent_id: None,
+        recurrence: None,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+        position: Task::initial_position(now, false),
+    };
+    apply_checkbox(checkbox, &mut task);
+    tasks.insert(id.clone(), task);
+    created.push(id.clone());
+    id
+}
+
+/// Write the fields a checkbox line carries onto `task`, keeping the time
+/// of a due date that is still on the same day.
+fn apply_checkbox(checkbox: &Checkbox, task: &mut Task) {
+    task.title = if checkbox.title.is_empty() { "Untitled".to_string() } else { checkbox.title.clone() };
+    task.completed = checkbox.completed();
+    task.priority = checkbox.priority;
+    task.due_date = match checkbox.due {
+        Some(day) if task.due_date.map(due_day) == Some(day) => task.due_date,
+        Some(day) => Some(day.and_time(NaiveTime::MIN)),
+        None => None,
+    };
+    let old_tags = std::mem::take(&mut task.tags);
+    for name in &checkbox.tags {
+        let tag = old_tags.iter().find(|t| tag_name(t) == *name).cloned().unwrap_or_else(|| name.clone());
+        if !task.tags.contains(&tag) {
+            task.tags.push(tag);
+        }
+    }
+    task.updated_at = Utc::now().naive_utc();
+}
+
+fn collect_notes(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            collect_notes(&path, paths)?;
+        } else if path.extension().map(|e| e.eq_ignore_ascii_case("md")).unwrap_or(false) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `x` is done; Obsidian's `-` (cancelled) counts as done too.
+fn is_done_status(status: char) -> bool {
+    matches!(status, 'x' | 'X' | '-')
+}
+
+fn is_block_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Obsidian tags need at least one non-digit and cannot contain spaces or
+/// most punctuation.
+fn is_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+        && !tag.chars().all(|c| c.is_ascii_digit())
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.split_whitespace().collect::<Vec<_>>().join("-")
+}
@@ -0,0 +1,333 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Weekday};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use crate::interop::services::{parse_service_date, ServiceImportReport, ServiceTask};
+use crate::models::{Frequency, Priority, RecurrenceRule, ReminderOffset};
+use crate::utils::AppError;
+
+pub const MICROSOFT_TODO_SOURCE: &str = "Microsoft To Do";
+
+/// Graph collections come either as a bare array or wrapped in `value`
+fn deserialize_collection<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    let value = Option::<Value>::deserialize(deserializer)?.unwrap_or(Value::Null);
+    let items = match value {
+        Value::Object(mut map) => map.remove("value").unwrap_or(Value::Null),
+        other => other,
+    };
+    match items {
+        Value::Null => Ok(Vec::new()),
+        items => serde_json::from_value(items).map_err(serde::de::Error::custom),
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TodoList {
+    #[serde(alias = "name", alias = "title")]
+    display_name: String,
+    #[serde(default, deserialize_with = "deserialize_collection")]
+    tasks: Vec<TodoTask>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphDateTime {
+    date_time: String,
+    #[serde(default)]
+    time_zone: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemBody {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    content_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChecklistItem {
+    #[serde(default)]
+    id: Option<String>,
+    display_name: String,
+    #[serde(default)]
+    is_checked: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecurrencePattern {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    interval: Option<u32>,
+    #[serde(default)]
+    days_of_week: Vec<String>,
+    #[serde(default)]
+    day_of_month: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecurrenceRange {
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    #[serde(default)]
+    end_date: Option<String>,
+    #[serde(default)]
+    number_of_occurrences: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PatternedRecurrence {
+    pattern: RecurrencePattern,
+    #[serde(default)]
+    range: Option<RecurrenceRange>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TodoTask {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    body: Option<ItemBody>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    importance: Option<String>,
+    #[serde(default)]
+    due_date_time: Option<GraphDateTime>,
+    #[serde(default)]
+    reminder_date_time: Option<GraphDateTime>,
+    #[serde(default)]
+    is_reminder_on: bool,
+    #[serde(default)]
+    created_date_time: Option<String>,
+    #[serde(default)]
+    categories: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_collection")]
+    checklist_items: Vec<ChecklistItem>,
+    #[serde(default)]
+    recurrence: Option<PatternedRecurrence>,
+    #[serde(default, deserialize_with = "deserialize_collection")]
+    linked_resources: Vec<Value>,
+    #[serde(default)]
+    has_attachments: bool,
+}
+
+/// Parse lists exported from Microsoft To Do in the shape of the Graph API:
+/// an array of lists (or `{"value": [...]}`, or a single list), each with
+/// its `tasks`. Checklist steps become subtasks.
+pub fn parse_microsoft_todo(text: &str, report: &mut ServiceImportReport) -> Result<Vec<ServiceTask>, AppError> {
+    let value: Value = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+        .map_err(|e| AppError::InvalidInput(format!("Not a Microsoft To Do export: {}", e)))?;
+    let lists = match value {
+        Value::Object(mut map) if !map.contains_key("tasks") => {
+            map.remove("value").or_else(|| map.remove("lists")).unwrap_or(Value::Null)
+        }
+        Value::Object(map) => Value::Array(vec![Value::Object(map)]),
+        other => other,
+    };
+    let lists: Vec<TodoList> = serde_json::from_value(lists)
+        .map_err(|e| AppError::InvalidInput(format!("Not a Microsoft To Do export: {}", e)))?;
+
+    let mut tasks = Vec::new();
+    for (list_index, list) in lists.into_iter().enumerate() {
+        for (task_index, item) in list.tasks.into_iter().enumerate() {
+            let source_id = item
+                .id
+                .clone()
+                .unwrap_or_else(|| format!("{}:{}", list_index, task_index));
+            let task = convert_task(item, source_id, &list.display_name, report, &mut tasks);
+            tasks.push(task);
+        }
+    }
+    Ok(tasks)
+}
+
+/// Convert one task; its checklist steps are pushed onto `steps`
+fn convert_task(
+    item: TodoTask,
+    source_id: String,
+    list: &str,
+    report: &mut ServiceImportReport,
+    steps: &mut Vec<ServiceTask>,
+) -> ServiceTask {
+    let status = item.status.as_deref().unwrap_or("notStarted");
+    if !matches!(status, "notStarted" | "completed") {
+        report.lossy("status", "Statuses other than not started and completed become open tasks");
+    }
+
+    let description = item.body.as_ref().map(|body| {
+        if body.content_type.as_deref().is_some_and(|t| t.eq_ignore_ascii_case("html")) {
+            let text = strip_html(&body.content);
+            if text.trim() != body.content.trim() {
+                report.lossy("formatting", "Formatted notes are imported as plain text");
+            }
+            text
+        } else {
+            body.content.clone()
+        }
+    });
+
+    // To Do only has due days; Graph writes them as midnight in some zone
+    let due_date = item
+        .due_date_time
+        .as_ref()
+        .and_then(|due| due.date_time.get(..10))
+        .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+        .map(|day| day.and_time(NaiveTime::MIN));
+
+    let mut task = ServiceTask {
+        source_id: source_id.clone(),
+        list: Some(list.to_string()),
+        title: item.title.clone(),
+        description,
+        completed: status == "completed",
+        priority: match item.importance.as_deref() {
+            Some("high") => Priority::High,
+            Some("low") => Priority::Low,
+            _ => Priority::Medium,
+        },
+        due_date,
+        tags: item.categories.clone(),
+        created_at: item.created_date_time.as_deref().and_then(|d| parse_service_date(d, false)),
+        ..Default::default()
+    };
+
+    if let Some(recurrence) = &item.recurrence {
+        task.recurrence = convert_recurrence(recurrence, report);
+    }
+
+    if item.is_reminder_on {
+        match (item.reminder_date_time.as_ref().and_then(graph_date_time), due_date) {
+            (Some(at), Some(due)) if local(at).date() == due.date() => {
+                let at = local(at);
+                task.reminders.push(ReminderOffset::OnDueDay { hour: at.hour(), minute: at.minute() });
+            }
+            _ => report.lossy("reminder", "Only reminders on the due day are imported"),
+        }
+    }
+    if !item.linked_resources.is_empty() {
+        report.lossy("linked resource", "Links to emails and other apps are not imported");
+    }
+    if item.has_attachments {
+        report.lossy("attachment", "Attachments are not imported");
+    }
+
+    for (index, step) in item.checklist_items.into_iter().enumerate() {
+        steps.push(ServiceTask {
+            source_id: step.id.unwrap_or_else(|| format!("{}:step{}", source_id, index)),
+            parent: Some(source_id.clone()),
+            list: Some(list.to_string()),
+            title: step.display_name,
+            completed: step.is_checked,
+            ..Default::default()
+        });
+    }
+
+    task
+}
+
+/// A Graph date-time as UTC. Zones other than UTC are Windows zone names,
+/// which are read as the local zone.
+fn graph_date_time(value: &GraphDateTime) -> Option<NaiveDateTime> {
+    let utc = value.time_zone.as_deref().is_none_or(|zone| zone.eq_ignore_ascii_case("UTC"));
+    let text = value.date_time.trim().trim_end_matches('Z');
+    let text = format!("{}{}", text, if utc { "Z" } else { "" });
+    parse_service_date(&text, false)
+}
+
+fn local(utc: NaiveDateTime) -> NaiveDateTime {
+    Local.from_utc_datetime(&utc).naive_local()
+}
+
+fn convert_recurrence(recurrence: &PatternedRecurrence, report: &mut ServiceImportReport) -> Option<RecurrenceRule> {
+    let pattern = &recurrence.pattern;
+    let mut rule = match pattern.kind.as_str() {
+        "daily" => RecurrenceRule::new(Frequency::Daily),
+        "weekly" => {
+            let mut rule = RecurrenceRule::new(Frequency::Weekly);
+            rule.by_weekday = pattern.days_of_week.iter().filter_map(|d| d.parse::<Weekday>().ok()).collect();
+            rule
+        }
+        "absoluteMonthly" => {
+            let mut rule = RecurrenceRule::new(Frequency::Monthly);
+            rule.by_month_day = pattern.day_of_month;
+            rule
+        }
+        "absoluteYearly" => RecurrenceRule::new(Frequency::Yearly),
+        "relativeMonthly" | "relativeYearly" => {
+            report.lossy(
+                "recurrence",
+                "Repeats on a weekday of the month (e.g. the first Monday) repeat on the same date instead",
+            );
+            RecurrenceRule::new(if pattern.kind == "relativeMonthly" { Frequency::Monthly } else { Frequency::Yearly })
+        }
+        other => {
+            report.lossy("recurrence", &format!("Unknown repeat pattern '{}' was dropped", other));
+            return None;
+        }
+    };
+    rule.interval = pattern.interval.unwrap_or(1).max(1);
+
+    if let Some(range) = &recurrence.range {
+        match range.kind.as_deref() {
+            Some("endDate") => {
+                rule.until = range
+                    .end_date
+                    .as_deref()
+                    .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                    .map(|d| d.and_time(NaiveTime::MIN));
+            }
+            Some("numbered") => rule.count = range.number_of_occurrences,
+            _ => {}
+        }
+    }
+    Some(rule)
+}
+
+/// The text of an HTML note, one paragraph per line
+fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    let mut tag = String::new();
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                tag.clear();
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                let name = tag.trim_start_matches('/').split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+                if matches!(name.as_str(), "br" | "br/" | "p" | "div" | "li") && !text.ends_with('\n') && !text.is_empty() {
+                    text.push('\n');
+                }
+            }
+            _ if in_tag => tag.push(c),
+            _ => text.push(c),
+        }
+    }
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
@@ -0,0 +1,134 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::database::{CategoryRepository, TaskRepository};
+use crate::models::{Category, CreateCategoryRequest, Task};
+
+pub mod backup;
+pub mod ical;
+pub mod csv;
+pub mod todotxt;
+pub mod markdown;
+pub mod org;
+pub mod services;
+pub mod todoist;
+pub mod microsoft_todo;
+pub mod google_tasks;
+pub mod caldav_client;
+pub mod caldav;
+
+/// Colour given to categories that an import has to create
+pub const IMPORTED_CATEGORY_COLOR: &str = "#6B7280";
+
+/// What an export wrote.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportSummary {
+    pub tasks: usize,
+    pub categories: usize,
+}
+
+/// What an import changed. `task_ids` and `category_ids` list the rows that
+/// were created or updated, so the caller can record them for undo or
+/// select them in the UI.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub tasks_created: usize,
+    pub tasks_updated: usize,
+    pub categories_created: usize,
+    /// Categories in the file that were matched to existing ones by ID or name
+    pub categories_matched: usize,
+    pub reminders_created: usize,
+    pub dependencies_created: usize,
+    /// Rows given a new ID because theirs was already taken
+    pub remapped_ids: usize,
+    pub task_ids: Vec<String>,
+    pub category_ids: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl ImportSummary {
+    /// The ID of the category called `name` (case-insensitive), creating it
+    /// if there is none yet. `categories` is the caller's cache of existing
+    /// categories and is kept up to date.
+    pub fn find_or_create_category(
+        &mut self,
+        conn: &Connection,
+        categories: &mut Vec<Category>,
+        name: &str,
+    ) -> rusqlite::Result<String> {
+        if let Some(category) = categories.iter().find(|c| c.name.eq_ignore_ascii_case(name)) {
+            return Ok(category.id.clone());
+        }
+
+        let category = Category::new(CreateCategoryRequest {
+            name: name.to_string(),
+            color: IMPORTED_CATEGORY_COLOR.to_string(),
+            icon: None,
+        });
+        CategoryRepository::new(conn).create(&category)?;
+
+        self.categories_created += 1;
+        self.category_ids.push(category.id.clone());
+        categories.push(category.clone());
+        Ok(category.id)
+    }
+}
+/// The calendar day a due date falls on: date-only values are stored at
+/// midnight UTC, timed ones are shown in local time.
+pub(crate) fn due_day(due: NaiveDateTime) -> NaiveDate {
+    if due.time() == NaiveTime::MIN {
+        due.date()
+    } else {
+        local_day(due)
+    }
+}
+
+pub(crate) fn local_day(utc: NaiveDateTime) -> NaiveDate {
+    Local.from_utc_datetime(&utc).date_naive()
+}
+
+/// Make each imported task whose chain of parents would lead back to it a
+/// top-level task, with a warning. Parents are looked up among `tasks`
+/// first and in the database after, since an import can point at tasks
+/// that are already there.
+pub(crate) fn break_parent_cycles<'t>(
+    conn: &Connection,
+    tasks: impl IntoIterator<Item = &'t mut Task>,
+    warnings: &mut Vec<String>,
+) -> rusqlite::Result<()> {
+    let task_repo = TaskRepository::new(conn);
+    let mut tasks: Vec<&mut Task> = tasks.into_iter().collect();
+    let mut parents: HashMap<String, Option<String>> =
+        tasks.iter().map(|t| (t.id.clone(), t.parent_id.clone())).collect();
+
+    for task in tasks.iter_mut() {
+        let parent_id = match task.parent_id.clone() {
+            Some(parent_id) => parent_id,
+            None => continue,
+        };
+
+        // An ancestor seen twice is a loop above the task, which is no
+        // better a place to hang it
+        let mut seen = HashSet::new();
+        let mut current = Some(parent_id.clone());
+        let mut cycle = false;
+        while let Some(ancestor) = current {
+            if ancestor == task.id || !seen.insert(ancestor.clone()) {
+                cycle = true;
+                break;
+            }
+            current = match parents.get(&ancestor) {
+                Some(parent) => parent.clone(),
+                None => task_repo.get_by_id(&ancestor)?.and_then(|t| t.parent_id),
+            };
+        }
+
+        if cycle {
+            warnings.push(format!("'{}': parent {} would make it its own subtask, imported as a top-level task", task.title, parent_id));
+            task.parent_id = None;
+            parents.insert(task.id.clone(), None);
+        }
+    }
+    Ok(())
+}
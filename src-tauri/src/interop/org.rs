@@ -0,0 +1,483 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
+use crate::database::{CategoryRepository, TaskRepository};
+use crate::interop::{local_day, ImportSummary};
+use crate::models::{Category, Frequency, Priority, RecurrenceRule, Task};
+use crate::utils::AppError;
+
+/// Property holding the task ID, so a re-import updates instead of duplicating
+const ID_PROPERTY: &str = "PLUTO_ID";
+/// Keywords that mark a headline as an open task
+const OPEN_KEYWORDS: &[&str] = &["TODO", "NEXT", "STARTED", "WAITING", "HOLD"];
+/// Keywords that mark a headline as a finished task
+const DONE_KEYWORDS: &[&str] = &["DONE", "CANCELLED", "CANCELED"];
+
+/// An active or inactive Org timestamp such as `<2026-10-20 Tue 09:30 +1w>`.
+/// A time is in local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrgTimestamp {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+    /// Repeater interval and unit (`d`, `w`, `m`, `y`)
+    pub repeater: Option<(u32, char)>,
+}
+
+impl OrgTimestamp {
+    fn parse(text: &str) -> Option<Self> {
+        let inner = text.trim().trim_start_matches(['<', '[']).trim_end_matches(['>', ']']);
+        let mut parts = inner.split_whitespace();
+        let date = NaiveDate::parse_from_str(parts.next()?, "%Y-%m-%d").ok()?;
+        let mut stamp = OrgTimestamp { date, time: None, repeater: None };
+
+        for part in parts {
+            if let Ok(time) = NaiveTime::parse_from_str(part, "%H:%M") {
+                stamp.time = Some(time);
+            } else if let Some(repeater) = part.strip_prefix(".+").or_else(|| part.strip_prefix("++")).or_else(|| part.strip_prefix('+')) {
+                let unit = repeater.chars().last()?;
+                let count = repeater[..repeater.len() - unit.len_utf8()].parse().ok()?;
+                stamp.repeater = Some((count, unit));
+            }
+            // Day names and warning periods (`-2d`) carry nothing we keep
+        }
+        Some(stamp)
+    }
+
+    /// Stored form of the date: midnight UTC without a time, the UTC instant
+    /// of the local time otherwise.
+    fn to_utc(self) -> NaiveDateTime {
+        match self.time {
+            None => self.date.and_time(NaiveTime::MIN),
+            Some(time) => Local
+                .from_local_datetime(&self.date.and_time(time))
+                .earliest()
+                .map(|local| local.naive_utc())
+                .unwrap_or_else(|| self.date.and_time(time)),
+        }
+    }
+
+    fn recurrence(self) -> Option<RecurrenceRule> {
+        let (count, unit) = self.repeater?;
+        let frequency = match unit {
+            'd' => Frequency::Daily,
+            'w' => Frequency::Weekly,
+            'm' => Frequency::Monthly,
+            'y' => Frequency::Yearly,
+            _ => return None,
+        };
+        let mut rule = RecurrenceRule::new(frequency);
+        rule.interval = count.max(1);
+        Some(rule)
+    }
+}
+
+/// A headline with the planning line, properties and body text under it.
+#[derive(Debug, Clone, Default)]
+pub struct OrgHeadline {
+    pub level: usize,
+    pub keyword: Option<String>,
+    pub priority: Option<char>,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub deadline: Option<OrgTimestamp>,
+    pub scheduled: Option<OrgTimestamp>,
+    pub properties: Vec<(String, String)>,
+    pub body: Vec<String>,
+}
+
+impl OrgHeadline {
+    fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Whether the headline has a TODO-type keyword; other headlines are
+    /// structure, not tasks.
+    pub fn is_task(&self) -> bool {
+        self.keyword.is_some()
+    }
+
+    pub fn id(&self) -> Option<String> {
+        self.property(ID_PROPERTY).map(str::to_string)
+    }
+}
+
+/// Render tasks as an Org document. Subtasks nest under their parent (a
+/// subtask whose parent is not exported becomes a top-level headline).
+///
+/// - TODO/DONE keywords follow completion, with a CLOSED date when done
+/// - priority maps to `[#A]` (high) and `[#C]` (low); medium is Org's
+///   default `[#B]` and is left out
+/// - the due date is the DEADLINE, with a repeater for simple recurrences;
+///   other rules are kept in an RRULE property
+/// - the category goes in the CATEGORY property and the ID in PLUTO_ID
+/// - the description is the headline's body
+pub fn export_org(tasks: &[Task], categories: &[Category]) -> String {
+    let ids: HashSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+    let mut children: HashMap<Option<&str>, Vec<&Task>> = HashMap::new();
+    for task in tasks {
+        let parent = task.parent_id.as_deref().filter(|p| ids.contains(p));
+        children.entry(parent).or_default().push(task);
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|t| t.created_at);
+    }
+
+    let mut out = String::from("#+TITLE: Pluto tasks\n#+TODO: TODO | DONE\n\n");
+    let mut stack: Vec<(&Task, usize)> = children.get(&None).map(|roots| roots.iter().rev().map(|t| (*t, 1)).collect()).unwrap_or_default();
+    while let Some((task, level)) = stack.pop() {
+        write_headline(&mut out, task, level, categories);
+        if let Some(subtasks) = children.get(&Some(task.id.as_str())) {
+            stack.extend(subtasks.iter().rev().map(|t| (*t, level + 1)));
+        }
+    }
+    out
+}
+
+fn write_headline(out: &mut String, task: &Task, level: usize, categories: &[Category]) {
+    let indent = " ".repeat(level + 1);
+
+    let mut headline = format!("{} {}", "*".repeat(level), if task.completed { "DONE" } else { "TODO" });
+    match task.priority {
+        Priority::High => headline.push_str(" [#A]"),
+        Priority::Medium => {}
+        Priority::Low => headline.push_str(" [#C]"),
+    }
+    headline.push(' ');
+    headline.push_str(&task.title.split_whitespace().collect::<Vec<_>>().join(" "));
+    let tags: Vec<String> = task.tags.iter().map(|t| tag_name(t)).filter(|t| !t.is_empty()).collect();
+    if !tags.is_empty() {
+        headline.push_str(&format!(" :{}:", tags.join(":")));
+    }
+    out.push_str(&headline);
+    out.push('\n');
+
+    let mut planning = Vec::new();
+    if task.completed {
+        planning.push(format!("CLOSED: [{}]", format_local(task.updated_at)));
+    }
+    let simple_rule = task.recurrence.as_ref().and_then(repeater);
+    if let Some(due) = task.due_date {
+        let stamp = if due.time() == NaiveTime::MIN {
+            let date = due.date();
+            format!("{} {}", date.format("%Y-%m-%d"), date.format("%a"))
+        } else {
+            format_local(due)
+        };
+        match simple_rule {
+            Some(ref repeater) => planning.push(format!("DEADLINE: <{} {}>", stamp, repeater)),
+            None => planning.push(format!("DEADLINE: <{}>", stamp)),
+        }
+    }
+    if !planning.is_empty() {
+        out.push_str(&format!("{}{}\n", indent, planning.join(" ")));
+    }
+
+    out.push_str(&format!("{}:PROPERTIES:\n", indent));
+    out.push_str(&format!("{}:{}: {}\n", indent, ID_PROPERTY, task.id));
+    if let Some(category) = task.category_id.as_ref().and_then(|id| categories.iter().find(|c| &c.id == id)) {
+        out.push_str(&format!("{}:CATEGORY: {}\n", indent, category.name));
+    }
+    out.push_str(&format!("{}:CREATED: [{}]\n", indent, format_local(task.created_at)));
+    if let Some(ref rule) = task.recurrence {
+        // A repeater needs a deadline to hang off, and only covers plain intervals
+        if simple_rule.is_none() || task.due_date.is_none() {
+            out.push_str(&format!("{}:RRULE: {}\n", indent, rule));
+        }
+    }
+    out.push_str(&format!("{}:END:\n", indent));
+
+    if let Some(ref description) = task.description {
+        for line in description.lines() {
+            if line.trim().is_empty() {
+                out.push('\n');
+            } else {
+                out.push_str(&format!("{}{}\n", indent, line));
+            }
+        }
+    }
+}
+
+/// Read every headline of an Org document.
+pub fn parse_org(text: &str) -> Vec<OrgHeadline> {
+    let mut headlines: Vec<OrgHeadline> = Vec::new();
+    let mut in_drawer = false;
+    let mut in_properties = false;
+    let mut planning_allowed = false;
+
+    for line in text.lines() {
+        if let Some(headline) = parse_headline(line) {
+            headlines.push(headline);
+            in_drawer = false;
+            in_properties = false;
+            planning_allowed = true;
+            continue;
+        }
+        let current = match headlines.last_mut() {
+            Some(current) => current,
+            // File-level settings and text before the first headline
+            None => continue,
+        };
+        let trimmed = line.trim();
+
+        if planning_allowed && is_planning_line(trimmed) {
+            for (keyword, stamp) in planning_entries(trimmed) {
+                match keyword {
+                    "DEADLINE" => current.deadline = OrgTimestamp::parse(stamp),
+                    "SCHEDULED" => current.scheduled = OrgTimestamp::parse(stamp),
+                    _ => {}
+                }
+            }
+            planning_allowed = false;
+            continue;
+        }
+        planning_allowed = false;
+
+        if in_drawer {
+            if trimmed.eq_ignore_ascii_case(":END:") {
+                in_drawer = false;
+                in_properties = false;
+            } else if in_properties {
+                if let Some((key, value)) = trimmed.strip_prefix(':').and_then(|rest| rest.split_once(':')) {
+                    current.properties.push((key.to_string(), value.trim().to_string()));
+                }
+            }
+            continue;
+        }
+        if trimmed.len() > 2 && trimmed.starts_with(':') && trimmed.ends_with(':') && !trimmed[1..trimmed.len() - 1].contains([' ', ':']) {
+            in_drawer = true;
+            in_properties = trimmed.eq_ignore_ascii_case(":PROPERTIES:");
+            continue;
+        }
+
+        current.body.push(line.to_string());
+    }
+
+    headlines
+}
+
+fn parse_headline(line: &str) -> Option<OrgHeadline> {
+    let level = line.chars().take_while(|&c| c == '*').count();
+    if level == 0 {
+        return None;
+    }
+    let rest = line[level..].strip_prefix(' ')?;
+
+    let mut headline = OrgHeadline { level, ..Default::default() };
+    let mut words: Vec<&str> = rest.split_whitespace().collect();
+
+    if let Some(keyword) = words.first().filter(|w| OPEN_KEYWORDS.contains(w) || DONE_KEYWORDS.contains(w)) {
+        headline.keyword = Some(keyword.to_string());
+        words.remove(0);
+    }
+    if let Some(letter) = words.first().and_then(|w| w.strip_prefix("[#")).and_then(|w| w.strip_suffix(']')) {
+        let mut chars = letter.chars();
+        if let (Some(letter), None) = (chars.next(), chars.next()) {
+            headline.priority = Some(letter.to_ascii_uppercase());
+            words.remove(0);
+        }
+    }
+    if let Some(tags) = words.last().filter(|w| w.len() > 2 && w.starts_with(':') && w.ends_with(':')) {
+        headline.tags = tags.split(':').filter(|t| !t.is_empty()).map(str::to_string).collect();
+        words.pop();
+    }
+    headline.title = words.join(" ");
+
+    Some(headline)
+}
+
+fn is_planning_line(line: &str) -> bool {
+    ["DEADLINE:", "SCHEDULED:", "CLOSED:"].iter().any(|k| line.starts_with(k))
+}
+
+/// `DEADLINE: <…> CLOSED: […]` into (keyword, timestamp) pairs.
+fn planning_entries(line: &str) -> Vec<(&str, &str)> {
+    let mut entries = Vec::new();
+    let mut rest = line;
+    while let Some((keyword, after)) = rest.split_once(':') {
+        let after = after.trim_start();
+        let close = match after.chars().next() {
+            Some('<') => '>',
+            Some('[') => ']',
+            _ => break,
+        };
+        let end = match after.find(close) {
+            Some(end) => end + 1,
+            None => break,
+        };
+        entries.push((keyword.trim(), &after[..end]));
+        rest = &after[end..];
+    }
+    entries
+}
+
+/// Import the task headlines of an Org document in a single transaction.
+/// Headlines whose PLUTO_ID matches an existing task update it; a task's
+/// parent is the closest task headline above it at a lower level.
+pub fn import_org(conn: &Connection, headlines: &[OrgHeadline]) -> Result<ImportSummary, AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let mut categories = CategoryRepository::new(conn).get_all()?;
+    let mut summary = ImportSummary::default();
+
+    let tx = conn.unchecked_transaction()?;
+    tx.pragma_update(None, "defer_foreign_keys", true)?;
+
+    // Level and task ID of the task headlines enclosing the current one;
+    // `None` marks a skipped headline so its subtree is not misattached
+    let mut ancestors: Vec<(usize, Option<String>)> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut tasks = Vec::new();
+
+    for headline in headlines {
+        while ancestors.last().map(|(level, _)| *level >= headline.level).unwrap_or(false) {
+            ancestors.pop();
+        }
+        if !headline.is_task() {
+            continue;
+        }
+        let parent_id = ancestors.iter().rev().find_map(|(_, id)| id.clone());
+
+        if headline.title.is_empty() {
+            summary.warnings.push("Skipped a headline without a title".to_string());
+            ancestors.push((headline.level, None));
+            continue;
+        }
+        let id = headline.id().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        if !seen.insert(id.clone()) {
+            summary.warnings.push(format!("Skipped '{}': ID {} appears more than once", headline.title, id));
+            ancestors.push((headline.level, None));
+            continue;
+        }
+        let existing = task_repo.get_by_id(&id)?;
+        if existing.is_none() && task_repo.row_exists(&id)? {
+            summary.warnings.push(format!("Skipped '{}': the task is in the trash", headline.title));
+            ancestors.push((headline.level, None));
+            continue;
+        }
+        ancestors.push((headline.level, Some(id.clone())));
+
+        let now = Utc::now().naive_utc();
+        let created_at = headline.property("CREATED").and_then(OrgTimestamp::parse).map(|s| s.to_utc()).unwrap_or(now);
+        let mut task = existing.clone().unwrap_or_else(|| Task {
+            id: id.clone(),
+            title: String::new(),
+            description: None,
+            completed: false,
+            priority: Priority::Medium,
+            due_date: None,
+            category_id: None,
+            tags: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+            created_at,
+            updated_at: now,
+            deleted_at: None,
+            position: Task::initial_position(created_at, false),
+        });
+
+        task.title = headline.title.clone();
+        task.completed = headline.keyword.as_deref().map(|k| DONE_KEYWORDS.contains(&k)).unwrap_or(false);
+        task.priority = match headline.priority {
+            Some('A') => Priority::High,
+            Some('B') | None => Priority::Medium,
+            Some(_) => Priority::Low,
+        };
+        task.updated_at = now;
+        task.parent_id = parent_id;
+
+        let due = headline.deadline.or(headline.scheduled);
+        task.due_date = due.map(|s| s.to_utc());
+        task.recurrence = match headline.property("RRULE") {
+            Some(rrule) => match rrule.parse::<RecurrenceRule>() {
+                Ok(rule) => Some(rule),
+                Err(e) => {
+                    summary.warnings.push(format!("'{}': recurrence dropped ({})", task.title, e));
+                    None
+                }
+            },
+            None => due.and_then(|s| s.recurrence()),
+        };
+
+        task.category_id = match headline.property("CATEGORY") {
+            Some(name) => Some(summary.find_or_create_category(conn, &mut categories, name)?),
+            None => None,
+        };
+        let old_tags = std::mem::take(&mut task.tags);
+        for name in &headline.tags {
+            let tag = old_tags.iter().find(|t| tag_name(t) == *name).cloned().unwrap_or_else(|| name.clone());
+            if !task.tags.contains(&tag) {
+                task.tags.push(tag);
+            }
+        }
+
+        task.description = body_text(&headline.body);
+
+        tasks.push((task, existing.is_some()));
+    }
+
+    for (task, exists) in &tasks {
+        if *exists {
+            task_repo.update(task)?;
+            summary.tasks_updated += 1;
+        } else {
+            task_repo.create(task)?;
+            summary.tasks_created += 1;
+        }
+        summary.task_ids.push(task.id.clone());
+    }
+
+    tx.commit()?;
+    Ok(summary)
+}
+
+/// The body with surrounding blank lines dropped and the common
+/// indentation removed. Indentation is counted in characters, as it may
+/// include multi-byte whitespace.
+fn body_text(lines: &[String]) -> Option<String> {
+    let start = lines.iter().position(|l| !l.trim().is_empty())?;
+    let end = lines.iter().rposition(|l| !l.trim().is_empty())? + 1;
+    let lines = &lines[start..end];
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.chars().take_while(|c| c.is_whitespace()).count())
+        .min()
+        .unwrap_or(0);
+    let text: Vec<&str> = lines
+        .iter()
+        .map(|l| match l.char_indices().nth(indent) {
+            Some((i, _)) if !l.trim().is_empty() => &l[i..],
+            _ => "",
+        })
+        .collect();
+    Some(text.join("\n"))
+}
+
+/// A `+1w` style repeater for rules that are a plain interval.
+fn repeater(rule: &RecurrenceRule) -> Option<String> {
+    if !rule.by_weekday.is_empty() || rule.by_month_day.is_some() || rule.until.is_some() || rule.count.is_some() {
+        return None;
+    }
+    let unit = match rule.frequency {
+        Frequency::Daily => 'd',
+        Frequency::Weekly => 'w',
+        Frequency::Monthly => 'm',
+        Frequency::Yearly => 'y',
+    };
+    Some(format!("+{}{}", rule.interval, unit))
+}
+
+fn format_local(utc: NaiveDateTime) -> String {
+    let local = Local.from_utc_datetime(&utc);
+    format!("{} {}", local_day(utc).format("%Y-%m-%d %a"), local.format("%H:%M"))
+}
+
+/// Org tags may only hold letters, digits, `_`, `@`, `#` and `%`.
+fn tag_name(tag: &str) -> String {
+    tag.chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%') { c } else { '_' })
+        .collect()
+}
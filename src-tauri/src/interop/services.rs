@@ -0,0 +1,214 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::database::{CategoryRepository, ReminderRepository, TaskRepository};
+use crate::interop::{break_parent_cycles, ImportSummary};
+use crate::models::{Priority, QuickAddPreview, RecurrenceRule, Reminder, ReminderOffset, Task};
+use crate::utils::AppError;
+
+/// A task read from another app's export, before it is written. The
+/// per-service parsers only produce these, so they can be checked against
+/// fixture files without a database.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceTask {
+    /// The task's ID in the source app, used to resolve `parent`
+    pub source_id: String,
+    pub parent: Option<String>,
+    /// Project or list name, which becomes the category
+    pub list: Option<String>,
+    pub title: String,
+    pub description: Option<String>,
+    pub completed: bool,
+    pub priority: Priority,
+    /// Stored form: midnight UTC for a date, the UTC instant otherwise
+    pub due_date: Option<NaiveDateTime>,
+    pub tags: Vec<String>,
+    pub recurrence: Option<RecurrenceRule>,
+    pub reminders: Vec<ReminderOffset>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// An item that was not imported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedItem {
+    pub title: String,
+    pub reason: String,
+}
+
+/// Data of one kind that Pluto has no place for, and how often it occurred.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LossyField {
+    pub field: String,
+    pub count: usize,
+    pub note: String,
+}
+
+/// What importing another app's export did and left behind.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceImportReport {
+    pub source: String,
+    pub summary: ImportSummary,
+    pub skipped: Vec<SkippedItem>,
+    pub lossy: Vec<LossyField>,
+}
+
+impl ServiceImportReport {
+    pub fn new(source: &str) -> Self {
+        ServiceImportReport {
+            source: source.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn skip(&mut self, title: &str, reason: &str) {
+        self.skipped.push(SkippedItem {
+            title: title.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    /// Count one occurrence of `field` being dropped or simplified. The note
+    /// of the first occurrence describes them all.
+    pub fn lossy(&mut self, field: &str, note: &str) {
+        match self.lossy.iter_mut().find(|l| l.field == field) {
+            Some(entry) => entry.count += 1,
+            None => self.lossy.push(LossyField {
+                field: field.to_string(),
+                count: 1,
+                note: note.to_string(),
+            }),
+        }
+    }
+}
+
+/// Write parsed tasks in a single transaction. Every task gets a new ID;
+/// lists become categories (matched by name or created) and parents are
+/// resolved through the source IDs.
+pub fn import_service_tasks(
+    conn: &Connection,
+    items: Vec<ServiceTask>,
+    report: &mut ServiceImportReport,
+) -> Result<(), AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let reminder_repo = ReminderRepository::new(conn);
+    let mut categories = CategoryRepository::new(conn).get_all()?;
+
+    let tx = conn.unchecked_transaction()?;
+    // Subtasks may come before their parents
+    tx.pragma_update(None, "defer_foreign_keys", true)?;
+
+    let mut items: Vec<ServiceTask> = items
+        .into_iter()
+        .filter(|item| {
+            let keep = !item.title.trim().is_empty();
+            if !keep {
+                report.skip("(untitled)", "The task has no title");
+            }
+            keep
+        })
+        .collect();
+
+    let ids: HashMap<String, String> = items
+        .iter()
+        .filter(|item| !item.source_id.is_empty())
+        .map(|item| (item.source_id.clone(), uuid::Uuid::new_v4().to_string()))
+        .collect();
+
+    let now = Utc::now().naive_utc();
+    let mut tasks = Vec::new();
+    for item in &mut items {
+        let id = ids.get(&item.source_id).cloned().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let parent_id = match item.parent.as_ref() {
+            Some(parent) => match ids.get(parent) {
+                Some(parent_id) if *parent_id != id => Some(parent_id.clone()),
+                _ => {
+                    report.lossy("parent", "The parent task was not in the export; imported as a top-level task");
+                    None
+                }
+            },
+            None => None,
+        };
+        let category_id = match item.list.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+            Some(list) => Some(report.summary.find_or_create_category(conn, &mut categories, list)?),
+            None => None,
+        };
+
+        let mut tags: Vec<String> = Vec::new();
+        for tag in item.tags.drain(..) {
+            let tag = tag.trim().to_string();
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+
+        let created_at = item.created_at.unwrap_or(now);
+        let position = Task::initial_position(created_at, parent_id.is_some());
+        let task = Task {
+            id,
+            title: item.title.trim().to_string(),
+            description: item.description.take().filter(|d| !d.trim().is_empty()),
+            completed: item.completed,
+            priority: item.priority,
+            due_date: item.due_date,
+            category_id,
+            tags,
+            parent_id,
+            recurrence: item.recurrence.take(),
+            created_at,
+            updated_at: now,
+            deleted_at: None,
+            position,
+        };
+        tasks.push((task, std::mem::take(&mut item.reminders)));
+    }
+
+    // Parents come from the export, which may have them going round in a loop
+    break_parent_cycles(conn, tasks.iter_mut().map(|(t, _)| t), &mut report.summary.warnings)?;
+
+    for (task, reminders) in tasks {
+        task_repo.create(&task)?;
+        for offset in reminders {
+            reminder_repo.create(&Reminder::new(task.id.clone(), offset))?;
+            report.summary.reminders_created += 1;
+        }
+        report.summary.tasks_created += 1;
+        report.summary.task_ids.push(task.id);
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Read an ISO 8601 date or date-time. Values with an offset or `Z` are
+/// instants; values without one are local wall-clock times. A bare date is
+/// stored as a date, and so is midnight when `midnight_is_date` is set, for
+/// services that write date-only due dates as a full timestamp.
+pub fn parse_service_date(value: &str, midnight_is_date: bool) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date.and_time(NaiveTime::MIN));
+    }
+    if let Ok(instant) = chrono::DateTime::parse_from_rfc3339(value) {
+        let utc = instant.naive_utc();
+        if midnight_is_date && instant.naive_local().time() == NaiveTime::MIN {
+            return Some(instant.naive_local());
+        }
+        return Some(utc);
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .ok()?;
+    if midnight_is_date && local.time() == NaiveTime::MIN {
+        return Some(local);
+    }
+    Some(Local.from_local_datetime(&local).earliest().map(|l| l.naive_utc()).unwrap_or(local))
+}
+
+/// Read a due date written in words ("every monday", "Oct 20 at 5pm") the
+/// way quick add does. Returns the due date, the recurrence and whatever
+/// text was not understood.
+pub fn parse_natural_due(text: &str, now: NaiveDateTime) -> (Option<NaiveDateTime>, Option<RecurrenceRule>, String) {
+    let preview = QuickAddPreview::parse(text, now, &[]);
+    (preview.due_date, preview.recurrence, preview.title)
+}
@@ -0,0 +1,437 @@
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use crate::interop::services::{parse_natural_due, parse_service_date, ServiceImportReport, ServiceTask};
+use crate::models::Priority;
+use crate::utils::AppError;
+
+pub const TODOIST_SOURCE: &str = "Todoist";
+
+/// Read a Todoist export: a JSON backup, one project's CSV file, or a
+/// folder of CSV files (an unpacked backup archive). CSV projects are named
+/// after their file.
+pub fn read_todoist_path(
+    path: &Path,
+    now: NaiveDateTime,
+    report: &mut ServiceImportReport,
+) -> Result<Vec<ServiceTask>, AppError> {
+    if !path.is_dir() {
+        let text = std::fs::read_to_string(path)?;
+        return if is_json(&text) {
+            parse_todoist_json(&text, now, report)
+        } else {
+            parse_todoist_csv(&text, &project_name(path), now, report)
+        };
+    }
+
+    let mut files: Vec<_> = std::fs::read_dir(path)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv")))
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        return Err(AppError::InvalidInput("The folder has no Todoist CSV files".to_string()));
+    }
+
+    let mut tasks = Vec::new();
+    for file in files {
+        let text = std::fs::read_to_string(&file)?;
+        tasks.extend(parse_todoist_csv(&text, &project_name(&file), now, report)?);
+    }
+    Ok(tasks)
+}
+
+fn is_json(text: &str) -> bool {
+    matches!(text.trim_start_matches('\u{feff}').trim_start().chars().next(), Some('{' | '['))
+}
+
+/// Backup archives name files `<project> [<id>].csv`
+fn project_name(path: &Path) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    match stem.rfind(" [") {
+        Some(i) if stem.ends_with(']') => stem[..i].to_string(),
+        _ => stem,
+    }
+}
+
+/// Priorities in CSV files count from 1 (urgent) to 4 (none)
+fn csv_priority(value: &str) -> Priority {
+    match value.trim() {
+        "1" => Priority::High,
+        "3" => Priority::Low,
+        _ => Priority::Medium,
+    }
+}
+
+/// The API counts the other way: 4 is urgent and 1 is none
+fn api_priority(value: u8) -> Priority {
+    match value {
+        4 => Priority::High,
+        2 => Priority::Low,
+        _ => Priority::Medium,
+    }
+}
+
+/// Split `@label` words out of a task's content
+fn split_labels(content: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut labels = Vec::new();
+    for word in content.split_whitespace() {
+        match word.strip_prefix('@').filter(|l| !l.is_empty()) {
+            Some(label) => labels.push(label.to_string()),
+            None => words.push(word),
+        }
+    }
+    (words.join(" "), labels)
+}
+
+/// Parse one project's CSV file, as written by Todoist's "Export as a
+/// template" and inside backup archives. Rows are tasks, sections or notes;
+/// INDENT nests tasks under the task above them.
+pub fn parse_todoist_csv(
+    text: &str,
+    project: &str,
+    now: NaiveDateTime,
+    report: &mut ServiceImportReport,
+) -> Result<Vec<ServiceTask>, AppError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut reader = ::csv::ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| AppError::InvalidInput(format!("CSV error: {}", e)))?
+        .iter()
+        .map(|h| h.trim().to_ascii_uppercase())
+        .collect();
+    if !headers.iter().any(|h| h == "TYPE") || !headers.iter().any(|h| h == "CONTENT") {
+        return Err(AppError::InvalidInput(format!(
+            "'{}' is not a Todoist CSV file (no TYPE and CONTENT columns)",
+            project
+        )));
+    }
+
+    let mut tasks: Vec<ServiceTask> = Vec::new();
+    // (indent, index into `tasks`) of the tasks that can still take subtasks
+    let mut ancestors: Vec<(u32, usize)> = Vec::new();
+
+    for (row, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| AppError::InvalidInput(format!("CSV error: {}", e)))?;
+        let cell = |name: &str| -> &str {
+            headers
+                .iter()
+                .position(|h| h == name)
+                .and_then(|i| record.get(i))
+                .map(str::trim)
+                .unwrap_or("")
+        };
+
+        match cell("TYPE").to_ascii_lowercase().as_str() {
+            "task" => {}
+            "section" => {
+                report.lossy("section", "Sections are not imported; their tasks are kept in the project");
+                ancestors.clear();
+                continue;
+            }
+            "note" => {
+                match tasks.last_mut() {
+                    Some(task) => append_description(task, cell("CONTENT")),
+                    None => report.skip(cell("CONTENT"), "A project comment, not attached to a task"),
+                }
+                continue;
+            }
+            // Blank separator rows and view settings
+            _ => continue,
+        }
+
+        let (title, labels) = split_labels(cell("CONTENT"));
+        let indent: u32 = cell("INDENT").parse().unwrap_or(1);
+        while ancestors.last().is_some_and(|(i, _)| *i >= indent) {
+            ancestors.pop();
+        }
+
+        let mut task = ServiceTask {
+            source_id: format!("{}:{}", project, row),
+            parent: ancestors.last().map(|(_, i)| tasks[*i].source_id.clone()),
+            list: Some(project.to_string()),
+            title,
+            description: Some(cell("DESCRIPTION").to_string()),
+            priority: csv_priority(cell("PRIORITY")),
+            tags: labels,
+            ..Default::default()
+        };
+        if indent > 1 && task.parent.is_none() {
+            report.lossy("indent", "A task was indented without a task above it; imported as a top-level task");
+        }
+
+        read_due(&mut task, cell("DATE"), now, report);
+        let deadline = cell("DEADLINE");
+        if !deadline.is_empty() {
+            if task.due_date.is_none() {
+                task.due_date = parse_service_date(deadline, true);
+            } else {
+                report.lossy("deadline", "Tasks with both a date and a deadline keep only the date");
+            }
+        }
+        if !cell("RESPONSIBLE").is_empty() {
+            report.lossy("assignee", "Assignees are not imported");
+        }
+        if !cell("DURATION").is_empty() {
+            report.lossy("duration", "Durations are not imported");
+        }
+
+        tasks.push(task);
+        ancestors.push((indent, tasks.len() - 1));
+    }
+
+    Ok(tasks)
+}
+
+fn append_description(task: &mut ServiceTask, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    let description = task.description.get_or_insert_with(String::new);
+    if !description.trim().is_empty() {
+        description.push_str("\n\n");
+    }
+    description.push_str(text);
+}
+
+/// Todoist dates are free text ("every mon", "Oct 20 5pm")
+fn read_due(task: &mut ServiceTask, text: &str, now: NaiveDateTime, report: &mut ServiceImportReport) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(due) = parse_service_date(text, false) {
+        task.due_date = Some(due);
+        return;
+    }
+    let (due, recurrence, rest) = parse_natural_due(text, now);
+    task.due_date = due;
+    task.recurrence = recurrence;
+    if !rest.is_empty() {
+        report.lossy(
+            "date",
+            &format!("Some dates could not be read, e.g. '{}' on '{}'", text, task.title),
+        );
+    }
+}
+
+/// Todoist IDs are numbers in older exports and strings in newer ones
+fn id_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn deserialize_id<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(Option::<Value>::deserialize(deserializer)?.as_ref().and_then(id_string))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TodoistBackup {
+    #[serde(default)]
+    projects: Vec<TodoistProject>,
+    #[serde(default, alias = "tasks")]
+    items: Vec<TodoistItem>,
+    #[serde(default)]
+    labels: Vec<TodoistLabel>,
+    #[serde(default, alias = "comments")]
+    notes: Vec<TodoistNote>,
+    #[serde(default)]
+    reminders: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TodoistProject {
+    #[serde(deserialize_with = "deserialize_id")]
+    id: Option<String>,
+    name: String,
+    #[serde(default, deserialize_with = "deserialize_id")]
+    parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TodoistLabel {
+    #[serde(deserialize_with = "deserialize_id")]
+    id: Option<String>,
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TodoistNote {
+    #[serde(default, alias = "task_id", deserialize_with = "deserialize_id")]
+    item_id: Option<String>,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    file_attachment: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TodoistDue {
+    #[serde(default)]
+    date: Option<String>,
+    /// Only set by the REST API, with the time
+    #[serde(default)]
+    datetime: Option<String>,
+    #[serde(default)]
+    string: Option<String>,
+    #[serde(default)]
+    is_recurring: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TodoistItem {
+    #[serde(deserialize_with = "deserialize_id")]
+    id: Option<String>,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_id")]
+    project_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_id")]
+    section_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_id")]
+    parent_id: Option<String>,
+    #[serde(default)]
+    priority: Option<u8>,
+    #[serde(default)]
+    labels: Vec<Value>,
+    #[serde(default)]
+    due: Option<TodoistDue>,
+    #[serde(default, alias = "is_completed")]
+    checked: bool,
+    #[serde(default)]
+    is_deleted: bool,
+    #[serde(default, alias = "created_at", alias = "date_added")]
+    added_at: Option<String>,
+    #[serde(default, alias = "assignee_id", deserialize_with = "deserialize_id")]
+    responsible_uid: Option<String>,
+    #[serde(default)]
+    duration: Option<Value>,
+}
+
+/// Parse a JSON backup in the shape of the Sync API (`projects`, `items`,
+/// `labels`, `notes`), or a plain array of tasks from the REST API.
+pub fn parse_todoist_json(
+    text: &str,
+    now: NaiveDateTime,
+    report: &mut ServiceImportReport,
+) -> Result<Vec<ServiceTask>, AppError> {
+    let text = text.trim_start_matches('\u{feff}').trim();
+    let backup: TodoistBackup = if text.starts_with('[') {
+        TodoistBackup {
+            projects: Vec::new(),
+            items: serde_json::from_str(text)
+                .map_err(|e| AppError::InvalidInput(format!("Not a Todoist export: {}", e)))?,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            reminders: Vec::new(),
+        }
+    } else {
+        serde_json::from_str(text).map_err(|e| AppError::InvalidInput(format!("Not a Todoist export: {}", e)))?
+    };
+
+    let projects: HashMap<String, &TodoistProject> = backup
+        .projects
+        .iter()
+        .filter_map(|p| p.id.clone().map(|id| (id, p)))
+        .collect();
+    if backup.projects.iter().any(|p| p.parent_id.is_some()) {
+        report.lossy("project hierarchy", "Sub-projects become separate categories");
+    }
+    for _ in &backup.reminders {
+        report.lossy("reminder", "Todoist reminders are not imported");
+    }
+    let labels: HashMap<String, &str> = backup
+        .labels
+        .iter()
+        .filter_map(|l| l.id.clone().map(|id| (id, l.name.as_str())))
+        .collect();
+
+    let mut tasks: Vec<ServiceTask> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for item in &backup.items {
+        if item.is_deleted {
+            report.skip(&item.content, "Deleted in Todoist");
+            continue;
+        }
+
+        let (title, mut tags) = split_labels(&item.content);
+        for label in &item.labels {
+            match label {
+                Value::String(name) => tags.push(name.clone()),
+                other => match id_string(other).and_then(|id| labels.get(&id).copied()) {
+                    Some(name) => tags.push(name.to_string()),
+                    None => report.lossy("label", "Some labels were referenced by an ID missing from the export"),
+                },
+            }
+        }
+
+        let mut task = ServiceTask {
+            source_id: item.id.clone().unwrap_or_default(),
+            parent: item.parent_id.clone(),
+            list: item
+                .project_id
+                .as_ref()
+                .and_then(|id| projects.get(id))
+                .map(|p| p.name.clone()),
+            title,
+            description: item.description.clone(),
+            completed: item.checked,
+            priority: item.priority.map(api_priority).unwrap_or_default(),
+            tags,
+            created_at: item.added_at.as_deref().and_then(|d| parse_service_date(d, false)),
+            ..Default::default()
+        };
+
+        if let Some(due) = &item.due {
+            let date = due.datetime.as_deref().or(due.date.as_deref());
+            task.due_date = date.and_then(|d| parse_service_date(d, false));
+            if due.is_recurring {
+                let phrase = due.string.as_deref().unwrap_or("");
+                let (_, recurrence, _) = parse_natural_due(phrase, now);
+                task.recurrence = recurrence;
+                if task.recurrence.is_none() {
+                    report.lossy(
+                        "recurrence",
+                        &format!("Some repeat rules could not be read, e.g. '{}' on '{}'", phrase, task.title),
+                    );
+                }
+            }
+            if task.due_date.is_none() {
+                read_due(&mut task, due.string.as_deref().unwrap_or(""), now, report);
+            }
+        }
+        if item.section_id.is_some() {
+            report.lossy("section", "Sections are not imported; their tasks are kept in the project");
+        }
+        if item.responsible_uid.is_some() {
+            report.lossy("assignee", "Assignees are not imported");
+        }
+        if item.duration.as_ref().is_some_and(|d| !d.is_null()) {
+            report.lossy("duration", "Durations are not imported");
+        }
+
+        index.insert(task.source_id.clone(), tasks.len());
+        tasks.push(task);
+    }
+
+    for note in &backup.notes {
+        let Some(task) = note.item_id.as_ref().and_then(|id| index.get(id)).map(|i| &mut tasks[*i]) else {
+            report.skip(&note.content, "A comment on a project or a task missing from the export");
+            continue;
+        };
+        append_description(task, &note.content);
+        if note.file_attachment.as_ref().is_some_and(|a| !a.is_null()) {
+            report.lossy("attachment", "Comment attachments are not imported");
+        }
+    }
+
+    Ok(tasks)
+}
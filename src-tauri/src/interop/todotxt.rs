@@ -0,0 +1,703 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use crate::database::{CategoryRepository, Journal, SettingsRepository, TaskRepository};
+use crate::interop::{due_day, local_day, ImportSummary};
+use crate::models::{Category, EntityState, Frequency, Priority, RecurrenceRule, Task};
+use crate::utils::AppError;
+
+/// One line of a todo.txt file, e.g.
+/// `(A) 2026-10-01 Call the bank +Finance @phone due:2026-10-20 id:…`.
+///
+/// Only the keys Pluto understands (`due`, `rec`, `pri`, `id`, `parent`) are
+/// taken out of the text; any other `key:value` stays part of the title so
+/// nothing written by another tool is lost.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TodoTxtItem {
+    pub completed: bool,
+    pub completed_on: Option<NaiveDate>,
+    pub created_on: Option<NaiveDate>,
+    /// Priority letter, `A` being the most important
+    pub priority: Option<char>,
+    pub title: String,
+    /// `+project` names; the first one is the task's category
+    pub projects: Vec<String>,
+    /// `@context` names, mapped to tags
+    pub contexts: Vec<String>,
+    pub due: Option<NaiveDate>,
+    /// `rec:` value as written, e.g. `2w` or a full RRULE
+    pub recurrence: Option<String>,
+    pub id: Option<String>,
+    pub parent_id: Option<String>,
+}
+
+impl TodoTxtItem {
+    /// Parse a line; blank lines give `None`.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut tokens = line.split_whitespace().peekable();
+        tokens.peek()?;
+
+        let mut item = TodoTxtItem::default();
+        if tokens.peek() == Some(&"x") {
+            tokens.next();
+            item.completed = true;
+        }
+        if let Some(letter) = tokens.peek().and_then(|t| parse_priority_letter(t)) {
+            tokens.next();
+            item.priority = Some(letter);
+        }
+        // A completed line carries the completion date first, then the creation date
+        if let Some(date) = tokens.peek().and_then(|t| parse_date(t)) {
+            tokens.next();
+            if item.completed {
+                item.completed_on = Some(date);
+                if let Some(created) = tokens.peek().and_then(|t| parse_date(t)) {
+                    tokens.next();
+                    item.created_on = Some(created);
+                }
+            } else {
+                item.created_on = Some(date);
+            }
+        }
+
+        let mut words = Vec::new();
+        for token in tokens {
+            if let Some(name) = token.strip_prefix('+').filter(|n| !n.is_empty()) {
+                if !item.projects.iter().any(|p| p == name) {
+                    item.projects.push(name.to_string());
+                }
+                continue;
+            }
+            if let Some(name) = token.strip_prefix('@').filter(|n| !n.is_empty()) {
+                if !item.contexts.iter().any(|c| c == name) {
+                    item.contexts.push(name.to_string());
+                }
+                continue;
+            }
+            if let Some((key, value)) = token.split_once(':').filter(|(k, v)| !k.is_empty() && !v.is_empty()) {
+                let known = match key {
+                    "due" => parse_date(value).map(|d| item.due = Some(d)).is_some(),
+                    "rec" => {
+                        item.recurrence = Some(value.to_string());
+                        true
+                    }
+                    "pri" => parse_priority_letter(&format!("({})", value)).map(|p| item.priority = Some(p)).is_some(),
+                    "id" => {
+                        item.id = Some(value.to_string());
+                        true
+                    }
+                    "parent" => {
+                        item.parent_id = Some(value.to_string());
+                        true
+                    }
+                    _ => false,
+                };
+                if known {
+                    continue;
+                }
+            }
+            words.push(token);
+        }
+        item.title = words.join(" ");
+
+        Some(item)
+    }
+
+    /// The line for `task`. Medium priority is written as no priority, and
+    /// date-only due dates keep their calendar day while timed ones use the
+    /// local day.
+    pub fn from_task(task: &Task, categories: &[Category]) -> Self {
+        TodoTxtItem {
+            completed: task.completed,
+            completed_on: task.completed.then(|| local_day(task.updated_at)),
+            created_on: Some(local_day(task.created_at)),
+            priority: match task.priority {
+                Priority::High => Some('A'),
+                Priority::Medium => None,
+                Priority::Low => Some('C'),
+            },
+            title: task.title.split_whitespace().collect::<Vec<_>>().join(" "),
+            projects: task
+                .category_id
+                .as_ref()
+                .and_then(|id| categories.iter().find(|c| &c.id == id))
+                .map(|c| vec![token_name(&c.name)])
+                .unwrap_or_default(),
+            contexts: task.tags.iter().map(|t| token_name(t)).collect(),
+            due: task.due_date.map(due_day),
+            recurrence: task.recurrence.as_ref().map(format_recurrence),
+            id: Some(task.id.clone()),
+            parent_id: task.parent_id.clone(),
+        }
+    }
+
+    pub fn task_priority(&self) -> Priority {
+        match self.priority {
+            None | Some('B') => Priority::Medium,
+            Some('A') => Priority::High,
+            Some(_) => Priority::Low,
+        }
+    }
+}
+
+impl fmt::Display for TodoTxtItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.completed {
+            parts.push("x".to_string());
+            // The creation date may only follow a completion date
+            if let Some(done) = self.completed_on {
+                parts.push(done.format("%Y-%m-%d").to_string());
+                if let Some(created) = self.created_on {
+                    parts.push(created.format("%Y-%m-%d").to_string());
+                }
+            }
+        } else {
+            if let Some(letter) = self.priority {
+                parts.push(format!("({})", letter));
+            }
+            if let Some(created) = self.created_on {
+                parts.push(created.format("%Y-%m-%d").to_string());
+            }
+        }
+        if !self.title.is_empty() {
+            parts.push(self.title.clone());
+        }
+        parts.extend(self.projects.iter().map(|p| format!("+{}", p)));
+        parts.extend(self.contexts.iter().map(|c| format!("@{}", c)));
+        if let Some(due) = self.due {
+            parts.push(format!("due:{}", due.format("%Y-%m-%d")));
+        }
+        if let Some(ref recurrence) = self.recurrence {
+            parts.push(format!("rec:{}", recurrence));
+        }
+        // Completed lines drop the leading priority, as the format asks
+        if let (true, Some(letter)) = (self.completed, self.priority) {
+            parts.push(format!("pri:{}", letter));
+        }
+        if let Some(ref parent_id) = self.parent_id {
+            parts.push(format!("parent:{}", parent_id));
+        }
+        if let Some(ref id) = self.id {
+            parts.push(format!("id:{}", id));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// What a sync changed on each side.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TodoTxtSyncReport {
+    pub path: String,
+    /// Tasks and categories created or updated from edits to the file
+    pub summary: ImportSummary,
+    /// Tasks moved to the trash because their line was removed from the file
+    pub tasks_deleted: usize,
+    pub lines_added: usize,
+    pub lines_updated: usize,
+    pub lines_removed: usize,
+    /// Tasks changed on both sides since the last sync, and which side won
+    pub conflicts: Vec<String>,
+}
+
+impl TodoTxtSyncReport {
+    /// Whether the sync changed anything in the app.
+    pub fn changed_app(&self) -> bool {
+        !self.summary.task_ids.is_empty() || self.tasks_deleted > 0
+    }
+}
+
+/// Render tasks as todo.txt lines, one per task, in the order given.
+pub fn export_todotxt(tasks: &[Task], categories: &[Category]) -> String {
+    tasks
+        .iter()
+        .map(|task| format!("{}\n", TodoTxtItem::from_task(task, categories)))
+        .collect()
+}
+
+/// Import every line of a todo.txt file. Lines carrying the `id:` of an
+/// existing task update it; the others create tasks, along with any
+/// `+project` category that does not exist yet.
+pub fn import_todotxt(conn: &Connection, text: &str) -> Result<ImportSummary, AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let mut categories = CategoryRepository::new(conn).get_all()?;
+    let mut summary = ImportSummary::default();
+
+    let tx = conn.unchecked_transaction()?;
+    // Subtasks may come before their parents
+    tx.pragma_update(None, "defer_foreign_keys", true)?;
+
+    let mut tasks = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, line) in text.lines().enumerate() {
+        let item = match TodoTxtItem::parse(line) {
+            Some(item) => item,
+            None => continue,
+        };
+        if item.title.is_empty() {
+            summary.warnings.push(format!("Line {}: skipped a task without a title", index + 1));
+            continue;
+        }
+        let id = item.id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        if !seen.insert(id.clone()) {
+            summary.warnings.push(format!("Line {}: skipped '{}', ID {} appears more than once", index + 1, item.title, id));
+            continue;
+        }
+
+        let existing = task_repo.get_by_id(&id)?;
+        if existing.is_none() && task_repo.row_exists(&id)? {
+            summary.warnings.push(format!("Line {}: skipped '{}', the task is in the trash", index + 1, item.title));
+            continue;
+        }
+
+        let mut task = existing.clone().unwrap_or_else(|| new_task(id, item.created_on));
+        apply_item(conn, &item, &mut task, &mut categories, &mut summary)?;
+        tasks.push((task, existing.is_some()));
+    }
+
+    // Parents must be in this file or already exist
+    let file_ids: HashSet<String> = tasks.iter().map(|(t, _)| t.id.clone()).collect();
+    for (task, _) in &mut tasks {
+        if let Some(ref parent_id) = task.parent_id {
+            if !file_ids.contains(parent_id) && task_repo.get_by_id(parent_id)?.is_none() {
+                summary.warnings.push(format!("'{}': parent {} not found, imported as a top-level task", task.title, parent_id));
+                task.parent_id = None;
+            }
+        }
+    }
+
+    for (task, exists) in &tasks {
+        if *exists {
+            task_repo.update(task)?;
+            summary.tasks_updated += 1;
+        } else {
+            task_repo.create(task)?;
+            summary.tasks_created += 1;
+        }
+        summary.task_ids.push(task.id.clone());
+    }
+
+    tx.commit()?;
+    Ok(summary)
+}
+
+/// Two-way sync with the todo.txt file at `path`, which the first sync
+/// creates if missing; after that a missing file is an error. Each side is
+/// compared with the file as it was written by the previous sync:
+///
+/// - a line changed only in the file updates its task, and a task changed
+///   only in the app rewrites its line
+/// - lines without an `id:` become new tasks and get their ID written back
+/// - a line removed from the file moves its task to the trash, and a task
+///   deleted in the app has its line removed
+/// - when both sides changed, the side modified most recently wins (the
+///   file's modification time against the task's `updated_at`) and the
+///   conflict is reported
+///
+/// App changes are recorded as one undoable operation.
+pub fn sync_file(conn: &Connection, path: &Path) -> Result<TodoTxtSyncReport, AppError> {
+    let settings = SettingsRepository::new(conn);
+    let journal = Journal::new(conn);
+
+    let snapshot = settings.todotxt_sync_snapshot()?;
+    let (file_text, file_modified) = match std::fs::read_to_string(path) {
+        Ok(text) => {
+            let modified = std::fs::metadata(path)?.modified()?;
+            (text, Some(DateTime::<Utc>::from(modified).naive_utc()))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && snapshot.is_none() => (String::new(), None),
+        // Once synced, a missing file is far more likely renamed or on a
+        // drive that is not mounted than emptied on purpose; reading it as
+        // empty would trash every task
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(AppError::InvalidInput(format!(
+                "{} no longer exists; nothing was synced. Restore the file or choose it again in the settings",
+                path.display()
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let snapshot = snapshot.unwrap_or_default();
+
+    let mut report = TodoTxtSyncReport {
+        path: path.display().to_string(),
+        ..Default::default()
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    tx.pragma_update(None, "defer_foreign_keys", true)?;
+
+    let mut before = Vec::new();
+    let (text, touched) = reconcile(conn, &file_text, file_modified, &snapshot, &mut report, &mut before)?;
+    // The file is written before committing so a failed write leaves the
+    // app and the snapshot untouched
+    if text != file_text {
+        std::fs::write(path, &text)?;
+    }
+    settings.set_todotxt_sync_snapshot(&text)?;
+
+    tx.commit()?;
+
+    let mut after = journal.capture_categories(&report.summary.category_ids)?;
+    after.extend(journal.capture_tasks(&touched)?);
+    journal.record("Sync todo.txt", before, after)?;
+
+    Ok(report)
+}
+
+/// Sync with the file chosen in settings; `None` when sync is turned off.
+pub fn sync_configured(conn: &Connection) -> Result<Option<TodoTxtSyncReport>, AppError> {
+    match SettingsRepository::new(conn).todotxt_sync_path()? {
+        Some(path) => Ok(Some(sync_file(conn, Path::new(&path))?)),
+        None => Ok(None),
+    }
+}
+
+/// Work out the merged state, write the app side and return the new file.
+fn reconcile(
+    conn: &Connection,
+    file_text: &str,
+    file_modified: Option<NaiveDateTime>,
+    snapshot: &str,
+    report: &mut TodoTxtSyncReport,
+    before: &mut Vec<EntityState>,
+) -> Result<(String, Vec<String>), AppError> {
+    let task_repo = TaskRepository::new(conn);
+    let mut categories = CategoryRepository::new(conn).get_all()?;
+
+    let base: HashMap<String, String> = snapshot
+        .lines()
+        .filter_map(TodoTxtItem::parse)
+        .filter_map(|item| Some((item.id.clone()?, item.to_string())))
+        .collect();
+
+    let mut live = task_repo.get_all(None)?;
+    live.sort_by_key(|t| t.created_at);
+    let order: Vec<String> = live.iter().map(|t| t.id.clone()).collect();
+    let mut tasks: HashMap<String, Task> = live.into_iter().map(|t| (t.id.clone(), t)).collect();
+
+    // Output lines in file order, each remembering the text it replaces
+    let mut slots: Vec<(String, Option<&str>)> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut created = Vec::new();
+    let mut changed = HashSet::new();
+
+    for (index, line) in file_text.lines().enumerate() {
+        let item = match TodoTxtItem::parse(line) {
+            Some(item) => item,
+            None => continue,
+        };
+        if item.title.is_empty() {
+            report.summary.warnings.push(format!("Line {}: removed a task without a title", index + 1));
+            continue;
+        }
+
+        let id = match item.id.clone() {
+            Some(id) => id,
+            None => {
+                let mut task = new_task(uuid::Uuid::new_v4().to_string(), item.created_on);
+                apply_item(conn, &item, &mut task, &mut categories, &mut report.summary)?;
+                seen.insert(task.id.clone());
+                slots.push((task.id.clone(), Some(line)));
+                created.push(task.id.clone());
+                tasks.insert(task.id.clone(), task);
+                continue;
+            }
+        };
+        if !seen.insert(id.clone()) {
+            report.summary.warnings.push(format!("Line {}: removed a duplicate of '{}'", index + 1, item.title));
+            continue;
+        }
+
+        let file_line = item.to_string();
+        let base_line = base.get(&id);
+
+        if let Some(task) = tasks.get_mut(&id) {
+            slots.push((id.clone(), Some(line)));
+            let app_line = TodoTxtItem::from_task(task, &categories).to_string();
+            if file_line == app_line {
+                continue;
+            }
+
+            let file_changed = base_line != Some(&file_line);
+            let app_changed = base_line != Some(&app_line);
+            let file_wins = match (file_changed, app_changed) {
+                (true, false) => true,
+                (false, _) => false,
+                (true, true) => {
+                    let wins = file_modified.map(|m| m > task.updated_at).unwrap_or(false);
+                    report.conflicts.push(format!(
+                        "'{}' changed in both places; kept the {} version",
+                        task.title,
+                        if wins { "todo.txt" } else { "app" }
+                    ));
+                    wins
+                }
+            };
+            if file_wins {
+                apply_item(conn, &item, task, &mut categories, &mut report.summary)?;
+                changed.insert(id);
+            }
+            continue;
+        }
+
+        // The task is not in the app (any more)
+        match base_line {
+            Some(base_line) if *base_line == file_line => {
+                report.lines_removed += 1;
+                continue;
+            }
+            Some(_) => {
+                report.conflicts.push(format!("'{}' was deleted in the app but edited in todo.txt; kept the edit", item.title));
+            }
+            None if task_repo.row_exists(&id)? => {
+                report.lines_removed += 1;
+                continue;
+            }
+            None => {}
+        }
+
+        // A trashed task keeps its ID, so the line comes back as a new task
+        let new_id = if task_repo.row_exists(&id)? { uuid::Uuid::new_v4().to_string() } else { id };
+        let mut task = new_task(new_id, item.created_on);
+        apply_item(conn, &item, &mut task, &mut categories, &mut report.summary)?;
+        seen.insert(task.id.clone());
+        slots.push((task.id.clone(), Some(line)));
+        created.push(task.id.clone());
+        tasks.insert(task.id.clone(), task);
+    }
+
+    let mut trashed = Vec::new();
+    let mut trashed_parents = HashMap::new();
+    for id in order.iter().filter(|id| !seen.contains(*id)) {
+        let app_line = TodoTxtItem::from_task(&tasks[id], &categories).to_string();
+        match base.get(id) {
+            Some(base_line) if *base_line == app_line => {
+                let task = tasks.remove(id).expect("task was just looked up");
+                trashed_parents.insert(id.clone(), task.parent_id);
+                trashed.push(id.clone());
+            }
+            Some(_) => {
+                report.conflicts.push(format!("'{}' was removed from todo.txt but changed in the app; kept it", tasks[id].title));
+                slots.push((id.clone(), None));
+            }
+            None => slots.push((id.clone(), None)),
+        }
+    }
+
+    // Subtasks of a removed line move up to the nearest ancestor still
+    // around, as they do when a task is deleted in the app
+    for task in tasks.values_mut() {
+        let mut parent_id = task.parent_id.clone();
+        while let Some(grandparent_id) = parent_id.as_ref().and_then(|p| trashed_parents.get(p)) {
+            parent_id = grandparent_id.clone();
+        }
+        if parent_id != task.parent_id {
+            task.parent_id = parent_id;
+            task.updated_at = Utc::now().naive_utc();
+            if !created.contains(&task.id) {
+                changed.insert(task.id.clone());
+            }
+        }
+    }
+
+    // Edited parents must still exist and must not form a cycle
+    for id in created.iter().chain(changed.iter()) {
+        let parent_id = match tasks[id].parent_id.clone() {
+            Some(parent_id) => parent_id,
+            None => continue,
+        };
+        let valid = tasks.contains_key(&parent_id) && !creates_cycle(&tasks, id, &parent_id);
+        if !valid {
+            let task = tasks.get_mut(id).expect("task was just looked up");
+            report.summary.warnings.push(format!("'{}': parent {} not usable, made it a top-level task", task.title, parent_id));
+            task.parent_id = None;
+        }
+    }
+
+    // Only the tasks written below change, along with the subtasks that
+    // trashing a task moves up a level
+    let mut touched: Vec<String> = changed.iter().chain(&trashed).cloned().collect();
+    for id in &trashed {
+        touched.extend(task_repo.get_direct_subtasks(id)?.into_iter().map(|t| t.id));
+    }
+    before.extend(Journal::new(conn).capture_tasks(&touched)?);
+    touched.extend(created.iter().cloned());
+
+    for id in &created {
+        task_repo.create(&tasks[id])?;
+        report.summary.tasks_created += 1;
+        report.summary.task_ids.push(id.clone());
+    }
+    for id in &changed {
+        task_repo.update(&tasks[id])?;
+        report.summary.tasks_updated += 1;
+        report.summary.task_ids.push(id.clone());
+    }
+    for id in &trashed {
+        task_repo.delete(id)?;
+        report.tasks_deleted += 1;
+    }
+
+    let mut text = String::new();
+    for (id, original) in &slots {
+        let line = TodoTxtItem::from_task(&tasks[id], &categories).to_string();
+        match original {
+            Some(original) if *original != line => report.lines_updated += 1,
+            Some(_) => {}
+            None => report.lines_added += 1,
+        }
+        text.push_str(&line);
+        text.push('\n');
+    }
+
+    Ok((text, touched))
+}
+
+/// Write the fields a todo.txt line carries onto `task`, leaving the
+/// description, reminders and the time of a due date on the same day alone.
+fn apply_item(
+    conn: &Connection,
+    item: &TodoTxtItem,
+    task: &mut Task,
+    categories: &mut Vec<Category>,
+    summary: &mut ImportSummary,
+) -> Result<(), AppError> {
+    task.title = item.title.clone();
+    task.completed = item.completed;
+    task.priority = item.task_priority();
+    task.updated_at = Utc::now().naive_utc();
+
+    task.due_date = match item.due {
+        Some(day) if task.due_date.map(due_day) == Some(day) => task.due_date,
+        Some(day) => Some(day.and_time(NaiveTime::MIN)),
+        None => None,
+    };
+
+    // Names with spaces are written with underscores
+    let mut projects = item.projects.iter();
+    task.category_id = match projects.next() {
+        Some(project) => match categories.iter().find(|c| token_name(&c.name).eq_ignore_ascii_case(project)) {
+            Some(category) => Some(category.id.clone()),
+            None => Some(summary.find_or_create_category(conn, categories, &project.replace('_', " "))?),
+        },
+        None => None,
+    };
+
+    // Further projects have nowhere else to go, so they become tags
+    let old_tags = std::mem::take(&mut task.tags);
+    for name in item.contexts.iter().chain(projects) {
+        let tag = old_tags.iter().find(|t| token_name(t) == *name).cloned().unwrap_or_else(|| name.clone());
+        if !task.tags.contains(&tag) {
+            task.tags.push(tag);
+        }
+    }
+
+    task.recurrence = match item.recurrence {
+        Some(ref value) => match parse_recurrence(value) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                summary.warnings.push(format!("'{}': recurrence '{}' not understood ({})", task.title, value, e));
+                task.recurrence.take()
+            }
+        },
+        None => None,
+    };
+
+    task.parent_id = item.parent_id.clone().filter(|p| p != &task.id);
+    Ok(())
+}
+
+fn new_task(id: String, created_on: Option<NaiveDate>) -> Task {
+    let now = Utc::now().naive_utc();
+    let created_at = created_on.map(|d| d.and_time(NaiveTime::MIN)).unwrap_or(now);
+    Task {
+        id,
+        title: String::new(),
+        description: None,
+        completed: false,
+        priority: Priority::Medium,
+        due_date: None,
+        category_id: None,
+        tags: Vec::new(),
+        parent_id: None,
+        recurrence: None,
+        created_at,
+        updated_at: now,
+        deleted_at: None,
+        position: Task::initial_position(created_at, false),
+    }
+}
+
+/// Whether making `parent_id` the parent of `id` would loop back to `id`.
+fn creates_cycle(tasks: &HashMap<String, Task>, id: &str, parent_id: &str) -> bool {
+    let mut current = Some(parent_id.to_string());
+    let mut steps = 0;
+    while let Some(ancestor) = current {
+        if ancestor == id || steps > tasks.len() {
+            return true;
+        }
+        current = tasks.get(&ancestor).and_then(|t| t.parent_id.clone());
+        steps += 1;
+    }
+    false
+}
+
+/// Simple rules use the short `rec:` form (`1d`, `2w`, `1m`, `1y`); anything
+/// richer is written as its RRULE.
+fn format_recurrence(rule: &RecurrenceRule) -> String {
+    let simple = rule.by_weekday.is_empty() && rule.by_month_day.is_none() && rule.until.is_none() && rule.count.is_none();
+    if !simple {
+        return rule.to_string();
+    }
+    let unit = match rule.frequency {
+        Frequency::Daily => 'd',
+        Frequency::Weekly => 'w',
+        Frequency::Monthly => 'm',
+        Frequency::Yearly => 'y',
+    };
+    format!("{}{}", rule.interval, unit)
+}
+
+fn parse_recurrence(value: &str) -> Result<RecurrenceRule, String> {
+    // A leading + (recur from the due date rather than completion) is how
+    // Pluto always recurs
+    let short = value.strip_prefix('+').unwrap_or(value);
+    if let Some(unit) = short.chars().last() {
+        let count = &short[..short.len() - unit.len_utf8()];
+        if count.chars().all(|c| c.is_ascii_digit()) {
+            let frequency = match unit.to_ascii_lowercase() {
+                'd' => Frequency::Daily,
+                'w' => Frequency::Weekly,
+                'm' => Frequency::Monthly,
+                'y' => Frequency::Yearly,
+                _ => return Err(format!("unknown unit '{}'", unit)),
+            };
+            let mut rule = RecurrenceRule::new(frequency);
+            rule.interval = if count.is_empty() { 1 } else { count.parse().map_err(|_| "interval too large".to_string())? };
+            if rule.interval == 0 {
+                return Err("interval must be at least 1".to_string());
+            }
+            return Ok(rule);
+        }
+    }
+    value.parse()
+}
+
+fn parse_priority_letter(token: &str) -> Option<char> {
+    let mut chars = token.strip_prefix('(')?.strip_suffix(')')?.chars();
+    let letter = chars.next().filter(|c| c.is_ascii_uppercase())?;
+    chars.next().is_none().then_some(letter)
+}
+
+fn parse_date(token: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(token, "%Y-%m-%d").ok().filter(|_| token.len() == 10)
+}
+
+/// Project and context names cannot contain spaces.
+fn token_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
@@ -5,22 +5,28 @@ pub mod models;
 pub mod database;
 pub mod commands;
 pub mod utils;
+pub mod interop;
+pub mod api;
 
-use database::Database;
+use database::{Database, DATABASE_FILE};
 use commands::*;
 use utils::window_state::setup_window_state_persistence;
+use utils::reminder_scheduler::start_reminder_scheduler;
+use utils::file_sync::start_file_sync;
+use api::server::{start_api_server, ApiServer};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             // Get the app data directory
             let app_dir = app.path().app_data_dir().expect("Failed to get app data dir");
             std::fs::create_dir_all(&app_dir).expect("Failed to create app data dir");
             
             // Initialize database
-            let db_path = app_dir.join("pluto_todo.db");
+            let db_path = app_dir.join(DATABASE_FILE);
             let database = Database::new(&db_path).expect("Failed to initialize database");
             
             // Store database in app state
@@ -31,38 +37,108 @@ pub fn run() {
                 eprintln!("Failed to setup window state persistence: {}", e);
             }
             
+            // Deliver due-date reminders and purge expired trash in the background
+            start_reminder_scheduler(app);
+            
+            // Keep the chosen todo.txt file and Markdown vault in sync in the background
+            start_file_sync(app);
+            
+            // Serve the local API if it has been turned on
+            app.manage(ApiServer::default());
+            start_api_server(app);
+            
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             // Task commands
             create_task,
             get_tasks,
+            get_task_counts,
+            quick_add_task,
+            preview_quick_add,
             get_task_by_id,
             update_task,
+            move_task,
             delete_task,
             delete_task_with_subtasks,
             delete_task_and_promote_subtasks,
             check_task_has_subtasks,
             toggle_task_completion,
+            skip_task_occurrence,
             search_tasks,
+            query_tasks,
+            validate_filter_query,
             get_tasks_by_category,
             // New subtask commands
             get_subtasks,
             get_task_hierarchy,
             get_task_with_subtasks,
             calculate_task_progress,
+            get_tasks_progress,
             get_incomplete_subtasks,
             bulk_mark_subtasks_completed,
             // Bulk commands
             bulk_check_tasks_have_subtasks,
             bulk_delete_tasks_with_subtasks,
             bulk_delete_tasks_and_promote_subtasks,
+            bulk_delete_tasks,
+            bulk_set_tasks_completed,
             // Category commands
             create_category,
             get_categories,
             get_category_by_id,
             update_category,
             delete_category,
+            // Reminder commands
+            create_reminder,
+            get_task_reminders,
+            delete_reminder,
+            // Dependency commands
+            add_task_dependency,
+            remove_task_dependency,
+            get_task_dependencies,
+            get_blocked_tasks,
+            get_unblocked_tasks,
+            // History commands
+            undo,
+            redo,
+            get_undo_state,
+            // Trash commands
+            list_trash,
+            restore_task,
+            empty_trash,
+            get_trash_retention_days,
+            set_trash_retention_days,
+            // Import/export commands
+            export_all,
+            import_all,
+            export_ics,
+            import_ics,
+            export_csv,
+            preview_csv,
+            import_csv,
+            export_todotxt,
+            import_todotxt,
+            get_todotxt_sync_path,
+            set_todotxt_sync_path,
+            sync_todotxt,
+            get_markdown_vault_path,
+            set_markdown_vault_path,
+            sync_markdown_vault,
+            export_taskwarrior,
+            import_taskwarrior,
+            export_org,
+            import_org,
+            import_todoist,
+            import_microsoft_todo,
+            import_google_tasks,
+            get_caldav_account,
+            set_caldav_account,
+            sync_caldav,
+            // Local API commands
+            get_api_settings,
+            set_api_settings,
+            regenerate_api_token,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
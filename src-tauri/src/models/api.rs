@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// Port the local API listens on unless another is chosen
+pub const DEFAULT_API_PORT: u16 = 4517;
+
+/// The local HTTP API. It only ever listens on the loopback interface, and
+/// every request except the OpenAPI description must carry `token`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
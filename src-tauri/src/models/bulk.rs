@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Outcome of a bulk action that is applied to each task on its own, so
+/// one task that cannot be changed does not hold up the rest.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkResult {
+    /// Tasks the action was applied to
+    pub succeeded: Vec<String>,
+    /// Tasks left as they were, and why
+    pub failed: Vec<BulkFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkFailure {
+    pub id: String,
+    pub error: String,
+}
+
+impl BulkResult {
+    pub fn add<E: std::fmt::Display>(&mut self, id: String, outcome: Result<(), E>) {
+        match outcome {
+            Ok(()) => self.succeeded.push(id),
+            Err(e) => self.failed.push(BulkFailure { id, error: e.to_string() }),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// How a sync settles a task that changed both in Pluto and on the server
+/// since the last sync. An edit always beats a deletion unless the side
+/// that deleted is the one that wins outright.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    ServerWins,
+    LocalWins,
+    /// Whichever side was modified last, by LAST-MODIFIED and `updated_at`
+    #[default]
+    NewestWins,
+}
+
+impl ConflictPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictPolicy::ServerWins => "server_wins",
+            ConflictPolicy::LocalWins => "local_wins",
+            ConflictPolicy::NewestWins => "newest_wins",
+        }
+    }
+
+    pub fn from_string(s: &str) -> Self {
+        match s {
+            "server_wins" => ConflictPolicy::ServerWins,
+            "local_wins" => ConflictPolicy::LocalWins,
+            _ => ConflictPolicy::NewestWins,
+        }
+    }
+}
+
+/// The CalDAV server to sync with. `url` is the server root, a principal
+/// or a calendar home; calendars are discovered from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalDavAccount {
+    pub url: String,
+    pub username: String,
+    /// Never sent back to the frontend. Kept as plain text in the settings
+    /// table, which backups and exports leave out
+    #[serde(default, skip_serializing)]
+    pub password: String,
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+}
+
+/// A calendar collection on the server and the category synced with it.
+/// `category_id` is cleared when the category is deleted, which stops the
+/// calendar from syncing without touching it on the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalDavCalendar {
+    pub url: String,
+    pub category_id: Option<String>,
+    pub display_name: String,
+    /// RFC 6578 token of the last sync, if the server supports them
+    pub sync_token: Option<String>,
+    pub synced_at: Option<NaiveDateTime>,
+}
+
+/// What the last sync knew about a task's VTODO on the server.
+/// `fingerprint` is the task's synced fields at that time, so a later sync
+/// can tell whether the task changed in Pluto since.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSyncState {
+    pub task_id: String,
+    pub calendar_url: String,
+    pub href: String,
+    pub uid: String,
+    pub etag: Option<String>,
+    pub fingerprint: String,
+    pub synced_at: NaiveDateTime,
+}
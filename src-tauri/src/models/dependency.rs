@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use crate::models::Task;
+
+/// The tasks a task waits on, and the tasks waiting on it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskDependencies {
+    /// Tasks that must be done before this one can start
+    pub blocked_by: Vec<Task>,
+    /// Tasks that cannot start until this one is done
+    pub blocking: Vec<Task>,
+}
+
+/// One link: `task_id` cannot start until `depends_on_id` is done.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskDependency {
+    pub task_id: String,
+    pub depends_on_id: String,
+}
+
+/// A task after completing or reopening it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToggleCompletionResult {
+    pub task: Task,
+    /// Set when the task was completed while tasks it depends on are still open
+    pub warning: Option<String>,
+}
+
+impl ToggleCompletionResult {
+    /// `open_blockers` are the tasks `task` depends on that are not done yet.
+    /// Completing it anyway is allowed, but worth pointing out.
+    pub fn new(task: Task, open_blockers: &[Task]) -> Self {
+        let warning = if task.completed && !open_blockers.is_empty() {
+            let titles: Vec<String> = open_blockers.iter().map(|t| format!("\"{}\"", t.title)).collect();
+            Some(format!("\"{}\" was completed while still blocked by {}", task.title, titles.join(", ")))
+        } else {
+            None
+        };
+        ToggleCompletionResult { task, warning }
+    }
+}
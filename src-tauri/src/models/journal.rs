@@ -0,0 +1,62 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use crate::models::{Category, Reminder, Task};
+
+/// Everything needed to put a task back exactly as it was, including the
+/// rows that hang off it (tags travel inside `Task`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSnapshot {
+    pub task: Task,
+    pub reminders: Vec<Reminder>,
+    /// IDs of the tasks this one depends on
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+/// The state of one row at a point in time; `None` means "does not exist".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum EntityState {
+    Task { id: String, state: Option<Box<TaskSnapshot>> },
+    Category { id: String, state: Option<Category> },
+}
+
+impl EntityState {
+    pub fn key(&self) -> (&'static str, &str) {
+        match self {
+            EntityState::Task { id, .. } => ("task", id),
+            EntityState::Category { id, .. } => ("category", id),
+        }
+    }
+
+    pub fn key_owned(&self) -> (&'static str, String) {
+        let (kind, id) = self.key();
+        (kind, id.to_string())
+    }
+
+    /// The same entity in its "does not exist" state.
+    pub fn absent(&self) -> Self {
+        match self {
+            EntityState::Task { id, .. } => EntityState::Task { id: id.clone(), state: None },
+            EntityState::Category { id, .. } => EntityState::Category { id: id.clone(), state: None },
+        }
+    }
+}
+
+/// One undoable operation. Undo restores every entity to `before`, redo
+/// restores every entity to `after`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub label: String,
+    pub before: Vec<EntityState>,
+    pub after: Vec<EntityState>,
+    pub undone: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoState {
+    pub undo_label: Option<String>,
+    pub redo_label: Option<String>,
+}
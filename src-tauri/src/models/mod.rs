@@ -1,5 +1,23 @@
 pub mod task;
 pub mod category;
+pub mod recurrence;
+pub mod reminder;
+pub mod journal;
+pub mod quick_add;
+pub mod caldav;
+pub mod api;
+pub mod dependency;
+pub mod paging;
+pub mod bulk;
 
 pub use task::*;
 pub use category::*;
+pub use recurrence::*;
+pub use reminder::*;
+pub use journal::*;
+pub use quick_add::*;
+pub use caldav::*;
+pub use api::*;
+pub use dependency::*;
+pub use paging::*;
+pub use bulk::*;
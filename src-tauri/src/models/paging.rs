@@ -0,0 +1,102 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::models::Task;
+use crate::utils::AppError;
+
+/// Most tasks one page can hold
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// What a task list can be ordered by. Mirrors `SortField` in the frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    Title,
+    /// Tasks without a due date count as due last
+    DueDate,
+    CreatedAt,
+    UpdatedAt,
+    /// Low, Medium, High, rather than alphabetically
+    Priority,
+    Completed,
+    Position,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskSort {
+    pub field: SortField,
+    pub order: SortOrder,
+}
+
+impl Default for TaskSort {
+    /// Manual order
+    fn default() -> Self {
+        TaskSort { field: SortField::Position, order: SortOrder::Asc }
+    }
+}
+
+/// One page of a task list. Pass `next_cursor` back, with the same filter
+/// and sort, to get the page after it; it is `None` on the last page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskPage {
+    pub tasks: Vec<Task>,
+    pub next_cursor: Option<String>,
+}
+
+/// How many tasks each sidebar view holds, so the counts do not need every
+/// page of the list loaded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskCounts {
+    pub all: usize,
+    /// Due on the local calendar day
+    pub today: usize,
+    pub overdue: usize,
+    pub completed: usize,
+    pub pending: usize,
+    pub no_category: usize,
+    pub by_category: HashMap<String, usize>,
+}
+
+/// The value a task is sorted on, as stored in a cursor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SortKey {
+    Integer(i64),
+    Text(String),
+}
+
+/// Where a page ended: the sort key and ID of its last task. Paging on from
+/// there, rather than skipping a number of rows, keeps pages stable while
+/// tasks are added or removed and fast however deep the list is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskCursor {
+    pub sort: TaskSort,
+    pub key: SortKey,
+    pub id: String,
+}
+
+impl TaskCursor {
+    /// The opaque text handed to callers.
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Read a cursor back, checking it was made for `sort`.
+    pub fn decode(text: &str, sort: &TaskSort) -> Result<Self, AppError> {
+        let invalid = || AppError::InvalidInput("Invalid page cursor".to_string());
+        let json = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(text).map_err(|_| invalid())?;
+        let cursor: TaskCursor = serde_json::from_slice(&json).map_err(|_| invalid())?;
+        if cursor.sort != *sort {
+            return Err(AppError::InvalidInput("Page cursor was made for a different sort order".to_string()));
+        }
+        Ok(cursor)
+    }
+}
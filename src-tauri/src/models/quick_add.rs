@@ -0,0 +1,641 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use crate::models::{Category, CreateTaskRequest, Frequency, Priority, RecurrenceRule, Task};
+
+/// The kind of field a piece of quick-add input was recognised as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuickAddField {
+    Priority,
+    Tag,
+    Category,
+    DueDate,
+    DueTime,
+    Recurrence,
+}
+
+/// A recognised span of the input. `start` and `end` are character offsets,
+/// so the UI can highlight the parsed parts of the line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickAddMatch {
+    pub field: QuickAddField,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// How a quick-add line was understood. Everything not recognised as a field
+/// makes up the title.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickAddPreview {
+    pub title: String,
+    pub priority: Option<Priority>,
+    pub tags: Vec<String>,
+    pub category_id: Option<String>,
+    pub category_name: Option<String>,
+    pub due_date: Option<NaiveDateTime>, // UTC, like Task::due_date
+    pub due_has_time: bool,
+    pub recurrence: Option<RecurrenceRule>,
+    pub matches: Vec<QuickAddMatch>,
+    pub warnings: Vec<String>,
+}
+
+/// A task created by quick add, together with how its line was parsed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickAddResult {
+    pub task: Task,
+    pub preview: QuickAddPreview,
+}
+
+impl QuickAddPreview {
+    /// Parse a line such as
+    /// `Pay rent every month on the 1st !high #finance @Personal tomorrow 9am`.
+    ///
+    /// - `!high`, `!medium`, `!low` (or `!h`, `!1` ...) set the priority
+    /// - `#tag` adds a tag, `@Category` picks a category by name (names with
+    ///   spaces match greedily: `@Client Work`)
+    /// - dates: `today`, `tomorrow`, weekday names, `next friday`,
+    ///   `next week`, `next month`, `in 3 days`, `nov 5`, `5th november`,
+    ///   `2026-11-05`; times: `9am`, `9:30pm`, `21:00`, `noon`; each may be
+    ///   preceded by `on`, `at`, `by` or `due`
+    /// - recurrence: `daily`, `weekly`, `every weekday`, `every other week`,
+    ///   `every 3 days`, `every mon and thu`, `every month on the 1st`,
+    ///   optionally followed by `until <date>`
+    ///
+    /// `now` is the current local time; relative dates and times are resolved
+    /// in the local time zone. A date without a time is stored the same way
+    /// as a date picked in the task form (midnight UTC on that day).
+    pub fn parse(input: &str, now: NaiveDateTime, categories: &[Category]) -> Self {
+        let words = split_words(input);
+        let today = now.date();
+
+        let mut preview = QuickAddPreview {
+            title: String::new(),
+            priority: None,
+            tags: Vec::new(),
+            category_id: None,
+            category_name: None,
+            due_date: None,
+            due_has_time: false,
+            recurrence: None,
+            matches: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let mut title_words: Vec<&str> = Vec::new();
+        let mut date: Option<NaiveDate> = None;
+        let mut time: Option<NaiveTime> = None;
+
+        let mut i = 0;
+        while i < words.len() {
+            let word = &words[i];
+
+            let matched = if let Some(priority) = parse_priority(&word.lower).filter(|_| preview.priority.is_none()) {
+                preview.priority = Some(priority);
+                Some((QuickAddField::Priority, 1))
+            } else if let Some(tag) = word.text.strip_prefix('#').map(trim_punctuation).filter(|t| !t.is_empty()) {
+                if !preview.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    preview.tags.push(tag.to_string());
+                }
+                Some((QuickAddField::Tag, 1))
+            } else if word.text.starts_with('@') && word.text.len() > 1 && preview.category_id.is_none() {
+                match match_category(&words[i..], categories) {
+                    Some((len, category)) => {
+                        preview.category_id = Some(category.id.clone());
+                        preview.category_name = Some(category.name.clone());
+                        Some((QuickAddField::Category, len))
+                    }
+                    None => {
+                        preview.warnings.push(format!("No category named '{}'", trim_punctuation(&word.text[1..])));
+                        None
+                    }
+                }
+            } else if let Some((len, rule)) = parse_recurrence(&words[i..], today).filter(|_| preview.recurrence.is_none()) {
+                preview.recurrence = Some(rule);
+                Some((QuickAddField::Recurrence, len))
+            } else if let Some((len, d)) = with_connector(&words[i..], |w| parse_date(w, today)).filter(|_| date.is_none()) {
+                date = Some(d);
+                Some((QuickAddField::DueDate, len))
+            } else if let Some((len, t)) = with_connector(&words[i..], parse_time).filter(|_| time.is_none()) {
+                time = Some(t);
+                Some((QuickAddField::DueTime, len))
+            } else {
+                None
+            };
+
+            match matched {
+                Some((field, len)) => {
+                    let last = &words[i + len - 1];
+                    preview.matches.push(QuickAddMatch {
+                        field,
+                        text: input.chars().skip(word.start).take(last.end - word.start).collect(),
+                        start: word.start,
+                        end: last.end,
+                    });
+                    i += len;
+                }
+                None => {
+                    title_words.push(&word.text);
+                    i += 1;
+                }
+            }
+        }
+
+        preview.title = title_words.join(" ");
+
+        // A recurring task without an explicit date starts at its first occurrence
+        if date.is_none() {
+            date = preview.recurrence.as_ref().map(|rule| first_occurrence(rule, today));
+        }
+        if date.is_none() {
+            // A bare time means the next time the clock shows it
+            date = time.map(|t| if t > now.time() { today } else { today + Duration::days(1) });
+        }
+
+        preview.due_has_time = time.is_some();
+        preview.due_date = date.and_then(|d| match time {
+            Some(t) => Local
+                .from_local_datetime(&d.and_time(t))
+                .earliest()
+                .map(|dt| dt.naive_utc()),
+            None => d.and_hms_opt(0, 0, 0),
+        });
+
+        preview
+    }
+
+    pub fn to_create_request(&self) -> CreateTaskRequest {
+        CreateTaskRequest {
+            title: self.title.clone(),
+            description: None,
+            priority: self.priority.map(|p| p.to_string()),
+            due_date: self.due_date.map(|d| d.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+            category_id: self.category_id.clone(),
+            tags: Some(self.tags.clone()),
+            parent_id: None,
+            recurrence: self.recurrence.as_ref().map(|r| r.to_string()),
+        }
+    }
+}
+
+struct Word {
+    text: String,
+    /// Lowercased, without trailing punctuation, for matching keywords
+    lower: String,
+    start: usize,
+    end: usize,
+}
+
+fn split_words(input: &str) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+
+    for (i, c) in input.chars().enumerate() {
+        if c.is_whitespace() {
+            if let Some((start, text)) = current.take() {
+                words.push(Word { lower: trim_punctuation(&text).to_lowercase(), text, start, end: i });
+            }
+        } else {
+            current.get_or_insert_with(|| (i, String::new())).1.push(c);
+        }
+    }
+    if let Some((start, text)) = current {
+        let end = start + text.chars().count();
+        words.push(Word { lower: trim_punctuation(&text).to_lowercase(), text, start, end });
+    }
+
+    words
+}
+
+fn trim_punctuation(s: &str) -> &str {
+    s.trim_end_matches([',', '.', ';'])
+}
+
+fn parse_priority(word: &str) -> Option<Priority> {
+    match word.strip_prefix('!')? {
+        "high" | "h" | "1" => Some(Priority::High),
+        "medium" | "med" | "m" | "2" => Some(Priority::Medium),
+        "low" | "l" | "3" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// Match `@Name` against category names, preferring the longest name so
+/// `@Client Work` wins over `@Client`.
+fn match_category<'c>(words: &[Word], categories: &'c [Category]) -> Option<(usize, &'c Category)> {
+    let first = trim_punctuation(&words[0].text[1..]).replace('_', " ");
+    let mut best = None;
+    let mut candidate = String::new();
+
+    for (len, word) in words.iter().enumerate() {
+        if len == 0 {
+            candidate.push_str(&first);
+        } else {
+            if word.text.starts_with(['#', '@', '!']) {
+                break;
+            }
+            candidate.push(' ');
+            candidate.push_str(trim_punctuation(&word.text));
+        }
+        if let Some(category) = categories.iter().find(|c| c.name.eq_ignore_ascii_case(&candidate)) {
+            best = Some((len + 1, category));
+        }
+    }
+
+    best
+}
+
+/// Try `parse` at the start of `words`, or after one of the connector words
+/// `on`, `at`, `by` or `due`, which are then consumed as well.
+fn with_connector<T>(words: &[Word], parse: impl Fn(&[Word]) -> Option<(usize, T)>) -> Option<(usize, T)> {
+    if let Some(found) = parse(words) {
+        return Some(found);
+    }
+    match words.first().map(|w| w.lower.as_str()) {
+        Some("on" | "at" | "by" | "due") if words.len() > 1 => parse(&words[1..]).map(|(len, v)| (len + 1, v)),
+        _ => None,
+    }
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_month(word: &str) -> Option<u32> {
+    let months = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    let full = [
+        "january", "february", "march", "april", "may", "june",
+        "july", "august", "september", "october", "november", "december",
+    ];
+    months
+        .iter()
+        .position(|m| *m == word)
+        .or_else(|| full.iter().position(|m| *m == word))
+        .or_else(|| (word == "sept").then_some(8))
+        .map(|i| i as u32 + 1)
+}
+
+/// `1st`, `22nd`, `3rd`, `15th` or a plain day number.
+fn parse_ordinal(word: &str) -> Option<u32> {
+    let digits = word
+        .strip_suffix("st")
+        .or_else(|| word.strip_suffix("nd"))
+        .or_else(|| word.strip_suffix("rd"))
+        .or_else(|| word.strip_suffix("th"))
+        .unwrap_or(word);
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn parse_count(word: &str) -> Option<u32> {
+    match word {
+        "a" | "an" | "one" => Some(1),
+        "two" => Some(2),
+        "three" => Some(3),
+        "four" => Some(4),
+        "five" => Some(5),
+        "six" => Some(6),
+        _ => word.parse().ok().filter(|&n| n > 0),
+    }
+}
+
+fn next_weekday(today: NaiveDate, day: Weekday, include_today: bool) -> NaiveDate {
+    let ahead = (day.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    let ahead = if ahead == 0 && !include_today { 7 } else { ahead };
+    today + Duration::days(ahead as i64)
+}
+
+fn add_months(date: NaiveDate, months: u32) -> Option<NaiveDate> {
+    date.checked_add_months(chrono::Months::new(months))
+}
+
+fn parse_date(words: &[Word], today: NaiveDate) -> Option<(usize, NaiveDate)> {
+    let w = |i: usize| words.get(i).map(|w| w.lower.as_str()).unwrap_or("");
+
+    match w(0) {
+        "today" | "tod" => return Some((1, today)),
+        "tomorrow" | "tmr" | "tmrw" => return Some((1, today + Duration::days(1))),
+        "next" => {
+            if let Some(day) = parse_weekday(w(1)) {
+                return Some((2, next_weekday(today, day, false)));
+            }
+            return match w(1) {
+                "week" => Some((2, next_weekday(today, Weekday::Mon, false))),
+                "month" => Some((2, add_months(today.with_day(1)?, 1)?)),
+                _ => None,
+            };
+        }
+        "in" => {
+            let n = parse_count(w(1))?;
+            let date = match w(2) {
+                "day" | "days" => today + Duration::days(n as i64),
+                "week" | "weeks" => today + Duration::weeks(n as i64),
+                "month" | "months" => add_months(today, n)?,
+                "year" | "years" => add_months(today, n * 12)?,
+                _ => return None,
+            };
+            return Some((3, date));
+        }
+        _ => {}
+    }
+
+    if let Some(day) = parse_weekday(w(0)) {
+        return Some((1, next_weekday(today, day, true)));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(w(0), "%Y-%m-%d") {
+        return Some((1, date));
+    }
+
+    // `nov 5`, `november 5th 2027`, `5 nov`, `5th of november`
+    let (month, day, mut len) = if let (Some(month), Some(day)) = (parse_month(w(0)), parse_ordinal(w(1))) {
+        (month, day, 2)
+    } else if let (Some(day), Some(month)) = (parse_ordinal(w(0)), parse_month(w(1))) {
+        (month, day, 2)
+    } else if let (Some(day), "of", Some(month)) = (parse_ordinal(w(0)), w(1), parse_month(w(2))) {
+        (month, day, 3)
+    } else {
+        return None;
+    };
+
+    let explicit_year = w(len).parse::<i32>().ok().filter(|y| (1970..=9999).contains(y));
+    let date = match explicit_year {
+        Some(year) => {
+            len += 1;
+            NaiveDate::from_ymd_opt(year, month, day)?
+        }
+        None => {
+            // Dates without a year mean the next time that day comes round
+            let this_year = NaiveDate::from_ymd_opt(today.year(), month, day);
+            match this_year {
+                Some(d) if d >= today => d,
+                _ => NaiveDate::from_ymd_opt(today.year() + 1, month, day)?,
+            }
+        }
+    };
+
+    Some((len, date))
+}
+
+fn parse_time(words: &[Word]) -> Option<(usize, NaiveTime)> {
+    let first = words.first()?.lower.as_str();
+    if first == "noon" {
+        return NaiveTime::from_hms_opt(12, 0, 0).map(|t| (1, t));
+    }
+
+    // `9am` / `9:30pm`, or `9 am` split over two words
+    let (clock, suffix, len) = if let Some(clock) = first.strip_suffix("am") {
+        (clock, Some("am"), 1)
+    } else if let Some(clock) = first.strip_suffix("pm") {
+        (clock, Some("pm"), 1)
+    } else {
+        match words.get(1).map(|w| w.lower.as_str()) {
+            Some(s @ ("am" | "pm")) => (first, Some(s), 2),
+            _ => (first, None, 1),
+        }
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        // A bare number is only a time with am/pm, so "buy 9 apples" stays text
+        None if suffix.is_some() => (clock.parse::<u32>().ok()?, 0),
+        _ => return None,
+    };
+
+    let hour = match suffix {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some("am") => hour % 12,
+        Some(_) => hour % 12 + 12,
+        None => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, 0).map(|t| (len, t))
+}
+
+/// `mon and thu`, `mon, wed, fri`. Returns the number of words consumed.
+fn parse_weekday_list(words: &[Word]) -> Option<(usize, Vec<Weekday>)> {
+    let mut days = vec![parse_weekday(&words.first()?.lower)?];
+    let mut len = 1;
+
+    while let Some(word) = words.get(len) {
+        if word.lower == "and" {
+            match words.get(len + 1).and_then(|w| parse_weekday(&w.lower)) {
+                Some(day) => {
+                    days.push(day);
+                    len += 2;
+                }
+                None => break,
+            }
+        } else if words[len - 1].text.ends_with(',') {
+            match parse_weekday(&word.lower) {
+                Some(day) => {
+                    days.push(day);
+                    len += 1;
+                }
+                None => break,
+            }
+        } else {
+            break;
+        }
+    }
+
+    days.dedup();
+    Some((len, days))
+}
+
+fn parse_recurrence(words: &[Word], today: NaiveDate) -> Option<(usize, RecurrenceRule)> {
+    let w = |i: usize| words.get(i).map(|w| w.lower.as_str()).unwrap_or("");
+
+    let (mut len, mut rule) = match w(0) {
+        "daily" => (1, RecurrenceRule::new(Frequency::Daily)),
+        "weekly" => (1, RecurrenceRule::new(Frequency::Weekly)),
+        "monthly" => (1, RecurrenceRule::new(Frequency::Monthly)),
+        "yearly" | "annually" => (1, RecurrenceRule::new(Frequency::Yearly)),
+        "every" | "each" => {
+            let mut len = 1;
+            let interval = match w(1) {
+                "other" => Some(2),
+                word => parse_count(word).filter(|_| word != "a" && word != "an"),
+            };
+            if interval.is_some() {
+                len += 1;
+            }
+
+            let mut rule = match w(len) {
+                "day" | "days" => RecurrenceRule::new(Frequency::Daily),
+                "week" | "weeks" => RecurrenceRule::new(Frequency::Weekly),
+                "month" | "months" => RecurrenceRule::new(Frequency::Monthly),
+                "year" | "years" => RecurrenceRule::new(Frequency::Yearly),
+                "weekday" | "weekdays" => {
+                    let mut rule = RecurrenceRule::new(Frequency::Weekly);
+                    rule.by_weekday = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+                    rule
+                }
+                "weekend" | "weekends" => {
+                    let mut rule = RecurrenceRule::new(Frequency::Weekly);
+                    rule.by_weekday = vec![Weekday::Sat, Weekday::Sun];
+                    rule
+                }
+                _ => {
+                    let (days_len, days) = parse_weekday_list(&words[len.min(words.len())..])?;
+                    let mut rule = RecurrenceRule::new(Frequency::Weekly);
+                    rule.by_weekday = days;
+                    len += days_len - 1;
+                    rule
+                }
+            };
+            len += 1;
+            rule.interval = interval.unwrap_or(1);
+            (len, rule)
+        }
+        _ => return None,
+    };
+
+    // `every month on the 1st`, `weekly on mon and thu`
+    if rule.frequency == Frequency::Monthly {
+        let skip = match (w(len), w(len + 1)) {
+            ("on", "the") => 2,
+            ("on", _) | ("the", _) => 1,
+            _ => 0,
+        };
+        if let Some(day) = parse_ordinal(w(len + skip)).filter(|_| skip > 0) {
+            rule.by_month_day = Some(day);
+            len += skip + 1;
+        }
+    } else if rule.frequency == Frequency::Weekly && rule.by_weekday.is_empty() && w(len) == "on" {
+        if let Some((days_len, days)) = parse_weekday_list(&words[(len + 1).min(words.len())..]) {
+            rule.by_weekday = days;
+            len += days_len + 1;
+        }
+    }
+
+    if w(len) == "until" {
+        if let Some((date_len, until)) = parse_date(&words[len + 1..], today) {
+            rule.until = until.and_hms_opt(23, 59, 59);
+            len += date_len + 1;
+        }
+    }
+
+    Some((len, rule))
+}
+
+/// The first date on or after `today` that fits the rule.
+fn first_occurrence(rule: &RecurrenceRule, today: NaiveDate) -> NaiveDate {
+    match rule.frequency {
+        Frequency::Weekly if !rule.by_weekday.is_empty() => (0..7)
+            .map(|d| today + Duration::days(d))
+            .find(|d| rule.by_weekday.contains(&d.weekday()))
+            .unwrap_or(today),
+        Frequency::Monthly => match rule.by_month_day {
+            Some(day) => (0..12)
+                .filter_map(|m| add_months(today.with_day(1)?, m)?.with_day(day))
+                .find(|d| *d >= today)
+                .unwrap_or(today),
+            None => today,
+        },
+        _ => today,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CreateCategoryRequest;
+
+    /// Wednesday 14 October 2026, 10:00 local time
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 14).unwrap().and_hms_opt(10, 0, 0).unwrap()
+    }
+
+    fn categories() -> Vec<Category> {
+        ["Personal", "Client Work"]
+            .iter()
+            .map(|name| Category::new(CreateCategoryRequest { name: name.to_string(), color: "#000000".to_string(), icon: None }))
+            .collect()
+    }
+
+    fn parse(input: &str) -> QuickAddPreview {
+        QuickAddPreview::parse(input, now(), &categories())
+    }
+
+    /// A date-only due date, stored as midnight UTC
+    fn day(m: u32, d: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(2026, m, d).unwrap().and_hms_opt(0, 0, 0)
+    }
+
+    /// A local date and time, as stored (in UTC)
+    fn local(m: u32, d: u32, h: u32, min: u32) -> Option<NaiveDateTime> {
+        let local = NaiveDate::from_ymd_opt(2026, m, d).unwrap().and_hms_opt(h, min, 0).unwrap();
+        Local.from_local_datetime(&local).earliest().map(|dt| dt.naive_utc())
+    }
+
+    #[test]
+    fn parses_every_field() {
+        let categories = categories();
+        let input = "Pay rent every month on the 1st !high #finance @Personal tomorrow 9am";
+        let preview = QuickAddPreview::parse(input, now(), &categories);
+        assert_eq!(preview.title, "Pay rent");
+        assert_eq!(preview.priority, Some(Priority::High));
+        assert_eq!(preview.tags, ["finance"]);
+        assert_eq!(preview.category_id.as_ref(), Some(&categories[0].id));
+        assert_eq!(preview.recurrence.map(|r| r.to_string()).as_deref(), Some("FREQ=MONTHLY;BYMONTHDAY=1"));
+        assert_eq!(preview.due_date, local(10, 15, 9, 0));
+        assert!(preview.due_has_time);
+        assert!(preview.warnings.is_empty());
+
+        let fields: Vec<(QuickAddField, &str, usize, usize)> =
+            preview.matches.iter().map(|m| (m.field, m.text.as_str(), m.start, m.end)).collect();
+        assert_eq!(
+            fields,
+            [
+                (QuickAddField::Recurrence, "every month on the 1st", 9, 31),
+                (QuickAddField::Priority, "!high", 32, 37),
+                (QuickAddField::Tag, "#finance", 38, 46),
+                (QuickAddField::Category, "@Personal", 47, 56),
+                (QuickAddField::DueDate, "tomorrow", 57, 65),
+                (QuickAddField::DueTime, "9am", 66, 69),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_relative_dates() {
+        assert_eq!(parse("Call Ana next friday").due_date, day(10, 16));
+        assert_eq!(parse("Email Ana in 3 days").due_date, day(10, 17));
+        assert_eq!(parse("Renew passport nov 5").due_date, day(11, 5));
+        assert_eq!(parse("Renew passport 2026-11-05").due_date, day(11, 5));
+        // A bare time is the next time the clock shows it
+        assert_eq!(parse("Buy milk 11am").due_date, local(10, 14, 11, 0));
+        assert_eq!(parse("Buy milk 8am").due_date, local(10, 15, 8, 0));
+    }
+
+    #[test]
+    fn recurring_task_starts_at_its_first_occurrence() {
+        let preview = parse("Water plants every mon and thu until 2026-12-01");
+        assert_eq!(preview.title, "Water plants");
+        assert_eq!(preview.due_date, day(10, 15));
+        assert_eq!(
+            preview.recurrence.map(|r| r.to_string()).as_deref(),
+            Some("FREQ=WEEKLY;BYDAY=MO,TH;UNTIL=20261201T235959Z")
+        );
+    }
+
+    #[test]
+    fn categories_match_by_name() {
+        let preview = parse("Call Ana @Client Work");
+        assert_eq!(preview.title, "Call Ana");
+        assert_eq!(preview.category_name.as_deref(), Some("Client Work"));
+
+        // An unknown category stays in the title
+        let preview = parse("Email @Nobody");
+        assert_eq!(preview.title, "Email @Nobody");
+        assert_eq!(preview.category_id, None);
+        assert_eq!(preview.warnings, ["No category named 'Nobody'"]);
+    }
+}
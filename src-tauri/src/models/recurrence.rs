@@ -0,0 +1,324 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A subset of the RFC 5545 RRULE grammar: FREQ, INTERVAL, BYDAY (plain
+/// weekdays only), BYMONTHDAY (a single positive day), UNTIL and COUNT.
+///
+/// Rules are stored and exchanged with the frontend in their textual form,
+/// e.g. `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;COUNT=10`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub by_weekday: Vec<Weekday>,
+    pub by_month_day: Option<u32>,
+    pub until: Option<NaiveDateTime>,
+    /// Number of occurrences left in the series, including the current one
+    pub count: Option<u32>,
+}
+
+impl RecurrenceRule {
+    pub fn new(frequency: Frequency) -> Self {
+        RecurrenceRule {
+            frequency,
+            interval: 1,
+            by_weekday: Vec::new(),
+            by_month_day: None,
+            until: None,
+            count: None,
+        }
+    }
+
+    /// Compute the occurrence that follows `current`, or `None` when the
+    /// series is exhausted by COUNT or UNTIL. The series advances in local
+    /// wall-clock time, so a task due at 09:00 stays at 09:00 across a
+    /// daylight saving change.
+    pub fn next_after(&self, current: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.next_after_in(&Local, current)
+    }
+
+    fn next_after_in<Tz: TimeZone>(&self, tz: &Tz, current: NaiveDateTime) -> Option<NaiveDateTime> {
+        if matches!(self.count, Some(n) if n <= 1) {
+            return None;
+        }
+
+        let local = to_local(tz, current);
+        let interval = self.interval.max(1);
+        let date = local.date();
+        let next_date = match self.frequency {
+            Frequency::Daily => date + Duration::days(interval as i64),
+            Frequency::Weekly => self.next_weekly(date, interval),
+            Frequency::Monthly => {
+                let day = self.by_month_day.unwrap_or(date.day());
+                add_months(date, interval, day)?
+            }
+            Frequency::Yearly => add_months(date, interval * 12, date.day())?,
+        };
+        let next = next_date.and_time(local.time());
+        let next = if is_date_only(current) { next } else { from_local(tz, next) };
+
+        match self.until {
+            Some(until) if next > until => None,
+            _ => Some(next),
+        }
+    }
+
+    /// The rule that governs the series once the occurrence due at `current`
+    /// is done. A monthly series that had to fall back to the end of a
+    /// shorter month is pinned to its day, so Jan 31 is followed by Feb 28
+    /// and then Mar 31 rather than Mar 28.
+    pub fn advanced(&self, current: NaiveDateTime) -> Self {
+        self.advanced_in(&Local, current)
+    }
+
+    fn advanced_in<Tz: TimeZone>(&self, tz: &Tz, current: NaiveDateTime) -> Self {
+        let mut rule = self.clone();
+        rule.count = self.count.map(|n| n.saturating_sub(1));
+        if self.frequency == Frequency::Monthly && self.by_month_day.is_none() {
+            let day = to_local(tz, current).day();
+            if self.next_after_in(tz, current).map(|next| to_local(tz, next).day()) != Some(day) {
+                rule.by_month_day = Some(day);
+            }
+        }
+        rule
+    }
+
+    fn next_weekly(&self, date: NaiveDate, interval: u32) -> NaiveDate {
+        if self.by_weekday.is_empty() {
+            return date + Duration::weeks(interval as i64);
+        }
+
+        let mut days: Vec<u32> = self
+            .by_weekday
+            .iter()
+            .map(|d| d.num_days_from_monday())
+            .collect();
+        days.sort_unstable();
+        days.dedup();
+
+        // A later weekday in the same week comes first
+        let today = date.weekday().num_days_from_monday();
+        if let Some(day) = days.iter().find(|&&d| d > today) {
+            return date + Duration::days((day - today) as i64);
+        }
+
+        // Otherwise jump to the first listed weekday `interval` weeks on
+        let week_start = date - Duration::days(today as i64);
+        week_start + Duration::weeks(interval as i64) + Duration::days(days[0] as i64)
+    }
+}
+
+/// Due dates are stored in UTC, except that a date without a time of day is
+/// kept as midnight UTC and has no time zone to convert.
+fn is_date_only(due: NaiveDateTime) -> bool {
+    due.time() == NaiveTime::MIN
+}
+
+fn to_local<Tz: TimeZone>(tz: &Tz, utc: NaiveDateTime) -> NaiveDateTime {
+    if is_date_only(utc) {
+        return utc;
+    }
+    tz.from_utc_datetime(&utc).naive_local()
+}
+
+/// Back from a local time to UTC. A wall-clock time skipped by a DST change
+/// moves to the hour after it.
+fn from_local<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> NaiveDateTime {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map(|t| t.naive_utc())
+        .unwrap_or(local)
+}
+
+/// Move `date` forward by `months`, landing on `day` or the last day of the
+/// target month when it is shorter.
+fn add_months(date: NaiveDate, months: u32, day: u32) -> Option<NaiveDate> {
+    let total = date.year() * 12 + date.month0() as i32 + months as i32;
+    let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+    let last_day = (28..=31)
+        .rev()
+        .find(|&d| NaiveDate::from_ymd_opt(year, month, d).is_some())?;
+    NaiveDate::from_ymd_opt(year, month, day.min(last_day))
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    match code {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let freq = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={}", freq)?;
+        if self.interval > 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_weekday.is_empty() {
+            let days: Vec<&str> = self.by_weekday.iter().map(|d| weekday_code(*d)).collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(day) = self.by_month_day {
+            write!(f, ";BYMONTHDAY={}", day)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+
+        let mut frequency = None;
+        let mut rule = RecurrenceRule::new(Frequency::Daily);
+
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Malformed recurrence part '{}'", part))?;
+            let invalid = || format!("Invalid value '{}' for {}", value, key);
+
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(invalid()),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?
+                }
+                "BYDAY" => {
+                    rule.by_weekday = value
+                        .split(',')
+                        .map(|d| parse_weekday(&d.to_ascii_uppercase()))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(invalid)?
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = Some(
+                        value.parse().ok().filter(|d| (1..=31).contains(d)).ok_or_else(invalid)?,
+                    )
+                }
+                "UNTIL" => {
+                    let until = NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S")
+                        .or_else(|_| {
+                            NaiveDate::parse_from_str(value, "%Y%m%d")
+                                .map(|d| d.and_hms_opt(23, 59, 59).unwrap_or_default())
+                        })
+                        .map_err(|_| invalid())?;
+                    rule.until = Some(until);
+                }
+                "COUNT" => {
+                    rule.count = Some(value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?)
+                }
+                _ => return Err(format!("Unsupported recurrence part '{}'", key)),
+            }
+        }
+
+        rule.frequency = frequency.ok_or_else(|| "Recurrence rule is missing FREQ".to_string())?;
+        Ok(rule)
+    }
+}
+
+impl Serialize for RecurrenceRule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for RecurrenceRule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_time(NaiveTime::MIN)
+    }
+
+    #[test]
+    fn month_end_series_returns_to_its_day() {
+        let mut rule: RecurrenceRule = "FREQ=MONTHLY".parse().unwrap();
+        let mut due = date(2027, 1, 31);
+        let mut dues = Vec::new();
+        for _ in 0..3 {
+            let next = rule.next_after(due).unwrap();
+            rule = rule.advanced(due);
+            due = next;
+            dues.push(due);
+        }
+        assert_eq!(dues, [date(2027, 2, 28), date(2027, 3, 31), date(2027, 4, 30)]);
+    }
+
+    #[test]
+    fn mid_month_series_is_left_unpinned() {
+        let rule: RecurrenceRule = "FREQ=MONTHLY".parse().unwrap();
+        assert_eq!(rule.advanced(date(2027, 1, 15)).to_string(), "FREQ=MONTHLY");
+    }
+
+    #[test]
+    fn timed_series_advances_in_local_time() {
+        // 01:30 on the 31st in UTC+2 is still the 30th in UTC
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let due = NaiveDate::from_ymd_opt(2027, 1, 30).unwrap().and_hms_opt(23, 30, 0).unwrap();
+        let rule: RecurrenceRule = "FREQ=MONTHLY".parse().unwrap();
+
+        let next = rule.next_after_in(&tz, due).unwrap();
+        assert_eq!(next, NaiveDate::from_ymd_opt(2027, 2, 27).unwrap().and_hms_opt(23, 30, 0).unwrap());
+        assert_eq!(rule.advanced_in(&tz, due).by_month_day, Some(31));
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::{Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// When a reminder fires, relative to its task's due date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ReminderOffset {
+    /// A fixed number of minutes before the due date
+    BeforeDue { minutes: i64 },
+    /// A wall-clock time (local time zone) on the day the task is due
+    OnDueDay { hour: u32, minute: u32 },
+}
+
+impl ReminderOffset {
+    /// Storage form used by the `task_reminders` table: a kind plus a single
+    /// minutes value (minutes before due, or minutes after local midnight).
+    pub fn to_columns(&self) -> (&'static str, i64) {
+        match *self {
+            ReminderOffset::BeforeDue { minutes } => ("before_due", minutes),
+            ReminderOffset::OnDueDay { hour, minute } => ("on_due_day", (hour * 60 + minute) as i64),
+        }
+    }
+
+    pub fn from_columns(kind: &str, minutes: i64) -> Self {
+        match kind {
+            "on_due_day" => ReminderOffset::OnDueDay {
+                hour: (minutes / 60) as u32,
+                minute: (minutes % 60) as u32,
+            },
+            _ => ReminderOffset::BeforeDue { minutes },
+        }
+    }
+
+    /// The UTC instant this offset points at for a task due at `due` (UTC).
+    pub fn fire_time(&self, due: NaiveDateTime) -> Option<NaiveDateTime> {
+        match *self {
+            ReminderOffset::BeforeDue { minutes } => Some(due - Duration::minutes(minutes)),
+            ReminderOffset::OnDueDay { hour, minute } => {
+                let due_day = Local.from_utc_datetime(&due).date_naive();
+                let local = due_day.and_time(NaiveTime::from_hms_opt(hour, minute, 0)?);
+                Local
+                    .from_local_datetime(&local)
+                    .earliest()
+                    .map(|dt| dt.naive_utc())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: String,
+    pub task_id: String,
+    pub offset: ReminderOffset,
+    /// The due date this reminder last fired for; a changed due date re-arms it
+    pub fired_for_due: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Reminder {
+    pub fn new(task_id: String, offset: ReminderOffset) -> Self {
+        Reminder {
+            id: uuid::Uuid::new_v4().to_string(),
+            task_id,
+            offset,
+            fired_for_due: None,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+/// A reminder whose fire time has passed, with the task details needed to
+/// show a notification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DueReminder {
+    pub reminder_id: String,
+    pub task_id: String,
+    pub task_title: String,
+    pub due_date: NaiveDateTime,
+    pub fire_at: NaiveDateTime,
+}
@@ -1,5 +1,6 @@
 use chrono::{NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
+use crate::models::RecurrenceRule;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -12,11 +13,24 @@ pub struct Task {
     pub category_id: Option<String>,
     pub tags: Vec<String>,
     pub parent_id: Option<String>, // For subtasks
+    pub recurrence: Option<RecurrenceRule>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>, // Set while the task is in the trash
+    #[serde(default)]
+    pub position: i64, // Order among tasks with the same parent and category
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// A task in a subtree listing, along with where it sits in the tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskNode {
+    #[serde(flatten)]
+    pub task: Task,
+    pub depth: u32, // 0 for the top level of the listing
+    pub path: Vec<String>, // IDs from the top level down to this task, inclusive
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Priority {
     Low,
     Medium,
@@ -58,6 +72,7 @@ pub struct CreateTaskRequest {
     pub category_id: Option<String>,
     pub tags: Option<Vec<String>>,
     pub parent_id: Option<String>,
+    pub recurrence: Option<String>, // RRULE text, e.g. "FREQ=WEEKLY;BYDAY=MO"
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +85,17 @@ pub struct UpdateTaskRequest {
     pub category_id: Option<String>,
     pub tags: Option<Vec<String>>,
     pub parent_id: Option<String>,
+    pub recurrence: Option<String>, // RRULE text, empty string means stop recurring
+}
+
+/// A full-text search hit. Matched terms in `title_highlight` and
+/// `description_snippet` are wrapped in `\u{2}` ... `\u{3}` markers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSearchResult {
+    pub task: Task,
+    pub score: f64, // Higher is more relevant
+    pub title_highlight: String,
+    pub description_snippet: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -126,9 +152,34 @@ impl TaskFilter {
     }
 }
 
+/// RRULE text from a request, where an empty string means no rule.
+fn parse_recurrence(text: &str) -> Result<Option<RecurrenceRule>, String> {
+    if text.is_empty() {
+        return Ok(None);
+    }
+    text.parse().map(Some).map_err(|e| format!("Invalid recurrence: {}", e))
+}
+
 impl Task {
-    pub fn new(request: CreateTaskRequest) -> Self {
+    /// Where a task goes among its siblings until it is moved by hand:
+    /// top-level tasks newest first and subtasks oldest first, the order
+    /// they were always listed in.
+    pub fn initial_position(created_at: NaiveDateTime, is_subtask: bool) -> i64 {
+        let millis = created_at.and_utc().timestamp_millis();
+        if is_subtask {
+            millis
+        } else {
+            -millis
+        }
+    }
+
+    /// Fails if the request's recurrence is not a valid RRULE.
+    pub fn new(request: CreateTaskRequest) -> Result<Self, String> {
         let now = Utc::now().naive_utc();
+        let recurrence = match request.recurrence {
+            Some(text) => parse_recurrence(&text)?,
+            None => None,
+        };
         
         // Parse due_date from string
         let parsed_due_date = request.due_date.and_then(|d| {
@@ -148,7 +199,9 @@ impl Task {
                 .map(|dt| dt.naive_utc())
         });
         
-        Task {
+        let position = Task::initial_position(now, request.parent_id.is_some());
+        
+        Ok(Task {
             id: uuid::Uuid::new_v4().to_string(),
             title: request.title,
             description: request.description,
@@ -158,12 +211,19 @@ impl Task {
             category_id: request.category_id,
             tags: request.tags.unwrap_or_default(),
             parent_id: request.parent_id,
+            recurrence,
             created_at: now,
             updated_at: now,
-        }
+            deleted_at: None,
+            position,
+        })
     }
 
-    pub fn update(&mut self, request: UpdateTaskRequest) {
+    /// Fails, leaving the task untouched, if the request's recurrence is not
+    /// a valid RRULE.
+    pub fn update(&mut self, request: UpdateTaskRequest) -> Result<(), String> {
+        let recurrence = request.recurrence.as_deref().map(parse_recurrence).transpose()?;
+        
         if let Some(title) = request.title {
             self.title = title;
         }
@@ -181,7 +241,6 @@ impl Task {
             if due_date_str.is_empty() {
                 // Empty string means clear the due date
                 self.due_date = None;
-                println!("Clearing due_date");
             } else {
                 // Parse the date string
                 use chrono::DateTime;
@@ -204,7 +263,31 @@ impl Task {
         if let Some(parent_id) = request.parent_id {
             self.parent_id = Some(parent_id);
         }
+        if let Some(recurrence) = recurrence {
+            self.recurrence = recurrence;
+        }
         
         self.updated_at = Utc::now().naive_utc();
+        Ok(())
+    }
+
+    /// Build the next instance of a recurring task: a fresh, incomplete copy
+    /// due at the following occurrence. Returns `None` for one-off tasks and
+    /// for series that have run out.
+    pub fn next_occurrence(&self) -> Option<Task> {
+        let rule = self.recurrence.as_ref()?;
+        let now = Utc::now().naive_utc();
+        let current = self.due_date.unwrap_or(now);
+        let next_due = rule.next_after(current)?;
+
+        Some(Task {
+            id: uuid::Uuid::new_v4().to_string(),
+            completed: false,
+            due_date: Some(next_due),
+            recurrence: Some(rule.advanced(current)),
+            created_at: now,
+            updated_at: now,
+            ..self.clone()
+        })
     }
 }
@@ -0,0 +1,93 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use crate::database::Database;
+use crate::interop::{caldav, markdown, todotxt};
+
+/// How often the chosen todo.txt file and Markdown vault are synced
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Emitted with the sync report whenever a background todo.txt sync changed
+/// tasks, so the window can reload them
+pub const TODOTXT_SYNCED_EVENT: &str = "todotxt-synced";
+
+/// Emitted with the sync report whenever a background vault sync changed tasks
+pub const MARKDOWN_SYNCED_EVENT: &str = "markdown-synced";
+
+/// The CalDAV server is synced once every this many polls, to spare it
+const CALDAV_POLLS: u32 = 5;
+
+/// Emitted with the sync report whenever a background CalDAV sync changed tasks
+pub const CALDAV_SYNCED_EVENT: &str = "caldav-synced";
+
+/// Start the background task that keeps the todo.txt file, Markdown vault
+/// and CalDAV account chosen in settings in sync. Each does nothing while
+/// turned off, and one failing does not stop the others.
+pub fn start_file_sync(app: &tauri::App) {
+    let handle = app.handle().clone();
+
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        let mut polls = 0u32;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = sync_todotxt(&handle) {
+                eprintln!("Failed to sync todo.txt: {}", e);
+            }
+            if let Err(e) = sync_markdown(&handle) {
+                eprintln!("Failed to sync Markdown vault: {}", e);
+            }
+            if polls % CALDAV_POLLS == 0 {
+                // The sync waits on the server, so it gets a blocking thread
+                let handle = handle.clone();
+                let _ = tauri::async_runtime::spawn_blocking(move || {
+                    if let Err(e) = sync_caldav(&handle) {
+                        eprintln!("Failed to sync with CalDAV server: {}", e);
+                    }
+                })
+                .await;
+            }
+            polls = polls.wrapping_add(1);
+        }
+    });
+}
+
+fn sync_todotxt(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let db = app.state::<Mutex<Database>>();
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+
+    if let Some(report) = todotxt::sync_configured(&db_lock.connection)? {
+        if report.changed_app() {
+            app.emit(TODOTXT_SYNCED_EVENT, &report)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn sync_markdown(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let db = app.state::<Mutex<Database>>();
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+
+    if let Some(report) = markdown::sync_configured(&db_lock.connection)? {
+        if report.changed_app() {
+            app.emit(MARKDOWN_SYNCED_EVENT, &report)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Locks the database only between requests to the server, not for the
+/// whole sync.
+fn sync_caldav(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let db = app.state::<Mutex<Database>>();
+
+    if let Some(report) = caldav::sync_configured(&db)? {
+        if report.changed_app() {
+            app.emit(CALDAV_SYNCED_EVENT, &report)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use thiserror::Error;
 
 pub mod window_state;
+pub mod reminder_scheduler;
+pub mod file_sync;
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -18,6 +20,19 @@ pub enum AppError {
     
     #[error("Invalid input: {0}")]
     InvalidInput(String),
+    
+    #[error("Migration {version} ({description}) failed: {source}")]
+    Migration {
+        version: u32,
+        description: &'static str,
+        source: rusqlite::Error,
+    },
+    
+    #[error("Database schema version {found} is newer than this app supports ({supported}); please update Pluto")]
+    SchemaTooNew { found: u32, supported: u32 },
+    
+    #[error("Server error: {0}")]
+    Server(String),
 }
 
 impl From<AppError> for String {
@@ -0,0 +1,93 @@
+use chrono::{Local, TimeZone, Utc};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+use crate::database::{Database, ReminderRepository, TaskRepository};
+use crate::models::DueReminder;
+
+/// How often the scheduler looks for reminders that have come due
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// When more reminders than this are due at once (usually after the app has
+/// been closed for a while) a single summary notification is shown instead
+const MAX_INDIVIDUAL_NOTIFICATIONS: usize = 3;
+
+/// Tasks past the trash retention period are purged once every this many
+/// polls (about once an hour), so the trash keeps to it while the app stays open
+const TRASH_PURGE_POLLS: u32 = 120;
+
+/// Start the background task that delivers reminder notifications. It runs
+/// independently of the main window, so reminders still arrive while the
+/// window is hidden. The first pass runs immediately to catch up on
+/// reminders missed while the app was not running. The same task also keeps
+/// the trash to its retention period.
+pub fn start_reminder_scheduler(app: &tauri::App) {
+    let handle = app.handle().clone();
+
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        let mut polls = 0u32;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = fire_due_reminders(&handle) {
+                eprintln!("Failed to deliver reminders: {}", e);
+            }
+            if polls % TRASH_PURGE_POLLS == 0 {
+                if let Err(e) = purge_expired_trash(&handle) {
+                    eprintln!("Failed to purge expired trash: {}", e);
+                }
+            }
+            polls = polls.wrapping_add(1);
+        }
+    });
+}
+
+fn purge_expired_trash(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let db = app.state::<Mutex<Database>>();
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    TaskRepository::new(&db_lock.connection).purge_expired_trash()?;
+    Ok(())
+}
+
+fn fire_due_reminders(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let db = app.state::<Mutex<Database>>();
+    let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let reminder_repo = ReminderRepository::new(&db_lock.connection);
+
+    let due = reminder_repo.get_due(Utc::now().naive_utc())?;
+    if due.is_empty() {
+        return Ok(());
+    }
+
+    show_notifications(app, &due)?;
+
+    for reminder in &due {
+        reminder_repo.mark_fired(&reminder.reminder_id, reminder.due_date)?;
+    }
+
+    Ok(())
+}
+
+fn show_notifications(app: &AppHandle, due: &[DueReminder]) -> Result<(), Box<dyn std::error::Error>> {
+    if due.len() > MAX_INDIVIDUAL_NOTIFICATIONS {
+        let titles: Vec<&str> = due.iter().map(|r| r.task_title.as_str()).take(MAX_INDIVIDUAL_NOTIFICATIONS).collect();
+        app.notification()
+            .builder()
+            .title(format!("{} tasks need your attention", due.len()))
+            .body(format!("{}, …", titles.join(", ")))
+            .show()?;
+        return Ok(());
+    }
+
+    for reminder in due {
+        let due_local = Local.from_utc_datetime(&reminder.due_date);
+        app.notification()
+            .builder()
+            .title(&reminder.task_title)
+            .body(format!("Due {}", due_local.format("%a %b %-d, %H:%M")))
+            .show()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,463 @@
+use chrono::NaiveDate;
+use pluto_todo_lib::database::{CategoryRepository, Database, Journal, TaskRepository};
+use pluto_todo_lib::interop::caldav::{sync_account, CalDavSyncReport};
+use pluto_todo_lib::interop::caldav_client::{DavRequest, DavResponse, DavTransport};
+use pluto_todo_lib::models::{CalDavAccount, Category, ConflictPolicy, CreateCategoryRequest, CreateTaskRequest, Task};
+use pluto_todo_lib::utils::AppError;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+const ORIGIN: &str = "http://dav.test";
+const HOME: &str = "/dav/";
+
+struct Item {
+    etag: String,
+    data: String,
+}
+
+struct Calendar {
+    name: String,
+    /// By path
+    items: BTreeMap<String, Item>,
+}
+
+/// An in-memory CalDAV server. The account URL is the calendar home, and
+/// a sync token is the number of changes made so far.
+#[derive(Default)]
+struct Server {
+    /// By path
+    calendars: BTreeMap<String, Calendar>,
+    /// (calendar, item) of every item written or deleted
+    changes: Vec<(String, String)>,
+    etags: u32,
+    /// Written just before the next PUT to that path, as if another client
+    /// got there first
+    edit_before_put: Option<(String, String)>,
+    /// Method, path and body of every request
+    requests: Vec<(String, String, String)>,
+}
+
+struct MockServer(RefCell<Server>);
+
+impl DavTransport for MockServer {
+    fn send(&self, request: DavRequest) -> Result<DavResponse, AppError> {
+        Ok(self.0.borrow_mut().handle(request))
+    }
+}
+
+fn multistatus(responses: &str) -> DavResponse {
+    DavResponse {
+        status: 207,
+        headers: Vec::new(),
+        body: format!(
+            r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">{}</d:multistatus>"#,
+            responses
+        ),
+    }
+}
+
+fn status(status: u16) -> DavResponse {
+    DavResponse { status, headers: Vec::new(), body: String::new() }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// The text of every `<open>...</close>` element in `text`
+fn elements<'a>(text: &'a str, open: &str, close: &'a str) -> Vec<&'a str> {
+    text.split(open).skip(1).filter_map(|rest| rest.split(close).next()).collect()
+}
+
+impl Server {
+    fn calendar_of(&self, path: &str) -> Option<String> {
+        self.calendars.keys().find(|c| path.starts_with(c.as_str()) && path.len() > c.len()).cloned()
+    }
+
+    fn write(&mut self, path: &str, data: &str) -> String {
+        let calendar = self.calendar_of(path).expect("no calendar for the item");
+        self.etags += 1;
+        let etag = format!("\"{}\"", self.etags);
+        let item = Item { etag: etag.clone(), data: data.to_string() };
+        self.calendars.get_mut(&calendar).unwrap().items.insert(path.to_string(), item);
+        self.changes.push((calendar, path.to_string()));
+        etag
+    }
+
+    fn remove(&mut self, path: &str) {
+        let calendar = self.calendar_of(path).expect("no calendar for the item");
+        self.calendars.get_mut(&calendar).unwrap().items.remove(path);
+        self.changes.push((calendar, path.to_string()));
+    }
+
+    fn item(&self, path: &str) -> Option<&Item> {
+        self.calendars.get(&self.calendar_of(path)?)?.items.get(path)
+    }
+
+    fn handle(&mut self, request: DavRequest) -> DavResponse {
+        let path = request.url.strip_prefix(ORIGIN).expect("request to another server").to_string();
+        let body = request.body.clone().unwrap_or_default();
+        let header = |name: &str| {
+            request
+                .headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+        };
+        self.requests.push((request.method.clone(), path.clone(), body.clone()));
+
+        match request.method.as_str() {
+            // No principal or home set: the account URL is the home
+            "PROPFIND" if path == HOME && header("Depth").as_deref() == Some("0") => multistatus(""),
+            "PROPFIND" if path == HOME => {
+                let mut responses = String::new();
+                for (path, calendar) in &self.calendars {
+                    responses += &format!(
+                        "<d:response><d:href>{}</d:href><d:propstat><d:prop>\
+                         <d:resourcetype><d:collection/><c:calendar/></d:resourcetype>\
+                         <d:displayname>{}</d:displayname>\
+                         <c:supported-calendar-component-set><c:comp name=\"VTODO\"/></c:supported-calendar-component-set>\
+                         </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+                        path,
+                        escape(&calendar.name)
+                    );
+                }
+                multistatus(&responses)
+            }
+            "MKCALENDAR" => {
+                let name = elements(&body, "<d:displayname>", "</d:displayname>")[0].to_string();
+                self.calendars.insert(path, Calendar { name, items: BTreeMap::new() });
+                status(201)
+            }
+            "REPORT" if body.contains("sync-collection") => {
+                let token = elements(&body, "<d:sync-token>", "</d:sync-token>")[0];
+                let since: usize = token.parse().unwrap_or(0);
+                let Some(calendar) = self.calendars.get(&path) else { return status(404) };
+                let mut paths: Vec<&String> = if token.is_empty() {
+                    calendar.items.keys().collect()
+                } else {
+                    self.changes[since..].iter().filter(|(c, _)| *c == path).map(|(_, item)| item).collect()
+                };
+                paths.sort();
+                paths.dedup();
+
+                let mut responses = String::new();
+                for item in paths {
+                    responses += &match calendar.items.get(item) {
+                        Some(Item { etag, .. }) => format!(
+                            "<d:response><d:href>{}</d:href><d:propstat><d:prop><d:getetag>{}</d:getetag></d:prop>\
+                             <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+                            item,
+                            escape(etag)
+                        ),
+                        None => format!(
+                            "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+                            item
+                        ),
+                    };
+                }
+                responses += &format!("<d:sync-token>{}</d:sync-token>", self.changes.len());
+                multistatus(&responses)
+            }
+            "REPORT" => {
+                let mut responses = String::new();
+                for href in elements(&body, "<d:href>", "</d:href>") {
+                    if let Some(Item { etag, data }) = self.item(href) {
+                        responses += &format!(
+                            "<d:response><d:href>{}</d:href><d:propstat><d:prop><d:getetag>{}</d:getetag>\
+                             <c:calendar-data>{}</c:calendar-data></d:prop>\
+                             <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+                            href,
+                            escape(etag),
+                            escape(data)
+                        );
+                    }
+                }
+                multistatus(&responses)
+            }
+            "PUT" => {
+                if let Some((edited, data)) = self.edit_before_put.take_if(|(edited, _)| *edited == path) {
+                    self.write(&edited, &data);
+                }
+                let current = self.item(&path).map(|item| item.etag.clone());
+                let allowed = match (header("If-Match"), header("If-None-Match")) {
+                    (Some(etag), _) => current.as_ref() == Some(&etag),
+                    (None, Some(_)) => current.is_none(),
+                    (None, None) => true,
+                };
+                if !allowed {
+                    return status(412);
+                }
+                let etag = self.write(&path, &body);
+                DavResponse {
+                    status: if current.is_some() { 204 } else { 201 },
+                    headers: vec![("ETag".to_string(), etag)],
+                    body: String::new(),
+                }
+            }
+            "DELETE" => {
+                let Some(current) = self.item(&path).map(|item| item.etag.clone()) else { return status(404) };
+                if header("If-Match").is_some_and(|etag| etag != current) {
+                    return status(412);
+                }
+                self.remove(&path);
+                status(204)
+            }
+            _ => status(405),
+        }
+    }
+}
+
+fn vtodo(uid: &str, summary: &str, extra: &str, modified: &str) -> String {
+    format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\nBEGIN:VTODO\r\nUID:{}\r\nDTSTAMP:20261001T000000Z\r\n\
+         LAST-MODIFIED:{}\r\nSUMMARY:{}\r\n{}END:VTODO\r\nEND:VCALENDAR\r\n",
+        uid, modified, summary, extra
+    )
+}
+
+/// A server with a "Home" calendar holding a task and its subtask
+fn server() -> MockServer {
+    let mut server = Server::default();
+    let home = Calendar { name: "Home".to_string(), items: BTreeMap::new() };
+    server.calendars.insert("/dav/home/".to_string(), home);
+    server.write("/dav/home/rent.ics", &vtodo("rent", "Pay rent", "PRIORITY:1\r\n", "20261001T000000Z"));
+    server.write(
+        "/dav/home/receipt.ics",
+        &vtodo("receipt", "Find receipt", "RELATED-TO:rent\r\n", "20261001T000000Z"),
+    );
+    MockServer(RefCell::new(server))
+}
+
+/// A database with a "Reports" category holding one task
+fn database() -> Mutex<Database> {
+    let db = Database::in_memory().unwrap();
+    let work = Category::new(CreateCategoryRequest {
+        name: "Reports".to_string(),
+        color: "#3b82f6".to_string(),
+        icon: None,
+    });
+    CategoryRepository::new(&db.connection).create(&work).unwrap();
+    let task = Task::new(CreateTaskRequest {
+        title: "Write report".to_string(),
+        description: None,
+        priority: None,
+        due_date: None,
+        category_id: Some(work.id),
+        tags: None,
+        parent_id: None,
+        recurrence: None,
+    })
+    .unwrap();
+    TaskRepository::new(&db.connection).create(&task).unwrap();
+    Mutex::new(db)
+}
+
+fn sync(db: &Mutex<Database>, server: &MockServer, policy: ConflictPolicy) -> CalDavSyncReport {
+    let account = CalDavAccount {
+        url: format!("{}{}", ORIGIN, HOME),
+        username: String::new(),
+        password: String::new(),
+        conflict_policy: policy,
+    };
+    sync_account(db, server, &account).unwrap()
+}
+
+fn task(db: &Mutex<Database>, title: &str) -> Option<Task> {
+    let db = db.lock().unwrap();
+    TaskRepository::new(&db.connection).get_all(None).unwrap().into_iter().find(|t| t.title == title)
+}
+
+fn update(db: &Mutex<Database>, task: &Task) {
+    TaskRepository::new(&db.lock().unwrap().connection).update(task).unwrap();
+}
+
+fn summary(server: &MockServer, path: &str) -> Option<String> {
+    let server = server.0.borrow();
+    let data = &server.item(path)?.data;
+    data.lines().find_map(|line| line.strip_prefix("SUMMARY:")).map(str::to_string)
+}
+
+/// Path of the item a task was uploaded as
+fn uploaded(server: &MockServer, task: &Task) -> String {
+    let server = server.0.borrow();
+    let (calendar, _) = server.calendars.iter().find(|(_, c)| c.name == "Reports").expect("no Reports calendar");
+    format!("{}{}.ics", calendar, task.id)
+}
+
+fn writes(server: &MockServer) -> Vec<String> {
+    let server = server.0.borrow();
+    server
+        .requests
+        .iter()
+        .filter(|(method, _, _)| matches!(method.as_str(), "PUT" | "DELETE" | "MKCALENDAR"))
+        .map(|(method, path, _)| format!("{} {}", method, path))
+        .collect()
+}
+
+#[test]
+fn first_sync_pulls_and_pushes() {
+    let db = database();
+    let server = server();
+    let categories = CategoryRepository::new(&db.lock().unwrap().connection).get_all().unwrap();
+
+    // Every category gets a calendar, and the calendar a category
+    let report = sync(&db, &server, ConflictPolicy::NewestWins);
+    assert_eq!(report.summary.tasks_created, 2);
+    assert_eq!(report.calendars_created, categories.len());
+    assert_eq!(report.items_created, 1);
+    assert!(report.changed_app());
+
+    let rent = task(&db, "Pay rent").unwrap();
+    let home = CategoryRepository::new(&db.lock().unwrap().connection)
+        .get_all()
+        .unwrap()
+        .into_iter()
+        .find(|c| c.name == "Home")
+        .unwrap();
+    assert_eq!(rent.category_id, Some(home.id));
+    assert_eq!(task(&db, "Find receipt").unwrap().parent_id, Some(rent.id));
+
+    let report_task = task(&db, "Write report").unwrap();
+    assert_eq!(summary(&server, &uploaded(&server, &report_task)).as_deref(), Some("Write report"));
+
+    // Nothing changed since, so nothing is written
+    server.0.borrow_mut().requests.clear();
+    let report = sync(&db, &server, ConflictPolicy::NewestWins);
+    assert!(!report.changed_app());
+    assert!(writes(&server).is_empty(), "{:?}", writes(&server));
+}
+
+#[test]
+fn sync_token_fetches_only_changes() {
+    let db = database();
+    let server = server();
+    sync(&db, &server, ConflictPolicy::NewestWins);
+
+    server
+        .0
+        .borrow_mut()
+        .write("/dav/home/rent.ics", &vtodo("rent", "Pay rent early", "", "20261005T000000Z"));
+    server.0.borrow_mut().requests.clear();
+    let report = sync(&db, &server, ConflictPolicy::NewestWins);
+    assert_eq!(report.summary.tasks_updated, 1);
+    assert!(task(&db, "Pay rent early").is_some());
+
+    let requests = &server.0.borrow().requests;
+    let (_, _, body) = requests
+        .iter()
+        .find(|(method, path, body)| method == "REPORT" && path == "/dav/home/" && body.contains("sync-collection"))
+        .unwrap();
+    assert!(!elements(body, "<d:sync-token>", "</d:sync-token>")[0].is_empty());
+    let (_, _, body) = requests
+        .iter()
+        .find(|(method, path, body)| method == "REPORT" && path == "/dav/home/" && body.contains("calendar-multiget"))
+        .unwrap();
+    assert_eq!(elements(body, "<d:href>", "</d:href>"), ["/dav/home/rent.ics"]);
+}
+
+/// Edit "Write report" in Pluto while another client changes it on the
+/// server between our read and our write, so the upload gets a 412
+fn conflict_on_write(policy: ConflictPolicy, server_modified: &str) -> (Mutex<Database>, MockServer, CalDavSyncReport) {
+    let db = database();
+    let server = server();
+    sync(&db, &server, policy);
+
+    let mut local = task(&db, "Write report").unwrap();
+    let path = uploaded(&server, &local);
+    local.title = "Write final report".to_string();
+    local.updated_at = NaiveDate::from_ymd_opt(2026, 10, 10).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    update(&db, &local);
+    let edit = vtodo(&local.id, "Write draft report", "", server_modified);
+    server.0.borrow_mut().edit_before_put = Some((path, edit));
+
+    let report = sync(&db, &server, policy);
+    assert_eq!(report.conflicts.len(), 1, "{:?}", report.conflicts);
+    (db, server, report)
+}
+
+fn assert_settled(db: &Mutex<Database>, server: &MockServer, title: &str, policy: ConflictPolicy) {
+    let local = task(db, title).unwrap();
+    assert_eq!(summary(server, &uploaded(server, &local)).as_deref(), Some(title));
+
+    server.0.borrow_mut().requests.clear();
+    sync(db, server, policy);
+    assert!(writes(server).is_empty(), "{:?}", writes(server));
+}
+
+#[test]
+fn conflict_on_write_server_wins() {
+    let (db, server, _) = conflict_on_write(ConflictPolicy::ServerWins, "20261001T000000Z");
+    assert_settled(&db, &server, "Write draft report", ConflictPolicy::ServerWins);
+}
+
+#[test]
+fn conflict_on_write_local_wins() {
+    let (db, server, _) = conflict_on_write(ConflictPolicy::LocalWins, "20261020T000000Z");
+    assert_settled(&db, &server, "Write final report", ConflictPolicy::LocalWins);
+}
+
+#[test]
+fn conflict_on_write_newest_wins() {
+    let (db, server, _) = conflict_on_write(ConflictPolicy::NewestWins, "20261020T000000Z");
+    assert_settled(&db, &server, "Write draft report", ConflictPolicy::NewestWins);
+
+    let (db, server, _) = conflict_on_write(ConflictPolicy::NewestWins, "20261001T000000Z");
+    assert_settled(&db, &server, "Write final report", ConflictPolicy::NewestWins);
+}
+
+#[test]
+fn server_side_delete() {
+    let db = database();
+    let server = server();
+    sync(&db, &server, ConflictPolicy::NewestWins);
+    let rent = task(&db, "Pay rent").unwrap();
+
+    server.0.borrow_mut().remove("/dav/home/rent.ics");
+    let report = sync(&db, &server, ConflictPolicy::NewestWins);
+    assert_eq!(report.tasks_deleted, 1);
+    assert!(task(&db, "Pay rent").is_none());
+    // Its subtask moves up a level rather than going with it
+    assert_eq!(task(&db, "Find receipt").unwrap().parent_id, None);
+
+    // Undoing the sync brings both back as they were
+    Journal::new(&db.lock().unwrap().connection).undo().unwrap();
+    assert!(task(&db, "Pay rent").is_some());
+    assert_eq!(task(&db, "Find receipt").unwrap().parent_id, Some(rent.id));
+}
+
+#[test]
+fn server_side_delete_of_edited_task() {
+    let db = database();
+    let server = server();
+    sync(&db, &server, ConflictPolicy::NewestWins);
+
+    let mut rent = task(&db, "Pay rent").unwrap();
+    rent.title = "Pay rent and bills".to_string();
+    update(&db, &rent);
+    server.0.borrow_mut().remove("/dav/home/rent.ics");
+
+    // An edit beats a deletion
+    let report = sync(&db, &server, ConflictPolicy::NewestWins);
+    assert_eq!(report.tasks_deleted, 0);
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(summary(&server, "/dav/home/rent.ics").as_deref(), Some("Pay rent and bills"));
+}
+
+#[test]
+fn foreign_uid_is_encoded_in_the_item_path() {
+    let db = database();
+    let server = server();
+    server.0.borrow_mut().write("/dav/home/water.ics", &vtodo("water/plants 1", "Water plants", "", "20261001T000000Z"));
+    sync(&db, &server, ConflictPolicy::NewestWins);
+
+    // Moving it to another calendar uploads it there under its own UID
+    let mut water = task(&db, "Water plants").unwrap();
+    water.category_id = task(&db, "Write report").unwrap().category_id;
+    update(&db, &water);
+    sync(&db, &server, ConflictPolicy::NewestWins);
+
+    let path = uploaded(&server, &water).replace(&water.id, "water%2Fplants%201");
+    assert_eq!(summary(&server, &path).as_deref(), Some("Water plants"));
+}
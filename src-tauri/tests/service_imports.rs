@@ -0,0 +1,198 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use pluto_todo_lib::database::{Database, TaskRepository};
+use pluto_todo_lib::interop::google_tasks::parse_google_tasks;
+use pluto_todo_lib::interop::microsoft_todo::parse_microsoft_todo;
+use pluto_todo_lib::interop::services::{import_service_tasks, ServiceImportReport, ServiceTask};
+use pluto_todo_lib::interop::todoist::{parse_todoist_json, read_todoist_path};
+use pluto_todo_lib::models::{Priority, ReminderOffset};
+use std::path::Path;
+
+fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+}
+
+fn now() -> NaiveDateTime {
+    at(2026, 10, 17, 12, 0)
+}
+
+fn find<'a>(tasks: &'a [ServiceTask], title: &str) -> &'a ServiceTask {
+    tasks.iter().find(|t| t.title == title).unwrap_or_else(|| panic!("no task '{}'", title))
+}
+
+fn skipped(report: &ServiceImportReport) -> Vec<(&str, &str)> {
+    report.skipped.iter().map(|s| (s.title.as_str(), s.reason.as_str())).collect()
+}
+
+fn lossy(report: &ServiceImportReport) -> Vec<(&str, usize)> {
+    report.lossy.iter().map(|l| (l.field.as_str(), l.count)).collect()
+}
+
+#[test]
+fn todoist_json_backup() {
+    let mut report = ServiceImportReport::new("Todoist");
+    let tasks = parse_todoist_json(include_str!("fixtures/todoist.json"), now(), &mut report).unwrap();
+    assert_eq!(tasks.len(), 3);
+
+    let milk = find(&tasks, "Buy milk");
+    assert_eq!(milk.source_id, "6780000001");
+    assert_eq!(milk.list.as_deref(), Some("Home"));
+    assert_eq!(milk.tags, ["shop", "errand"]);
+    assert_eq!(milk.description.as_deref(), Some("Semi-skimmed\n\nFrom the corner shop"));
+    assert_eq!(milk.priority, Priority::High);
+    assert_eq!(milk.due_date, Some(at(2026, 10, 20, 0, 0)));
+    assert_eq!(milk.created_at, Some(at(2026, 10, 1, 8, 30)));
+    assert!(!milk.completed);
+
+    let plants = find(&tasks, "Water the plants");
+    assert_eq!(plants.list.as_deref(), Some("Garden"));
+    assert_eq!(plants.priority, Priority::Medium);
+    assert_eq!(plants.due_date, Some(at(2026, 10, 19, 0, 0)));
+    assert_eq!(plants.recurrence.as_ref().map(|r| r.to_string()).as_deref(), Some("FREQ=WEEKLY;BYDAY=MO"));
+
+    let oat = find(&tasks, "Oat milk");
+    assert_eq!(oat.parent.as_deref(), Some("6780000001"));
+    assert_eq!(oat.priority, Priority::Low);
+    assert!(oat.completed);
+
+    assert_eq!(
+        skipped(&report),
+        [
+            ("Old chore", "Deleted in Todoist"),
+            ("Orphaned comment", "A comment on a project or a task missing from the export"),
+        ]
+    );
+    assert_eq!(lossy(&report), [("project hierarchy", 1), ("reminder", 1), ("section", 1)]);
+}
+
+#[test]
+fn todoist_csv_project() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/Trip [2203306150].csv");
+    let mut report = ServiceImportReport::new("Todoist");
+    let tasks = read_todoist_path(&path, now(), &mut report).unwrap();
+    assert_eq!(tasks.len(), 3);
+    assert!(tasks.iter().all(|t| t.list.as_deref() == Some("Trip")));
+
+    let trip = find(&tasks, "Plan the trip");
+    assert_eq!(trip.tags, ["travel"]);
+    assert_eq!(trip.description.as_deref(), Some("Book by Friday\n\nAsk about the visa"));
+    assert_eq!(trip.priority, Priority::High);
+    assert_eq!(trip.due_date, Some(at(2026, 11, 2, 0, 0)));
+
+    let pack = find(&tasks, "Pack bags");
+    assert_eq!(pack.parent.as_deref(), Some(trip.source_id.as_str()));
+    assert_eq!(pack.priority, Priority::Medium);
+
+    // The section above it ends the nesting
+    let postcards = find(&tasks, "Send postcards");
+    assert_eq!(postcards.parent, None);
+    assert_eq!(postcards.priority, Priority::Low);
+
+    assert!(report.skipped.is_empty());
+    assert_eq!(lossy(&report), [("section", 1), ("indent", 1), ("assignee", 1), ("duration", 1)]);
+}
+
+#[test]
+fn todoist_csv_without_columns_is_rejected() {
+    let dir = std::env::temp_dir().join(format!("pluto-todoist-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("Notes.csv");
+    std::fs::write(&path, "title,notes\nSomething,else\n").unwrap();
+
+    let mut report = ServiceImportReport::new("Todoist");
+    let result = read_todoist_path(&path, now(), &mut report);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(result.is_err());
+}
+
+#[test]
+fn microsoft_todo_lists() {
+    let mut report = ServiceImportReport::new("Microsoft To Do");
+    let tasks = parse_microsoft_todo(include_str!("fixtures/microsoft_todo.json"), &mut report).unwrap();
+    assert_eq!(tasks.len(), 4);
+    assert!(tasks.iter().all(|t| t.list.as_deref() == Some("Groceries")));
+
+    let shop = find(&tasks, "Weekly shop");
+    assert_eq!(shop.description.as_deref(), Some("Bread\nEggs & cheese"));
+    assert_eq!(shop.priority, Priority::High);
+    assert_eq!(shop.due_date, Some(at(2026, 10, 22, 0, 0)));
+    assert_eq!(shop.tags, ["Food"]);
+    assert_eq!(shop.reminders, [ReminderOffset::OnDueDay { hour: 9, minute: 0 }]);
+    assert_eq!(shop.recurrence.as_ref().map(|r| r.to_string()).as_deref(), Some("FREQ=WEEKLY;BYDAY=TH"));
+    assert_eq!(shop.created_at, Some(at(2026, 10, 1, 7, 0)));
+
+    // Checklist steps become subtasks
+    let fridge = find(&tasks, "Check the fridge");
+    assert_eq!(fridge.source_id, "Step1");
+    assert_eq!(fridge.parent.as_deref(), Some("AAMkTask1"));
+    assert!(fridge.completed);
+    let list = find(&tasks, "Make a list");
+    assert_eq!(list.source_id, "AAMkTask1:step1");
+    assert_eq!(list.parent.as_deref(), Some("AAMkTask1"));
+    assert!(!list.completed);
+
+    let bottles = find(&tasks, "Return bottles");
+    assert!(!bottles.completed);
+    assert_eq!(bottles.priority, Priority::Low);
+    assert!(bottles.reminders.is_empty());
+    assert_eq!(bottles.recurrence.as_ref().map(|r| r.to_string()).as_deref(), Some("FREQ=MONTHLY;COUNT=3"));
+
+    assert!(report.skipped.is_empty());
+    assert_eq!(
+        lossy(&report),
+        [
+            ("formatting", 1),
+            ("status", 1),
+            ("recurrence", 1),
+            ("reminder", 1),
+            ("linked resource", 1),
+            ("attachment", 1),
+        ]
+    );
+}
+
+#[test]
+fn google_takeout_tasks() {
+    let mut report = ServiceImportReport::new("Google Tasks");
+    let tasks = parse_google_tasks(include_str!("fixtures/google_tasks.json"), &mut report).unwrap();
+    assert_eq!(tasks.len(), 3);
+    assert!(tasks.iter().all(|t| t.list.as_deref() == Some("My Tasks")));
+
+    let passport = find(&tasks, "Renew passport");
+    assert_eq!(passport.source_id, "gt-1");
+    assert_eq!(
+        passport.description.as_deref(),
+        Some("Photos first\nReminder email: https://mail.example.com/1")
+    );
+    assert_eq!(passport.due_date, Some(at(2026, 11, 5, 0, 0)));
+    assert_eq!(passport.created_at, Some(at(2026, 10, 2, 10, 15)));
+    assert!(!passport.completed);
+
+    let booth = find(&tasks, "Book photo booth");
+    assert_eq!(booth.parent.as_deref(), Some("gt-1"));
+    assert!(booth.completed);
+
+    assert_eq!(skipped(&report), [("Cancelled plan", "Deleted in Google Tasks")]);
+    assert_eq!(lossy(&report), [("assignment", 1)]);
+}
+
+#[test]
+fn parent_cycle_is_broken_on_import() {
+    let db = Database::in_memory().unwrap();
+    let item = |id: &str, parent: &str| ServiceTask {
+        source_id: id.to_string(),
+        parent: Some(parent.to_string()),
+        title: format!("Task {}", id),
+        ..Default::default()
+    };
+
+    let mut report = ServiceImportReport::new("Todoist");
+    import_service_tasks(&db.connection, vec![item("a", "b"), item("b", "a")], &mut report).unwrap();
+    assert_eq!(report.summary.tasks_created, 2);
+    assert_eq!(report.summary.warnings.len(), 1);
+
+    let tasks = TaskRepository::new(&db.connection).get_all(None).unwrap();
+    let a = tasks.iter().find(|t| t.title == "Task a").unwrap();
+    let b = tasks.iter().find(|t| t.title == "Task b").unwrap();
+    assert_eq!(a.parent_id, None);
+    assert_eq!(b.parent_id.as_deref(), Some(a.id.as_str()));
+}
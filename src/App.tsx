@@ -1,4 +1,5 @@
 import { useState, useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { Sidebar } from './components/Sidebar';
 import { TaskList } from './components/TaskList';
 import { TaskForm } from './components/TaskForm';
@@ -92,9 +93,10 @@ function App() {
     updateTask, 
     tasks,
     sortConfig,
-    setSortConfig
+    setSortConfig,
+    loadTasks
   } = useTaskStore();
-  const { deleteCategory } = useCategoryStore();
+  const { deleteCategory, loadCategories } = useCategoryStore();
 
   useEffect(() => {
     // Check for saved dark mode preference
@@ -105,6 +107,23 @@ function App() {
     }
   }, []);
 
+  // Reload after a background sync or a local API request brings in changes
+  useEffect(() => {
+    const reload = () => {
+      loadTasks();
+      loadCategories();
+    };
+    const unlisteners = [
+      listen('todotxt-synced', reload),
+      listen('markdown-synced', reload),
+      listen('caldav-synced', reload),
+      listen('api-changed', reload),
+    ];
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((stop) => stop()));
+    };
+  }, [loadTasks, loadCategories]);
+
   // Close bulk actions menu when no tasks are selected
   useEffect(() => {
     if (selectedTasks.size === 0) {
@@ -50,7 +50,7 @@ export const Sidebar: React.FC<SidebarProps> = ({
   onCollapse
 }) => {
   const { categories, loadCategories } = useCategoryStore();
-  const { setFilter, filter, allTasks } = useTaskStore();
+  const { setFilter, filter, counts } = useTaskStore();
   const { updateInfo, isChecking, checkForUpdates, error } = useUpdateChecker();
   const [showUpdateModal, setShowUpdateModal] = useState(false);
   const [showUpdateResult, setShowUpdateResult] = useState(false);
@@ -64,33 +64,25 @@ export const Sidebar: React.FC<SidebarProps> = ({
     loadCategories();
   }, [loadCategories]);
 
-  // Calculate task counts for different filters
+  // Task counts for the different filters, as counted by the backend
   const getTaskCount = (filterType: string, categoryId?: string) => {
-    const now = new Date();
-    const today = new Date().toISOString().split('T')[0];
+    if (!counts) return 0;
     
     switch (filterType) {
       case 'all':
-        return allTasks.length;
+        return counts.all;
       case 'today':
-        return allTasks.filter(task => {
-          if (!task.due_date) return false;
-          const taskDate = new Date(task.due_date).toISOString().split('T')[0];
-          return taskDate === today;
-        }).length;
+        return counts.today;
       case 'overdue':
-        return allTasks.filter(task => {
-          if (!task.due_date || task.completed) return false;
-          return new Date(task.due_date) < now;
-        }).length;
+        return counts.overdue;
       case 'completed':
-        return allTasks.filter(task => task.completed).length;
+        return counts.completed;
       case 'pending':
-        return allTasks.filter(task => !task.completed).length;
+        return counts.pending;
       case 'category':
-        return allTasks.filter(task => task.category_id === categoryId).length;
+        return (categoryId && counts.by_category[categoryId]) || 0;
       case 'no_category':
-        return allTasks.filter(task => !task.category_id).length;
+        return counts.no_category;
       default:
         return 0;
     }
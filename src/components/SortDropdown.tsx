@@ -14,6 +14,7 @@ export const SortDropdown: React.FC = () => {
     { field: SortField.UpdatedAt, label: 'Updated Date', shortLabel: 'Updated' },
     { field: SortField.Priority, label: 'Priority', shortLabel: 'Priority' },
     { field: SortField.Completed, label: 'Status', shortLabel: 'Status' },
+    { field: SortField.Position, label: 'Manual Order', shortLabel: 'Manual' },
   ];
 
   const handleSortChange = (field: SortField) => {
@@ -2,7 +2,7 @@ import React, { useState, useEffect } from 'react';
 import { Card, CardContent } from './ui/Card';
 import { Button } from './ui/Button';
 import { Check, Tag, Calendar, Edit2, Trash2, Plus, ChevronDown, ChevronRight } from 'lucide-react';
-import { Task as TaskType, TaskProgress } from '../types';
+import { Task as TaskType } from '../types';
 import { useTaskStore } from '../stores/taskStore';
 import { SubtaskCompletionModal } from './SubtaskCompletionModal';
 import { DeleteTaskOptionsModal } from './DeleteTaskOptionsModal';
@@ -44,11 +44,11 @@ export const SubtaskItem: React.FC<SubtaskItemProps> = ({
     deleteTaskAndPromoteSubtasks,
     expandedTasks, 
     toggleTaskExpansion, 
-    calculateTaskProgress,
+    progress: progressById,
     tasks // Get all tasks from store to find subtasks
   } = useTaskStore();
   
-  const [progress, setProgress] = useState<TaskProgress | null>(null);
+  const progress = progressById[task.id] ?? null; // Loaded for the whole list by TaskList
   const [loadingSubtasks] = useState(false); // Keep for loading state display
   const [showSubtaskModal, setShowSubtaskModal] = useState(false);
   const [incompleteSubtasks, setIncompleteSubtasks] = useState<TaskType[]>([]);
@@ -72,37 +72,12 @@ export const SubtaskItem: React.FC<SubtaskItemProps> = ({
   // Check if this task has subtasks - either from progress data or from direct count
   const hasSubtasks = (progress?.has_subtasks || subtasks.length > 0);
 
-  // Load progress data
-  useEffect(() => {
-    loadProgressData();
-  }, [task.id]);
-
-  // Reload progress when subtasks change - but only for tasks that have subtasks
-  useEffect(() => {
-    // Only recalculate if this task might have subtasks
-    if (subtasks.length > 0) {
-      console.log(`Recalculating progress for task ${task.id} due to subtask changes`);
-      loadProgressData();
-    }
-  }, [subtasks.map(t => `${t.id}-${t.completed}`).join(',')]); // Only react to subtask completion changes
-
   // Debug: Log when task completion status changes
   useEffect(() => {
     console.log(`Task ${task.id} completion status:`, task.completed);
     console.log(`Task ${task.id} progress:`, progress);
   }, [task.completed, task.id, progress]);
 
-  const loadProgressData = async () => {
-    try {
-      console.log(`Loading progress for task ${task.id}`);
-      const progressData = await calculateTaskProgress(task.id);
-      console.log(`Progress data for task ${task.id}:`, progressData);
-      setProgress(progressData);
-    } catch (error) {
-      console.error('Failed to load progress:', error);
-    }
-  };
-
   const handleToggleCompletion = async () => {
     try {
       // Check if this task is being marked as complete and has incomplete subtasks
@@ -119,9 +94,6 @@ export const SubtaskItem: React.FC<SubtaskItemProps> = ({
       // If no incomplete subtasks or task is being marked as incomplete, proceed normally
       await toggleTaskCompletion(task.id);
       console.log('Task completion toggled for task:', task.id);
-      
-      // Reload progress data for this task
-      await loadProgressData();
     } catch (error) {
       console.error('Failed to toggle task completion:', error);
     }
@@ -136,9 +108,6 @@ export const SubtaskItem: React.FC<SubtaskItemProps> = ({
         // Just mark the parent task as done, leave subtasks unchanged
         await toggleTaskCompletion(task.id);
       }
-      
-      // Reload progress data
-      await loadProgressData();
     } catch (error) {
       console.error('Failed to complete task with subtasks:', error);
     }
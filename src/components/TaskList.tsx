@@ -27,7 +27,7 @@ export const TaskList: React.FC<TaskListProps> = ({
   selectedTasks = new Set(), 
   onToggleTaskSelect 
 }) => {
-  const { tasks, loading, error, loadTasks, filter, setViewMode } = useTaskStore();
+  const { tasks, loading, error, loadTasks, loadTasksProgress, filter, setViewMode, nextCursor, loadingMore, loadMoreTasks } = useTaskStore();
   const [localViewMode, setLocalViewMode] = useState<ViewMode>('hierarchy');
   const [selectedTask, setSelectedTask] = useState<Task | null>(null);
   const [isModalOpen, setIsModalOpen] = useState(false);
@@ -36,6 +36,11 @@ export const TaskList: React.FC<TaskListProps> = ({
     loadTasks();
   }, [loadTasks, filter]); // React to filter changes
 
+  // Progress for every task on screen, in one call rather than one per row
+  useEffect(() => {
+    loadTasksProgress(tasks.map(t => t.id));
+  }, [tasks, loadTasksProgress]);
+
   // Set initial view mode in store
   useEffect(() => {
     setViewMode('hierarchical');
@@ -276,6 +281,16 @@ export const TaskList: React.FC<TaskListProps> = ({
         />
       )}
 
+      {/* Further pages are only fetched when asked for */}
+      {nextCursor && (
+        <div className="flex justify-center">
+          <Button variant="outline" size="sm" onClick={() => loadMoreTasks()} disabled={loadingMore}>
+            {loadingMore && <Loader2 className="w-3 h-3 mr-1 animate-spin" />}
+            Load more
+          </Button>
+        </div>
+      )}
+
       {/* Task Detail Modal */}
       <TaskDetailModal
         task={selectedTask}
@@ -1,12 +1,16 @@
 import { create } from 'zustand';
-import { Task, TaskFilter, CreateTaskRequest, UpdateTaskRequest, SortConfig, SortField, SortOrder, Priority, TaskProgress, TaskHierarchy } from '../types';
+import { Task, TaskFilter, CreateTaskRequest, UpdateTaskRequest, SortConfig, SortField, SortOrder, TaskProgress, TaskHierarchy, ToggleCompletionResult, TaskSearchResult, FilterQueryError, QuickAddPreview, QuickAddResult, TaskPage, TaskCounts, BulkResult } from '../types';
 import { invoke } from '@tauri-apps/api/core';
 
 interface TaskStore {
   tasks: Task[];
-  allTasks: Task[]; // Unfiltered tasks for count calculations
+  counts: TaskCounts | null; // Task counts for the sidebar views
+  nextCursor: string | null; // Where the next page of `tasks` starts, if there is one
+  loadingMore: boolean;
   loading: boolean;
   error: string | null;
+  warning: string | null; // Shown after an action that succeeded with a caveat
+  progress: Record<string, TaskProgress>; // Subtask progress by task ID
   filter: TaskFilter;
   sortConfig: SortConfig;
   expandedTasks: Set<string>; // Track expanded tasks for hierarchical view
@@ -14,31 +18,38 @@ interface TaskStore {
   
   // Actions
   setTasks: (tasks: Task[]) => void;
-  setAllTasks: (tasks: Task[]) => void;
   setLoading: (loading: boolean) => void;
   setError: (error: string | null) => void;
+  setWarning: (warning: string | null) => void;
   setFilter: (filter: TaskFilter) => void;
   setSortConfig: (sortConfig: SortConfig) => void;
   setViewMode: (mode: 'flat' | 'hierarchical') => void;
   toggleTaskExpansion: (taskId: string) => void;
-  sortTasks: (tasks: Task[]) => Task[];
   
   // Async actions
   loadTasks: () => Promise<void>;
+  loadMoreTasks: () => Promise<void>;
+  loadTaskCounts: () => Promise<void>;
   createTask: (task: CreateTaskRequest) => Promise<Task>;
   updateTask: (id: string, updates: UpdateTaskRequest) => Promise<Task>;
+  moveTask: (id: string, parentId: string | null, anchor?: { beforeId?: string; afterId?: string }) => Promise<Task>;
   deleteTask: (id: string) => Promise<void>;
   deleteTaskWithSubtasks: (id: string) => Promise<void>;
   deleteTaskAndPromoteSubtasks: (id: string) => Promise<void>;
   checkTaskHasSubtasks: (id: string) => Promise<boolean>;
   toggleTaskCompletion: (id: string) => Promise<Task>;
-  searchTasks: (query: string) => Promise<Task[]>;
+  searchTasks: (query: string) => Promise<TaskSearchResult[]>;
+  queryTasks: (query: string, filter?: TaskFilter) => Promise<Task[]>;
+  quickAddTask: (input: string) => Promise<Task>;
+  previewQuickAdd: (input: string) => Promise<QuickAddPreview>;
+  validateFilterQuery: (query: string) => Promise<FilterQueryError | null>;
   
   // New subtask methods
   loadSubtasks: (parentId: string) => Promise<Task[]>;
   loadTaskHierarchy: (rootId?: string) => Promise<Task[]>;
   loadTaskWithSubtasks: (id: string) => Promise<Task[]>;
   calculateTaskProgress: (id: string) => Promise<TaskProgress>;
+  loadTasksProgress: (ids: string[]) => Promise<void>;
   buildTaskHierarchy: (tasks: Task[]) => TaskHierarchy[];
   flattenHierarchy: (hierarchy: TaskHierarchy[]) => Task[];
   getIncompleteSubtasks: (parentId: string) => Promise<Task[]>;
@@ -53,23 +64,46 @@ interface TaskStore {
   bulkMarkTasksCompleted: (ids: string[], completed: boolean) => Promise<void>;
 }
 
+// The warning to show after a bulk action that some tasks did not go through
+const bulkFailureWarning = (verb: string, failed: number): string | null =>
+  failed === 0 ? null : `${failed} ${failed === 1 ? 'task' : 'tasks'} could not be ${verb}`;
+
+// How many tasks to ask the backend for at a time; more are loaded on request
+const PAGE_SIZE = 200;
+
+// The filter to send to the backend; only pass it if it has meaningful values
+const activeFilter = (filter: TaskFilter): TaskFilter | null => {
+  const hasFilter = filter && (
+    filter.completed !== undefined ||
+    filter.priority !== undefined ||
+    filter.category_id !== undefined ||
+    filter.parent_id !== undefined ||
+    filter.search_query !== undefined ||
+    filter.due_before !== undefined ||
+    filter.due_after !== undefined ||
+    filter.no_category !== undefined
+  );
+  return hasFilter ? filter : null;
+};
+
 export const useTaskStore = create<TaskStore>((set, get) => ({
   tasks: [],
-  allTasks: [],
+  counts: null,
+  nextCursor: null,
+  loadingMore: false,
   loading: false,
   error: null,
+  warning: null,
+  progress: {},
   filter: {},
   sortConfig: { field: SortField.CreatedAt, order: SortOrder.Desc },
   expandedTasks: new Set<string>(),
   viewMode: 'flat',
 
-  setTasks: (tasks) => {
-    const sortedTasks = get().sortTasks(tasks);
-    set({ tasks: sortedTasks });
-  },
-  setAllTasks: (allTasks) => set({ allTasks }),
+  setTasks: (tasks) => set({ tasks }),
   setLoading: (loading) => set({ loading }),
   setError: (error) => set({ error }),
+  setWarning: (warning) => set({ warning }),
   setFilter: (filter) => {
     set({ filter });
     // Automatically reload tasks when filter changes
@@ -77,10 +111,8 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
   },
   setSortConfig: (sortConfig) => {
     set({ sortConfig });
-    // Re-sort existing tasks with new config
-    const currentTasks = get().tasks;
-    const sortedTasks = get().sortTasks(currentTasks);
-    set({ tasks: sortedTasks });
+    // The backend does the sorting, so reload in the new order
+    get().loadTasks();
   },
   setViewMode: (mode) => set({ viewMode: mode }),
   toggleTaskExpansion: (taskId) => {
@@ -95,76 +127,57 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
     });
   },
 
-  sortTasks: (tasks) => {
-    const { field, order } = get().sortConfig;
-    
-    return [...tasks].sort((a, b) => {
-      let comparison = 0;
-      
-      switch (field) {
-        case SortField.Title:
-          comparison = a.title.toLowerCase().localeCompare(b.title.toLowerCase());
-          break;
-        case SortField.DueDate:
-          // Handle null/undefined due dates - put them at the end
-          if (!a.due_date && !b.due_date) comparison = 0;
-          else if (!a.due_date) comparison = 1;
-          else if (!b.due_date) comparison = -1;
-          else comparison = new Date(a.due_date).getTime() - new Date(b.due_date).getTime();
-          break;
-        case SortField.CreatedAt:
-          comparison = new Date(a.created_at).getTime() - new Date(b.created_at).getTime();
-          break;
-        case SortField.UpdatedAt:
-          comparison = new Date(a.updated_at).getTime() - new Date(b.updated_at).getTime();
-          break;
-        case SortField.Priority:
-          const priorityOrder = { [Priority.High]: 3, [Priority.Medium]: 2, [Priority.Low]: 1 };
-          comparison = priorityOrder[a.priority] - priorityOrder[b.priority];
-          break;
-        case SortField.Completed:
-          comparison = Number(a.completed) - Number(b.completed);
-          break;
-        default:
-          comparison = 0;
-      }
-      
-      return order === SortOrder.Asc ? comparison : -comparison;
-    });
-  },
-
   loadTasks: async () => {
     try {
       set({ loading: true, error: null });
       const currentFilter = get().filter;
       
-      // Always load all tasks for count calculations
-      const allTasks = await invoke<Task[]>('get_tasks', { filter: null });
-      get().setAllTasks(allTasks);
-      
-      // Only pass filter if it has meaningful values
-      const hasFilter = currentFilter && (
-        currentFilter.completed !== undefined ||
-        currentFilter.priority !== undefined ||
-        currentFilter.category_id !== undefined ||
-        currentFilter.parent_id !== undefined ||
-        currentFilter.search_query !== undefined ||
-        currentFilter.due_before !== undefined ||
-        currentFilter.due_after !== undefined ||
-        currentFilter.no_category !== undefined
-      );
-      
-      const tasks = hasFilter 
-        ? await invoke<Task[]>('get_tasks', { filter: currentFilter })
-        : allTasks; // Use already loaded allTasks if no filter
+      // Counts come from the backend, so the sidebar does not need every task
+      get().loadTaskCounts();
+      
+      // Only the first page; the rest is fetched by loadMoreTasks
+      const page = await invoke<TaskPage>('get_tasks', {
+        filter: activeFilter(currentFilter),
+        sort: get().sortConfig,
+        limit: PAGE_SIZE,
+      });
         
-      get().setTasks(tasks); // Use setTasks to apply sorting
-      set({ loading: false });
+      get().setTasks(page.tasks);
+      set({ nextCursor: page.next_cursor ?? null, loading: false });
     } catch (error) {
       set({ error: error as string, loading: false });
     }
   },
 
+  loadMoreTasks: async () => {
+    const { nextCursor, loadingMore, filter, sortConfig } = get();
+    if (!nextCursor || loadingMore) return;
+    try {
+      set({ loadingMore: true });
+      const page = await invoke<TaskPage>('get_tasks', {
+        filter: activeFilter(filter),
+        sort: sortConfig,
+        limit: PAGE_SIZE,
+        cursor: nextCursor,
+      });
+      set(state => ({
+        tasks: [...state.tasks, ...page.tasks],
+        nextCursor: page.next_cursor ?? null,
+        loadingMore: false
+      }));
+    } catch (error) {
+      set({ error: error as string, loadingMore: false });
+    }
+  },
+
+  loadTaskCounts: async () => {
+    try {
+      set({ counts: await invoke<TaskCounts>('get_task_counts') });
+    } catch (error) {
+      set({ error: error as string });
+    }
+  },
+
   createTask: async (taskRequest) => {
     try {
       set({ loading: true, error: null });
@@ -191,12 +204,8 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
         });
       }
       
-      // Add new task and re-sort
-      const currentTasks = get().tasks;
-      const currentAllTasks = get().allTasks;
-      get().setTasks([newTask, ...currentTasks]);
-      get().setAllTasks([newTask, ...currentAllTasks]);
-      set({ loading: false });
+      // Reload so the new task lands where the backend sorts it
+      await get().loadTasks();
       return newTask;
     } catch (error) {
       set({ error: error as string, loading: false });
@@ -204,6 +213,39 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
     }
   },
 
+  quickAddTask: async (input) => {
+    try {
+      set({ loading: true, error: null });
+      const result = await invoke<QuickAddResult>('quick_add_task', { input });
+      await get().loadTasks();
+      return result.task;
+    } catch (error) {
+      set({ error: error as string, loading: false });
+      throw error;
+    }
+  },
+
+  previewQuickAdd: async (input) => {
+    return await invoke<QuickAddPreview>('preview_quick_add', { input });
+  },
+
+  moveTask: async (id, parentId, anchor = {}) => {
+    try {
+      const movedTask = await invoke<Task>('move_task', {
+        id,
+        parentId,
+        beforeId: anchor.beforeId ?? null,
+        afterId: anchor.afterId ?? null,
+      });
+      // Siblings may have been renumbered too
+      await get().loadTasks();
+      return movedTask;
+    } catch (error) {
+      set({ error: error as string });
+      throw error;
+    }
+  },
+
   updateTask: async (id, updates) => {
     try {
       set({ loading: true, error: null });
@@ -216,14 +258,8 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
       
       console.log('TaskStore: Received updated task from backend:', updatedTask);
       
-      // Update task and re-sort
-      const currentTasks = get().tasks;
-      const currentAllTasks = get().allTasks;
-      const updatedTasks = currentTasks.map(task => task.id === id ? updatedTask : task);
-      const updatedAllTasks = currentAllTasks.map(task => task.id === id ? updatedTask : task);
-      get().setTasks(updatedTasks);
-      get().setAllTasks(updatedAllTasks);
-      set({ loading: false });
+      // Reload so the task moves if the change affects its sort position
+      await get().loadTasks();
       return updatedTask;
     } catch (error) {
       set({ error: error as string, loading: false });
@@ -237,12 +273,9 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
       set({ loading: true, error: null });
       console.log('Invoking Tauri delete_task command...');
       await invoke('delete_task', { id });
-      console.log('Tauri command completed, updating local state...');
-      set(state => ({
-        tasks: state.tasks.filter(task => task.id !== id),
-        allTasks: state.allTasks.filter(task => task.id !== id),
-        loading: false
-      }));
+      console.log('Tauri command completed, reloading tasks...');
+      // Reload rather than drop the row locally, since its subtasks move up a level
+      await get().loadTasks();
       console.log('Local state updated');
     } catch (error) {
       console.error('Error in deleteTask:', error);
@@ -257,8 +290,7 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
       await invoke('delete_task_with_subtasks', { id });
       
       // Reload all tasks to get the updated state
-      const allTasks = await invoke<Task[]>('get_tasks', {});
-      set({ tasks: allTasks, allTasks: allTasks, loading: false });
+      await get().loadTasks();
     } catch (error) {
       set({ error: error as string, loading: false });
       throw error;
@@ -271,8 +303,7 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
       await invoke('delete_task_and_promote_subtasks', { id });
       
       // Reload all tasks to get the updated state
-      const allTasks = await invoke<Task[]>('get_tasks', {});
-      set({ tasks: allTasks, allTasks: allTasks, loading: false });
+      await get().loadTasks();
     } catch (error) {
       set({ error: error as string, loading: false });
       throw error;
@@ -290,11 +321,12 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
 
   toggleTaskCompletion: async (id) => {
     try {
-      const updatedTask = await invoke<Task>('toggle_task_completion', { id });
+      const { task: updatedTask, warning } = await invoke<ToggleCompletionResult>('toggle_task_completion', { id });
       set(state => ({
         tasks: state.tasks.map(task => task.id === id ? updatedTask : task),
-        allTasks: state.allTasks.map(task => task.id === id ? updatedTask : task)
+        warning: warning ?? null
       }));
+      get().loadTaskCounts();
       return updatedTask;
     } catch (error) {
       set({ error: error as string });
@@ -304,7 +336,17 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
 
   searchTasks: async (query) => {
     try {
-      const tasks = await invoke<Task[]>('search_tasks', { query });
+      const results = await invoke<TaskSearchResult[]>('search_tasks', { query });
+      return results;
+    } catch (error) {
+      set({ error: error as string });
+      throw error;
+    }
+  },
+
+  queryTasks: async (query, filter) => {
+    try {
+      const tasks = await invoke<Task[]>('query_tasks', { query, filter });
       return tasks;
     } catch (error) {
       set({ error: error as string });
@@ -312,24 +354,22 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
     }
   },
 
+  validateFilterQuery: async (query) => {
+    return await invoke<FilterQueryError | null>('validate_filter_query', { query });
+  },
+
   bulkDeleteTasks: async (ids) => {
     try {
       console.log('Bulk deleting tasks:', ids);
       set({ loading: true, error: null });
       
-      // Delete tasks one by one (we could optimize this with a bulk command later)
-      for (const id of ids) {
-        await invoke('delete_task', { id });
-      }
+      const { succeeded, failed } = await invoke<BulkResult>('bulk_delete_tasks', { ids });
       
-      // Update local state
-      set(state => ({
-        tasks: state.tasks.filter(task => !ids.includes(task.id)),
-        allTasks: state.allTasks.filter(task => !ids.includes(task.id)),
-        loading: false
-      }));
+      // Reload, since subtasks of deleted tasks move up a level
+      await get().loadTasks();
+      set({ warning: bulkFailureWarning('deleted', failed.length) });
       
-      console.log('Bulk delete completed');
+      console.log('Bulk delete completed:', succeeded.length, 'deleted,', failed.length, 'failed');
     } catch (error) {
       console.error('Bulk delete failed:', error);
       set({ error: error as string, loading: false });
@@ -377,9 +417,9 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
       // Update local state - remove all tasks that were deleted (parents + subtasks)
       set(state => ({
         tasks: state.tasks.filter(task => !tasksToDelete.has(task.id)),
-        allTasks: state.allTasks.filter(task => !tasksToDelete.has(task.id)),
         loading: false
       }));
+      get().loadTaskCounts();
       
       console.log('Bulk delete with subtasks completed');
     } catch (error) {
@@ -397,14 +437,7 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
       await invoke('bulk_delete_tasks_and_promote_subtasks', { ids });
       
       // Reload tasks to get the updated state with promoted subtasks
-      const updatedTasks = await invoke<Task[]>('get_tasks', { filter: get().filter });
-      const allTasks = await invoke<Task[]>('get_tasks', { filter: null });
-      
-      set({
-        tasks: updatedTasks,
-        allTasks: allTasks,
-        loading: false
-      });
+      await get().loadTasks();
       
       console.log('Bulk delete and promote subtasks completed');
     } catch (error) {
@@ -419,40 +452,13 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
       console.log('Bulk marking tasks as completed:', ids, completed);
       set({ loading: true, error: null });
       
-      // Update tasks one by one (we could optimize this with a bulk command later)
-      const updatedTasks: Task[] = [];
-      for (const id of ids) {
-        const task = get().tasks.find(t => t.id === id);
-        if (task) {
-          const updatedTask = await invoke<Task>('update_task', {
-            id,
-            title: task.title,
-            description: task.description,
-            completed,
-            priority: task.priority,
-            due_date: task.due_date,
-            category_id: task.category_id,
-            tags: task.tags,
-            parent_id: task.parent_id,
-          });
-          updatedTasks.push(updatedTask);
-        }
-      }
+      const { succeeded, failed } = await invoke<BulkResult>('bulk_set_tasks_completed', { ids, completed });
       
-      // Update local state
-      set(state => ({
-        tasks: state.tasks.map(task => {
-          const updated = updatedTasks.find(ut => ut.id === task.id);
-          return updated || task;
-        }),
-        allTasks: state.allTasks.map(task => {
-          const updated = updatedTasks.find(ut => ut.id === task.id);
-          return updated || task;
-        }),
-        loading: false
-      }));
+      // Reload, since completing a recurring task creates its next instance
+      await get().loadTasks();
+      set({ warning: bulkFailureWarning('updated', failed.length) });
       
-      console.log('Bulk mark completed finished');
+      console.log('Bulk mark completed finished:', succeeded.length, 'updated,', failed.length, 'failed');
     } catch (error) {
       console.error('Bulk mark completed failed:', error);
       set({ error: error as string, loading: false });
@@ -501,6 +507,17 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
     }
   },
 
+  loadTasksProgress: async (ids) => {
+    try {
+      const progress = ids.length > 0
+        ? await invoke<Record<string, TaskProgress>>('get_tasks_progress', { ids })
+        : {};
+      set({ progress });
+    } catch (error) {
+      set({ error: error as string });
+    }
+  },
+
   getIncompleteSubtasks: async (parentId: string) => {
     try {
       const incompleteSubtasks = await invoke<Task[]>('get_incomplete_subtasks', { parentId });
@@ -520,12 +537,9 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
         tasks: state.tasks.map(task => {
           const updated = updatedTasks.find(ut => ut.id === task.id);
           return updated || task;
-        }),
-        allTasks: state.allTasks.map(task => {
-          const updated = updatedTasks.find(ut => ut.id === task.id);
-          return updated || task;
         })
       }));
+      get().loadTaskCounts();
       
       return updatedTasks;
     } catch (error) {
@@ -542,11 +556,11 @@ export const useTaskStore = create<TaskStore>((set, get) => ({
       }
       
       // Then toggle the parent task
-      const updatedTask = await invoke<Task>('toggle_task_completion', { id: taskId });
+      const { task: updatedTask, warning } = await invoke<ToggleCompletionResult>('toggle_task_completion', { id: taskId });
       
       // Reload all tasks to get the updated state
-      const allTasks = await invoke<Task[]>('get_tasks', {});
-      set({ tasks: allTasks, allTasks: allTasks });
+      await get().loadTasks();
+      set({ warning: warning ?? null });
       
       return updatedTask;
     } catch (error) {
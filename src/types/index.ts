@@ -8,12 +8,16 @@ export interface Task {
   category_id?: string;
   tags: string[];
   parent_id?: string; // For subtasks
+  recurrence?: string; // RRULE text, e.g. "FREQ=WEEKLY;BYDAY=MO"
   created_at: string; // ISO string
   updated_at: string; // ISO string
+  deleted_at?: string; // ISO string, set while the task is in the trash
+  position: number; // Manual order among tasks with the same parent and category
   // Computed properties for UI
   subtasks?: Task[];
   progress?: TaskProgress;
   depth?: number; // For rendering nested structure
+  path?: string[]; // IDs from the top of a hierarchy listing down to this task
   isExpanded?: boolean; // For UI state
 }
 
@@ -24,6 +28,17 @@ export interface TaskProgress {
   has_subtasks: boolean;
 }
 
+export interface TaskDependencies {
+  blocked_by: Task[]; // Must be done before this task can start
+  blocking: Task[]; // Waiting on this task
+}
+
+export interface ToggleCompletionResult {
+  task: Task;
+  // Set when the task was completed while tasks it depends on are still open
+  warning?: string | null;
+}
+
 export interface TaskHierarchy {
   task: Task;
   children: TaskHierarchy[];
@@ -44,6 +59,7 @@ export interface CreateTaskRequest {
   category_id?: string;
   tags?: string[];
   parent_id?: string;
+  recurrence?: string; // RRULE text
 }
 
 export interface UpdateTaskRequest {
@@ -55,6 +71,7 @@ export interface UpdateTaskRequest {
   category_id?: string;
   tags?: string[];
   parent_id?: string;
+  recurrence?: string; // RRULE text, empty string stops recurring
 }
 
 export interface TaskFilter {
@@ -74,7 +91,8 @@ export enum SortField {
   CreatedAt = "created_at",
   UpdatedAt = "updated_at",
   Priority = "priority",
-  Completed = "completed"
+  Completed = "completed",
+  Position = "position"
 }
 
 export enum SortOrder {
@@ -87,6 +105,31 @@ export interface SortConfig {
   order: SortOrder;
 }
 
+export interface TaskPage {
+  tasks: Task[];
+  next_cursor?: string;
+}
+
+export interface TaskCounts {
+  all: number;
+  today: number;
+  overdue: number;
+  completed: number;
+  pending: number;
+  no_category: number;
+  by_category: Record<string, number>;
+}
+
+export interface BulkFailure {
+  id: string;
+  error: string;
+}
+
+export interface BulkResult {
+  succeeded: string[];
+  failed: BulkFailure[];
+}
+
 export interface Category {
   id: string;
   name: string;
@@ -106,3 +149,187 @@ export interface UpdateCategoryRequest {
   color?: string;
   icon?: string;
 }
+
+export type ReminderOffset =
+  | { type: "BeforeDue"; minutes: number }
+  | { type: "OnDueDay"; hour: number; minute: number }; // local time
+
+export interface Reminder {
+  id: string;
+  task_id: string;
+  offset: ReminderOffset;
+  fired_for_due?: string; // ISO string
+  created_at: string; // ISO string
+}
+
+export interface UndoState {
+  undo_label?: string; // label of the operation `undo` would revert
+  redo_label?: string; // label of the operation `redo` would re-apply
+}
+
+// Matched terms in `title_highlight` / `description_snippet` are wrapped in
+// "\u0002" ... "\u0003" markers; escape the text before rendering them.
+export interface TaskSearchResult {
+  task: Task;
+  score: number; // Higher is more relevant
+  title_highlight: string;
+  description_snippet?: string;
+}
+
+export type QuickAddField = 'priority' | 'tag' | 'category' | 'due_date' | 'due_time' | 'recurrence';
+
+export interface QuickAddMatch {
+  field: QuickAddField;
+  text: string;
+  start: number; // Character offsets into the input line
+  end: number;
+}
+
+// How a quick-add line such as "Pay rent every month on the 1st !high #finance" was parsed
+export interface QuickAddPreview {
+  title: string;
+  priority?: Priority;
+  tags: string[];
+  category_id?: string;
+  category_name?: string;
+  due_date?: string;
+  due_has_time: boolean;
+  recurrence?: string; // RRULE text
+  matches: QuickAddMatch[];
+  warnings: string[];
+}
+
+export interface QuickAddResult {
+  task: Task;
+  preview: QuickAddPreview;
+}
+
+// Parse error from a filter query such as `priority:high #client-a -done`
+export interface FilterQueryError {
+  message: string;
+  position: number; // Character offset into the query
+  length: number;
+}
+
+export interface ExportSummary {
+  tasks: number;
+  categories: number;
+}
+
+// 'merge' adds to existing data, 'replace' wipes it first
+export type ImportMode = 'merge' | 'replace';
+
+export interface ImportSummary {
+  tasks_created: number;
+  tasks_updated: number;
+  categories_created: number;
+  categories_matched: number; // Reused an existing category with the same ID or name
+  reminders_created: number;
+  dependencies_created: number;
+  remapped_ids: number; // Rows given a new ID because theirs was taken
+  task_ids: string[];
+  category_ids: string[];
+  warnings: string[];
+}
+
+// Zero-based CSV column feeding each task field; only title is required
+export interface CsvColumnMapping {
+  title?: number;
+  description?: number;
+  completed?: number;
+  priority?: number;
+  due_date?: number;
+  category?: number;
+  tags?: number;
+  id?: number; // The row's own ID in the file, used to resolve parent
+  parent?: number; // Parent ID or title
+  recurrence?: number;
+}
+
+export interface CsvPreview {
+  headers: string[];
+  sample_rows: string[][];
+  total_rows: number;
+  suggested_mapping: CsvColumnMapping;
+}
+
+export interface CsvRowError {
+  line: number; // Header is line 1
+  column?: string;
+  message: string;
+}
+
+export interface CsvImportReport {
+  dry_run: boolean;
+  total_rows: number;
+  valid_rows: number;
+  errors: CsvRowError[];
+  new_categories: string[];
+  summary: ImportSummary;
+}
+
+export interface TodoTxtSyncReport {
+  path: string;
+  summary: ImportSummary;
+  tasks_deleted: number;
+  lines_added: number;
+  lines_updated: number;
+  lines_removed: number;
+  conflicts: string[];
+}
+
+export interface MarkdownSyncReport {
+  path: string;
+  files_scanned: number;
+  files_written: number;
+  summary: ImportSummary;
+  tasks_deleted: number;
+  lines_updated: number;
+  lines_removed: number;
+  conflicts: string[];
+}
+
+export type ConflictPolicy = 'server_wins' | 'local_wins' | 'newest_wins';
+
+export interface CalDavAccount {
+  url: string;
+  username: string;
+  // Only sent when changing it; an empty password keeps the stored one
+  password?: string;
+  conflict_policy: ConflictPolicy;
+}
+
+export interface CalDavSyncReport {
+  summary: ImportSummary;
+  tasks_deleted: number;
+  calendars_created: number;
+  items_created: number;
+  items_updated: number;
+  items_deleted: number;
+  conflicts: string[];
+}
+
+export interface ApiSettings {
+  enabled: boolean;
+  port: number;
+  // Sent as `Authorization: Bearer <token>`; an empty token keeps the current one
+  token: string;
+}
+
+export interface SkippedItem {
+  title: string;
+  reason: string;
+}
+
+export interface LossyField {
+  field: string;
+  count: number;
+  note: string;
+}
+
+export interface ServiceImportReport {
+  source: string;
+  summary: ImportSummary;
+  skipped: SkippedItem[];
+  lossy: LossyField[];
+}